@@ -1,4 +1,6 @@
+use std::collections::HashMap;
 use std::fmt::{Debug, Display, Formatter};
+use std::hash::{Hash, Hasher};
 use std::str::FromStr;
 
 use lazy_static::lazy_static;
@@ -9,7 +11,9 @@ use inkayaku_core::constants::ColoredPiece;
 use inkayaku_core::constants::File;
 use inkayaku_core::constants::Piece;
 use inkayaku_core::constants::Square;
+use inkayaku_core::epd::{Epd, EpdParseError};
 use inkayaku_core::fen::{Fen, FenParseError};
+use inkayaku_uci::UciMove;
 
 use crate::{mask_and_shift_from_lowest_one_bit, opposite_color, piece_to_string, square_to_string};
 #[allow(clippy::wildcard_imports)]
@@ -20,9 +24,18 @@ use crate::board::precalculated::{BLACK_PAWN_NONMAGICS, KING_NONMAGICS, KNIGHT_N
 use crate::board::zobrist::Zobrist;
 
 pub mod constants;
+mod castling_rights;
+mod history;
+mod move_gen;
+mod perft;
 mod precalculated;
 mod zobrist;
 
+pub use castling_rights::CastlingRights;
+pub use history::BoardWithHistory;
+pub use move_gen::MoveGen;
+pub use perft::PerftResult;
+
 fn _construct_pgn_regex() -> Regex {
     #[allow(clippy::unwrap_used)]
     Regex::new("^(?:(?:(?P<piece>[BNRQK])?(?P<from_file>[a-h])?(?P<from_rank>[1-8])?(?P<takes>x)?(?P<target>[a-h][1-8])(?:=(?P<promotion>[BNRQ]))?)|(?P<castle>O-O(?P<long_castle>-O)?))(?P<check>[+#])?(?P<annotation>[!?]+)?$").unwrap()
@@ -33,7 +46,10 @@ lazy_static! {
     static ref PGN_REGEX: Regex = _construct_pgn_regex();
 }
 
+/// A move with everything required to unmake it packed into `bits`. The bit layout is stable, so [`Move::to_bits`]
+/// can be persisted and restored with [`Move::from_bits`]. With the `serde` feature, moves serialize as their bits.
 #[derive(Eq, PartialEq, Hash, Copy, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(from = "u64", into = "u64"))]
 pub struct Move {
     pub bits: u64,
     pub mvvlva: i32,
@@ -72,6 +88,8 @@ impl Move {
     pub const fn get_promotion_piece(&self) -> PieceBits { (self.bits & PROMOTION_PIECE_MASK) >> PROMOTION_PIECE_SHIFT }
     #[inline(always)]
     pub const fn get_side_to_move(&self) -> ColorBits { ((self.bits & SIDE_TO_MOVE_MASK) >> SIDE_TO_MOVE_SHIFT) as ColorBits }
+    #[inline(always)]
+    pub const fn get_castle_rook_file(&self) -> SquareShiftBits { ((self.bits & CASTLE_ROOK_FILE_MASK) >> CASTLE_ROOK_FILE_SHIFT) as SquareShiftBits }
 
     #[inline(always)]
     pub fn set_piece_moved(&mut self, value: PieceBits) { self.bits |= value << PIECE_MOVED_SHIFT }
@@ -105,6 +123,8 @@ impl Move {
     pub fn set_promotion_piece(&mut self, value: PieceBits) { self.bits |= value << PROMOTION_PIECE_SHIFT }
     #[inline(always)]
     pub fn set_side_to_move(&mut self, value: ColorBits) { self.bits |= (value as u64) << SIDE_TO_MOVE_SHIFT }
+    #[inline(always)]
+    pub fn set_castle_rook_file(&mut self, value: SquareShiftBits) { self.bits |= (value as u64) << CASTLE_ROOK_FILE_SHIFT }
 
     #[inline(always)]
     pub const fn is_self_lost_king_side_castle(&self) -> bool { self.get_self_lost_king_side_castle() != 0 }
@@ -124,13 +144,73 @@ impl Move {
     pub const fn is_attack(&self) -> bool { self.get_piece_attacked() != NO_PIECE }
     #[inline(always)]
     pub const fn is_promotion(&self) -> bool { self.get_promotion_piece() != NO_PIECE }
+    /// Neither a capture nor a promotion. Whether the move checks depends on the board, see [`Bitboard::is_quiet`].
+    #[inline(always)]
+    pub const fn is_quiet(&self) -> bool { !self.is_attack() && !self.is_promotion() }
+
+    pub const fn to_bits(&self) -> u64 {
+        self.bits
+    }
+
+    /// Restore a move from the bits of [`Move::to_bits`], the move ordering value is recalculated
+    pub const fn from_bits(bits: u64) -> Self {
+        let mut result = Self { bits, mvvlva: 0 };
+        result.mvvlva = Bitboard::mvv_lva(result.get_piece_moved(), result.get_piece_attacked());
+        result
+    }
 
     pub fn to_uci_string(&self) -> String {
         format!("{}{}{}", square_to_string(self.get_source_square()), square_to_string(self.get_target_square()), piece_to_string(self.get_promotion_piece()))
     }
 
     pub fn to_pgn_string(&self, board: &mut Bitboard) -> Result<String, MoveFromUciError> {
-        board.uci_to_pgn(&self.to_uci_string())
+        board.uci_to_pgn(&board.move_to_uci_string(*self))
+    }
+
+    /// This move displayed in SAN on `board`, the position before the move is made
+    pub const fn with_board(self, board: &Bitboard) -> MoveWithBoard<'_> {
+        MoveWithBoard { mv: self, board }
+    }
+
+    /// All fields of the move, as displayed by [`Display`]
+    pub fn to_verbose_string(&self) -> String {
+        format!(
+            "Move({}) {{ piece_moved = {}, piece_attacked = {}, self_lost_king_side_castle = {}, self_lost_queen_side_castle = {}, opponent_lost_king_side_castle = {}, opponent_lost_queen_side_castle = {}, castle_move = {}, en_passant_attack = {}, source_square = {}, target_square = {}, halfmove_reset = {}, previous_halfmove = {}, previous_en_passant_square = {}, next_en_passant_square = {}, promotion_piece = {}}}",
+            self.to_uci_string(),
+            piece_to_string(self.get_piece_moved()),
+            piece_to_string(self.get_piece_attacked()),
+            self.is_self_lost_king_side_castle(),
+            self.is_self_lost_queen_side_castle(),
+            self.is_opponent_lost_king_side_castle(),
+            self.is_opponent_lost_queen_side_castle(),
+            self.is_castle_move(),
+            self.is_en_passant_attack(),
+            square_to_string(self.get_source_square()),
+            square_to_string(self.get_target_square()),
+            self.is_halfmove_reset(),
+            self.get_previous_halfmove(),
+            square_to_string(self.get_previous_en_passant_square()),
+            square_to_string(self.get_next_en_passant_square()),
+            piece_to_string(self.get_promotion_piece()),
+        )
+    }
+}
+
+/// Displays a move in SAN, see [`Move::with_board`]. Moves that are not legal on the board are displayed in UCI notation.
+#[derive(Copy, Clone, Debug)]
+pub struct MoveWithBoard<'a> {
+    mv: Move,
+    board: &'a Bitboard,
+}
+
+impl Display for MoveWithBoard<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut board = *self.board;
+
+        match self.mv.to_pgn_string(&mut board) {
+            Ok(san) => write!(f, "{}", san),
+            Err(_) => write!(f, "{}", self.mv.to_uci_string()),
+        }
     }
 }
 
@@ -156,6 +236,18 @@ impl From<Move> for MoveStructs {
     }
 }
 
+impl From<u64> for Move {
+    fn from(bits: u64) -> Self {
+        Self::from_bits(bits)
+    }
+}
+
+impl From<Move> for u64 {
+    fn from(mv: Move) -> Self {
+        mv.to_bits()
+    }
+}
+
 impl Debug for Move {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(
@@ -172,15 +264,90 @@ pub enum MoveFromUciError {
     MoveIsNotValid(Move),
 }
 
+#[derive(Eq, PartialEq, Debug, Clone)]
 pub enum PgnParseError {
-    Error
+    /// No pseudo legal move matches the SAN
+    NoMatchingMove(String),
+    /// The SAN matches the given number of legal moves
+    AmbiguousMove(String, usize),
+    /// The SAN only matches moves leaving the king in check
+    IllegalMove(String),
+    /// The SAN is not valid notation
+    MalformedNotation(String),
+}
+
+#[derive(Eq, PartialEq, Debug)]
+pub enum FenValidationError {
+    FenParseError(FenParseError),
+    InconsistentEnPassantSquare(String),
+}
+
+/// Reasons a position can't arise in a game of chess, see [`Bitboard::validate`]
+#[derive(Eq, PartialEq, Copy, Clone, Debug)]
+pub enum PositionError {
+    /// The player doesn't have exactly one king, contains the number of kings the player has
+    KingCount(Color, u32),
+    /// A pawn stands on the first or eighth rank
+    PawnOnBackRank(Square),
+    /// The player not to move is in check
+    PassivePlayerInCheck,
+}
+
+/// Determines how [`Bitboard::from_fen_validated`] handles an en passant square that can't have been caused by a double pawn push
+#[derive(Eq, PartialEq, Copy, Clone, Debug)]
+pub enum EnPassantPolicy {
+    Reject,
+    Clear,
+}
+
+/// Classification of a capture by the outcome of its static exchange, see [`Bitboard::see`].
+#[derive(Eq, PartialEq, Copy, Clone, Debug)]
+pub enum CaptureClass {
+    Winning,
+    Equal,
+    Losing,
+}
+
+/// Status of the game in a position, see [`Bitboard::status`]
+#[derive(Eq, PartialEq, Copy, Clone, Debug)]
+pub enum GameStatus {
+    Ongoing,
+    Check,
+    Checkmate,
+    Stalemate,
+    DrawFiftyMove,
+    DrawInsufficientMaterial,
+}
+
+/// State lost by [`Bitboard::make_null`] that is required to restore the position with [`Bitboard::unmake_null`]
+#[derive(Eq, PartialEq, Copy, Clone, Debug)]
+pub struct NullMoveUndo {
+    en_passant_square_shift: SquareShiftBits,
+    halfmove_clock: u32,
+    checkers: OccupancyBits,
 }
 
-#[derive(Eq, PartialEq, Copy, Clone, Debug, Default)]
+#[derive(Eq, PartialEq, Copy, Clone, Debug)]
 pub struct PlayerState {
     occupancy: [OccupancyBits; 7],
     pub queen_side_castle: bool,
     pub king_side_castle: bool,
+    /// File index of the rook castling queen side, `0` for the a-file in standard chess
+    pub queen_side_rook_file: SquareShiftBits,
+    /// File index of the rook castling king side, `7` for the h-file in standard chess
+    pub king_side_rook_file: SquareShiftBits,
+}
+
+impl Default for PlayerState {
+    fn default() -> Self {
+        Self {
+            occupancy: [0; 7],
+            queen_side_castle: false,
+            king_side_castle: false,
+            queen_side_rook_file: 0,
+            king_side_rook_file: H1 - A1,
+        }
+    }
 }
 
 impl PlayerState {
@@ -188,6 +355,24 @@ impl PlayerState {
         self.kings() | self.queens() | self.rooks() | self.bishops() | self.knights() | self.pawns()
     }
 
+    /// Put `piece` on `square`, replacing any piece of this player already there
+    pub fn set_piece(&mut self, piece: Piece, square: Square) {
+        self.clear_square(square);
+        *self.occupancy_ref(piece.index as PieceBits) |= square.mask;
+    }
+
+    /// Remove the piece of this player on `square`, if any
+    pub fn clear_square(&mut self, square: Square) {
+        for occupancy in &mut self.occupancy {
+            *occupancy &= !square.mask;
+        }
+    }
+
+    /// The pieces with ranks flipped, castling rights and rook files are kept
+    fn mirror(&self) -> Self {
+        Self { occupancy: self.occupancy.map(OccupancyBits::swap_bytes), ..*self }
+    }
+
     #[inline(always)]
     fn occupancy_ref(&mut self, piece: PieceBits) -> &mut OccupancyBits { &mut self.occupancy[piece as usize] }
     #[inline(always)]
@@ -247,9 +432,56 @@ impl PlayerState {
     const fn find_piece_struct_by_square_mask(&self, square: SquareMaskBits) -> Option<Piece> {
         Piece::from_index(self.get_piece_const_by_square_mask(square) as usize)
     }
+
+    /// Add the castling right of a FEN castling availability symbol, either `K`/`Q` for the outermost rook on that side
+    /// of the king (X-FEN) or the file letter of the castling rook (Shredder-FEN). The case of `symbol` is ignored.
+    fn add_castling_right(&mut self, symbol: char, back_rank: SquareShiftBits) {
+        let rank_occupancy = |occupancy: OccupancyBits| ((occupancy >> back_rank) & 0xFF) as u32;
+
+        let king_file = match rank_occupancy(self.kings()) {
+            0 => E1 - A1,
+            kings => kings.trailing_zeros(),
+        };
+
+        let rook_files = rank_occupancy(self.rooks());
+        let king_side_rook_files = rook_files & !((2 << king_file) - 1);
+        let queen_side_rook_files = rook_files & ((1 << king_file) - 1);
+
+        match symbol.to_ascii_uppercase() {
+            'K' => {
+                self.king_side_castle = true;
+                self.king_side_rook_file = if king_side_rook_files == 0 { H1 - A1 } else { u32::BITS - 1 - king_side_rook_files.leading_zeros() };
+            }
+            'Q' => {
+                self.queen_side_castle = true;
+                self.queen_side_rook_file = if queen_side_rook_files == 0 { 0 } else { queen_side_rook_files.trailing_zeros() };
+            }
+            file @ 'A'..='H' => {
+                let file = file as u32 - 'A' as u32;
+
+                if file > king_file {
+                    self.king_side_castle = true;
+                    self.king_side_rook_file = file;
+                } else {
+                    self.queen_side_castle = true;
+                    self.queen_side_rook_file = file;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Whether the castling rights can't be expressed in standard chess, i.e. the king or a castling rook isn't on its
+    /// standard square
+    const fn has_chess960_castling_rights(&self, back_rank: SquareShiftBits) -> bool {
+        let king_on_standard_square = (self.kings() & (1 << (back_rank + E1 - A1))) != 0;
+
+        (self.queen_side_castle && (!king_on_standard_square || self.queen_side_rook_file != 0))
+            || (self.king_side_castle && (!king_on_standard_square || self.king_side_rook_file != H1 - A1))
+    }
 }
 
-#[derive(Eq, PartialEq, Debug)]
+#[derive(Eq, PartialEq, Copy, Clone, Debug)]
 pub struct Bitboard {
     pub white: PlayerState,
     pub black: PlayerState,
@@ -257,15 +489,38 @@ pub struct Bitboard {
     pub en_passant_square_shift: SquareShiftBits,
     pub fullmove_clock: u32,
     pub halfmove_clock: u32,
+    /// Pieces giving check to the side to move, updated by [`Bitboard::make`] and [`Bitboard::unmake`]
+    checkers: OccupancyBits,
+    /// Zobrist hash of the current position, updated by [`Bitboard::make`] and [`Bitboard::unmake`]
+    zobrist_hash: ZobristHash,
+    /// Zobrist pawn hash of the current position, updated by [`Bitboard::make`] and [`Bitboard::unmake`]
+    zobrist_pawn_hash: ZobristHash,
+    /// Material of each side in centipawns without kings, updated by [`Bitboard::make`] and [`Bitboard::unmake`]
+    material: [i32; 2],
+    /// Sum of the [`Bitboard::PHASE_WEIGHTS`] of all pieces, updated by [`Bitboard::make`] and [`Bitboard::unmake`]
+    phase_weight: i32,
+    /// Whether castling rights are written as rook files in FENs and castle moves as the king capturing its rook in UCI
+    chess960: bool,
 }
 
 // Move Generation
 impl Bitboard {
     pub fn generate_legal_moves(&mut self) -> Vec<Move> {
-        self.generate_pseudo_legal_moves()
-            .into_iter()
-            .filter(|&mv| self.is_move_legal(mv))
-            .collect()
+        let mut buffer = Vec::new();
+        self.generate_legal_moves_with_buffer(&mut buffer);
+        buffer
+    }
+
+    /// Iterate the legal moves lazily, see [`MoveGen`]
+    pub fn legal_moves_iter(&mut self) -> MoveGen<'_> {
+        MoveGen::new(self)
+    }
+
+    /// Replace the contents of `buffer` with the legal moves, reusing its allocation
+    pub fn generate_legal_moves_with_buffer(&mut self, buffer: &mut Vec<Move>) {
+        buffer.clear();
+        self.generate_pseudo_legal_moves_with_buffer(buffer);
+        buffer.retain(|&mv| self.is_move_legal(mv));
     }
 
     pub fn generate_pseudo_legal_moves(&self) -> Vec<Move> {
@@ -480,33 +735,60 @@ impl Bitboard {
     }
 
     fn castle_moves(&self, result: &mut Vec<Move>, full_occupancy: OccupancyBits) {
-        if self.is_white_turn() {
-            if self.white.queen_side_castle
-                && (full_occupancy & WHITE_QUEEN_SIDE_CASTLE_EMPTY_OCCUPANCY) == 0
-                && !Self::_is_occupancy_in_check(WHITE, &self.black, full_occupancy, WHITE_QUEEN_SIDE_CASTLE_CHECK_OCCUPANCY) {
-                self.make_castle_move(result, E1, C1);
-            }
+        let (active, back_rank) = if self.is_white_turn() { (&self.white, A1) } else { (&self.black, A8) };
 
-            if self.white.king_side_castle
-                && (full_occupancy & WHITE_KING_SIDE_CASTLE_EMPTY_OCCUPANCY) == 0
-                && !Self::_is_occupancy_in_check(WHITE, &self.black, full_occupancy, WHITE_KING_SIDE_CASTLE_CHECK_OCCUPANCY) {
-                self.make_castle_move(result, E1, G1);
-            }
-        } else {
-            if self.black.queen_side_castle
-                && ((full_occupancy & BLACK_QUEEN_SIDE_CASTLE_EMPTY_OCCUPANCY) == 0)
-                && !Self::_is_occupancy_in_check(BLACK, &self.white, full_occupancy, BLACK_QUEEN_SIDE_CASTLE_CHECK_OCCUPANCY) {
-                self.make_castle_move(result, E8, C8);
-            }
+        if !active.queen_side_castle && !active.king_side_castle {
+            return;
+        }
 
-            if self.black.king_side_castle
-                && (full_occupancy & BLACK_KING_SIDE_CASTLE_EMPTY_OCCUPANCY) == 0
-                && !Self::_is_occupancy_in_check(BLACK, &self.white, full_occupancy, BLACK_KING_SIDE_CASTLE_CHECK_OCCUPANCY) {
-                self.make_castle_move(result, E8, G8);
-            }
+        let king_source_square_shift = active.kings().trailing_zeros();
+
+        if active.queen_side_castle {
+            let rook_source_square_shift = back_rank + active.queen_side_rook_file;
+            self.castle_move(result, full_occupancy, king_source_square_shift, rook_source_square_shift, back_rank + C1 - A1, back_rank + D1 - A1);
+        }
+
+        if active.king_side_castle {
+            let rook_source_square_shift = back_rank + active.king_side_rook_file;
+            self.castle_move(result, full_occupancy, king_source_square_shift, rook_source_square_shift, back_rank + G1 - A1, back_rank + F1 - A1);
+        }
+    }
+
+    /// Generate the castle move if all squares the king and rook pass through are empty, except for the castling king
+    /// and rook themselves, and the king doesn't pass through any attacked square. Works for Chess960 starting files.
+    fn castle_move(
+        &self,
+        result: &mut Vec<Move>,
+        full_occupancy: OccupancyBits,
+        king_source_square_shift: SquareShiftBits,
+        rook_source_square_shift: SquareShiftBits,
+        king_target_square_shift: SquareShiftBits,
+        rook_target_square_shift: SquareShiftBits,
+    ) {
+        let king_path = Self::rank_span(king_source_square_shift, king_target_square_shift);
+        let rook_path = Self::rank_span(rook_source_square_shift, rook_target_square_shift);
+        let rook_source_square_mask = square_mask_from_shift(rook_source_square_shift);
+        let castling_pieces = square_mask_from_shift(king_source_square_shift) | rook_source_square_mask;
+
+        if (full_occupancy & (king_path | rook_path) & !castling_pieces) != 0 {
+            return;
+        }
+
+        let (_, passive) = self.get_active_and_passive();
+
+        // Without the castling rook, which may shield the king target from a slider on the back rank
+        if !Self::_is_occupancy_in_check(self.turn, passive, full_occupancy & !rook_source_square_mask, king_path) {
+            self.make_castle_move(result, king_source_square_shift, king_target_square_shift);
         }
     }
 
+    /// All squares from `a` to `b` inclusive, both on the same rank
+    const fn rank_span(a: SquareShiftBits, b: SquareShiftBits) -> OccupancyBits {
+        let (low, high) = if a < b { (a, b) } else { (b, a) };
+
+        (u64::MAX >> (63 - high)) & (u64::MAX << low)
+    }
+
     #[inline(always)]
     fn make_castle_move(&self, result: &mut Vec<Move>, king_source_square_shift: SquareShiftBits, king_target_square_shift: SquareShiftBits) {
         self.make_move(
@@ -563,7 +845,8 @@ impl Bitboard {
     ) {
         let active;
         let passive;
-        let d_castle;
+        let active_back_rank;
+        let passive_back_rank;
         let attack_square_shift;
 
         let en_passant_offset = if is_en_passant_attack_mask == 0 {
@@ -575,12 +858,14 @@ impl Bitboard {
         if self.is_white_turn() {
             active = &self.white;
             passive = &self.black;
-            d_castle = 0;
+            active_back_rank = A1;
+            passive_back_rank = A8;
             attack_square_shift = target_square_shift + en_passant_offset;
         } else {
             active = &self.black;
             passive = &self.white;
-            d_castle = 56;
+            active_back_rank = A8;
+            passive_back_rank = A1;
             attack_square_shift = target_square_shift - en_passant_offset;
         }
 
@@ -612,25 +897,36 @@ impl Bitboard {
             mv.set_halfmove_reset();
         }
 
-        if passive.queen_side_castle && target_square_shift == (A8 + d_castle) {
+        if passive.queen_side_castle && target_square_shift == passive_back_rank + passive.queen_side_rook_file {
             mv.set_opponent_lost_queen_side_castle();
-        } else if passive.king_side_castle && target_square_shift == (H8 + d_castle) {
+        } else if passive.king_side_castle && target_square_shift == passive_back_rank + passive.king_side_rook_file {
             mv.set_opponent_lost_king_side_castle();
         }
 
-        if active.queen_side_castle && (source_square_shift == (A1 - d_castle) || source_square_shift == (E1 - d_castle)) {
+        if active.queen_side_castle && (piece_active == KING || source_square_shift == active_back_rank + active.queen_side_rook_file) {
             mv.set_self_lost_queen_side_castle();
         }
 
-        if active.king_side_castle && (source_square_shift == (H1 - d_castle) || source_square_shift == (E1 - d_castle)) {
+        if active.king_side_castle && (piece_active == KING || source_square_shift == active_back_rank + active.king_side_rook_file) {
             mv.set_self_lost_king_side_castle();
         }
 
+        if is_castle_move_mask != 0 {
+            let is_king_side = target_square_shift == active_back_rank + G1 - A1;
+            mv.set_castle_rook_file(if is_king_side { active.king_side_rook_file } else { active.queen_side_rook_file });
+        }
+
         mv.mvvlva = Self::mvv_lva(piece_active, piece_attacked);
         result.push(mv);
     }
 
-    const PIECE_VALUES: [i32; 7] = [0, 100, 320, 330, 500, 900, 901];
+    /// Material values by piece, as used for move ordering and static exchange evaluation. The king is worth slightly
+    /// more than a queen, so it is the last attacker by MVV-LVA.
+    pub const PIECE_VALUES: [i32; 7] = [0, Piece::PAWN.value(), Piece::KNIGHT.value(), Piece::BISHOP.value(), Piece::ROOK.value(), Piece::QUEEN.value(), Piece::QUEEN.value() + 1];
+
+    /// Contribution of each piece to the early game, see [`Bitboard::phase`]
+    const PHASE_WEIGHTS: [i32; 7] = [0, Piece::PAWN.phase_weight(), Piece::KNIGHT.phase_weight(), Piece::BISHOP.phase_weight(), Piece::ROOK.phase_weight(), Piece::QUEEN.phase_weight(), Piece::KING.phase_weight()];
+    const TOTAL_PHASE_WEIGHT: i32 = 24;
 
     /// Returns a value in `0..=230300`
     const fn mvv_lva(piece_active: PieceBits, piece_attacked: PieceBits) -> i32 {
@@ -651,6 +947,8 @@ impl Bitboard {
     pub fn make(&mut self, mv: Move) {
         let is_white_turn = self.is_white_turn();
 
+        self.apply_material_and_phase_delta(mv, self.turn, 1);
+
         self.fullmove_clock += self.turn;
 
         if mv.is_halfmove_reset() {
@@ -689,13 +987,8 @@ impl Bitboard {
         let target_square_mask: SquareMaskBits = 1_u64 << target_square_shift;
 
         if mv.is_castle_move() {
-            match target_square_shift {
-                C1 => Self::make_castle(active, A1_MASK, source_square_mask, D1_MASK, target_square_mask),
-                G1 => Self::make_castle(active, H1_MASK, source_square_mask, F1_MASK, target_square_mask),
-                C8 => Self::make_castle(active, A8_MASK, source_square_mask, D8_MASK, target_square_mask),
-                G8 => Self::make_castle(active, H8_MASK, source_square_mask, F8_MASK, target_square_mask),
-                _ => panic!(),
-            };
+            let (rook_source_square_shift, rook_target_square_shift) = Self::castle_rook_square_shifts(mv);
+            Self::make_castle(active, square_mask_from_shift(rook_source_square_shift), source_square_mask, square_mask_from_shift(rook_target_square_shift), target_square_mask);
         } else if mv.is_en_passant_attack() {
             *active.pawns_ref() &= !source_square_mask;
             *active.pawns_ref() |= target_square_mask;
@@ -718,6 +1011,9 @@ impl Bitboard {
             *passive.occupancy_ref(mv.get_piece_attacked()) &= !target_square_mask;
             // passive.unset_all(target_square_mask);
         }
+
+        self.checkers = self.calculate_checkers();
+        self.apply_zobrist_xor(mv);
     }
 
     /// "Unmake" `mv` on this bitboard
@@ -729,6 +1025,8 @@ impl Bitboard {
         self.en_passant_square_shift = mv.get_previous_en_passant_square();
         self.turn = self.opposite_turn();
 
+        self.apply_material_and_phase_delta(mv, self.turn, -1);
+
         let (active, passive) = self.get_active_and_passive_mut();
 
         if mv.is_self_lost_king_side_castle() {
@@ -757,13 +1055,8 @@ impl Bitboard {
         let piece_attacked = mv.get_piece_attacked();
 
         if mv.is_castle_move() {
-            match target_square_shift {
-                C1 => Self::unmake_castle(active, A1_MASK, source_square_mask, D1_MASK, target_square_mask),
-                G1 => Self::unmake_castle(active, H1_MASK, source_square_mask, F1_MASK, target_square_mask),
-                C8 => Self::unmake_castle(active, A8_MASK, source_square_mask, D8_MASK, target_square_mask),
-                G8 => Self::unmake_castle(active, H8_MASK, source_square_mask, F8_MASK, target_square_mask),
-                _ => panic!(),
-            };
+            let (rook_source_square_shift, rook_target_square_shift) = Self::castle_rook_square_shifts(mv);
+            Self::unmake_castle(active, square_mask_from_shift(rook_source_square_shift), source_square_mask, square_mask_from_shift(rook_target_square_shift), target_square_mask);
         } else if mv.is_en_passant_attack() {
             *active.pawns_ref() &= !target_square_mask;
             *active.pawns_ref() |= source_square_mask;
@@ -784,6 +1077,64 @@ impl Bitboard {
             *active.occupancy_ref(piece_moved) |= source_square_mask;
             *active.occupancy_ref(piece_moved) &= !target_square_mask;
         }
+
+        // Restoring a non-capture writes the target square to the unused `NO_PIECE` occupancy, reset it so unmade
+        // boards compare equal
+        *passive.occupancy_ref(NO_PIECE) = 0;
+
+        self.checkers = self.calculate_checkers();
+        self.apply_zobrist_xor(mv);
+    }
+
+    /// Apply the material and phase changes of the capture and promotion of `mv` by `color`, `sign` is `1` for make and
+    /// `-1` for unmake
+    #[inline(always)]
+    fn apply_material_and_phase_delta(&mut self, mv: Move, color: ColorBits, sign: i32) {
+        let piece_attacked = mv.get_piece_attacked();
+        if piece_attacked != NO_PIECE {
+            self.material[opposite_color(color) as usize] -= sign * Self::PIECE_VALUES[piece_attacked as usize];
+            self.phase_weight -= sign * Self::PHASE_WEIGHTS[piece_attacked as usize];
+        }
+
+        let promotion_piece = mv.get_promotion_piece();
+        if promotion_piece != NO_PIECE {
+            self.material[color as usize] += sign * (Self::PIECE_VALUES[promotion_piece as usize] - Self::PIECE_VALUES[PAWN as usize]);
+            self.phase_weight += sign * Self::PHASE_WEIGHTS[promotion_piece as usize];
+        }
+    }
+
+    /// Pass the turn without moving, e.g. for null move pruning. The side to move must not be in check.
+    pub fn make_null(&mut self) -> NullMoveUndo {
+        let undo = NullMoveUndo { en_passant_square_shift: self.en_passant_square_shift, halfmove_clock: self.halfmove_clock, checkers: self.checkers };
+
+        self.fullmove_clock += self.turn;
+        self.halfmove_clock += 1;
+        self.apply_null_move_zobrist_xor(self.en_passant_square_shift);
+        self.en_passant_square_shift = NO_SQUARE;
+        self.turn = self.opposite_turn();
+        self.checkers = self.calculate_checkers();
+
+        undo
+    }
+
+    /// "Unmake" a null move made by [`Bitboard::make_null`]
+    pub fn unmake_null(&mut self, undo: NullMoveUndo) {
+        self.fullmove_clock -= 1 - self.turn;
+        self.halfmove_clock = undo.halfmove_clock;
+        self.en_passant_square_shift = undo.en_passant_square_shift;
+        self.turn = self.opposite_turn();
+        self.checkers = undo.checkers;
+        self.apply_null_move_zobrist_xor(self.en_passant_square_shift);
+    }
+
+    /// Returns the source and target square of the rook moved by the castle move `mv`
+    #[inline(always)]
+    const fn castle_rook_square_shifts(mv: Move) -> (SquareShiftBits, SquareShiftBits) {
+        let king_target_square_shift = mv.get_target_square();
+        let back_rank = king_target_square_shift - king_target_square_shift % 8;
+        let rook_target_square_shift = if king_target_square_shift == back_rank + G1 - A1 { back_rank + F1 - A1 } else { back_rank + D1 - A1 };
+
+        (back_rank + mv.get_castle_rook_file(), rook_target_square_shift)
     }
 
     #[inline(always)]
@@ -825,12 +1176,165 @@ impl Bitboard {
         !self._is_in_check_by_bits(self.opposite_turn())
     }
 
-    pub fn is_current_in_check(&self) -> bool {
-        self._is_in_check_by_bits(self.turn)
+    /// Verify that each player has exactly one king, no pawns stand on the first or eighth rank and the player not to
+    /// move is not in check. Parsing a FEN doesn't reject any of these.
+    pub fn validate(&self) -> Result<(), PositionError> {
+        for (color, player_state) in [(Color::WHITE, &self.white), (Color::BLACK, &self.black)] {
+            let king_count = player_state.kings().count_ones();
+
+            if king_count != 1 {
+                return Err(PositionError::KingCount(color, king_count));
+            }
+        }
+
+        let back_rank_pawns = (self.white.pawns() | self.black.pawns()) & (RANK_1_OCCUPANCY | RANK_8_OCCUPANCY);
+
+        if back_rank_pawns != 0 {
+            return Err(PositionError::PawnOnBackRank(Square::from_index_unchecked(back_rank_pawns.trailing_zeros() as usize)));
+        }
+
+        if !self.is_valid() {
+            return Err(PositionError::PassivePlayerInCheck);
+        }
+
+        Ok(())
+    }
+
+    pub const fn is_current_in_check(&self) -> bool {
+        self.checkers != 0
     }
 
     pub fn is_in_check(&self, color: &Color) -> bool {
-        self._is_in_check_by_bits(color.index)
+        if color.index == self.turn { self.is_current_in_check() } else { self._is_in_check_by_bits(color.index) }
+    }
+
+    /// Returns the pieces giving check to the side to move.
+    pub const fn checkers(&self) -> OccupancyBits {
+        self.checkers
+    }
+
+    /// Returns whether `square` is attacked by any piece of color `by`, regardless of what occupies it.
+    pub fn is_square_attacked(&self, square: Square, by: Color) -> bool {
+        let (attacked_color_bits, attacker) = if by.index == WHITE { (BLACK, &self.white) } else { (WHITE, &self.black) };
+        let full_occupancy = self.white.full_occupancy() | self.black.full_occupancy();
+
+        Self::_is_square_in_check(attacked_color_bits, attacker, square.shift, full_occupancy)
+    }
+
+    /// Returns the squares attacked by the piece on `square`, with sliding attacks blocked by pieces of both colors, or 0
+    /// if `square` is empty.
+    pub fn attacks_from(&self, square: Square) -> OccupancyBits {
+        let full_occupancy = self.white.full_occupancy() | self.black.full_occupancy();
+
+        let (piece, pawn_nonmagics) = match self.white.get_piece_const_by_square_mask(square.mask) {
+            NO_PIECE => (self.black.get_piece_const_by_square_mask(square.mask), &BLACK_PAWN_NONMAGICS),
+            piece => (piece, &WHITE_PAWN_NONMAGICS),
+        };
+
+        match piece {
+            PAWN => unsafe { pawn_nonmagics.get_attacks(square.shift) },
+            KNIGHT => unsafe { KNIGHT_NONMAGICS.get_attacks(square.shift) },
+            BISHOP => BISHOP_MAGICS.get_attacks(square.shift, full_occupancy),
+            ROOK => ROOK_MAGICS.get_attacks(square.shift, full_occupancy),
+            QUEEN => ROOK_MAGICS.get_attacks(square.shift, full_occupancy) | BISHOP_MAGICS.get_attacks(square.shift, full_occupancy),
+            KING => unsafe { KING_NONMAGICS.get_attacks(square.shift) },
+            _ => 0,
+        }
+    }
+
+    /// Returns the squares attacked by any piece of color `by`, including squares occupied by its own pieces.
+    pub fn attacked_squares(&self, by: Color) -> OccupancyBits {
+        let (attacker, pawn_nonmagics) = if by.index == WHITE { (&self.white, &WHITE_PAWN_NONMAGICS) } else { (&self.black, &BLACK_PAWN_NONMAGICS) };
+        let full_occupancy = self.white.full_occupancy() | self.black.full_occupancy();
+
+        Self::union_attacks(attacker.pawns(), |square_shift| unsafe { pawn_nonmagics.get_attacks(square_shift) })
+            | Self::union_attacks(attacker.knights(), |square_shift| unsafe { KNIGHT_NONMAGICS.get_attacks(square_shift) })
+            | Self::union_attacks(attacker.bishops() | attacker.queens(), |square_shift| BISHOP_MAGICS.get_attacks(square_shift, full_occupancy))
+            | Self::union_attacks(attacker.rooks() | attacker.queens(), |square_shift| ROOK_MAGICS.get_attacks(square_shift, full_occupancy))
+            | Self::union_attacks(attacker.kings(), |square_shift| unsafe { KING_NONMAGICS.get_attacks(square_shift) })
+    }
+
+    /// Returns whether `mv` is neither a capture nor a promotion and doesn't put the opponent in check
+    pub fn is_quiet(&self, mv: Move) -> bool {
+        mv.is_quiet() && !self.gives_check(mv)
+    }
+
+    /// Returns whether `mv` puts the opponent in check, either by attacking the king with the moved piece or by
+    /// uncovering a sliding attack. Equivalent to checking [`Bitboard::is_current_in_check`] after making `mv`.
+    pub fn gives_check(&self, mv: Move) -> bool {
+        // Castling moves two pieces, which is rare enough to not warrant its own logic
+        if mv.is_castle_move() {
+            let mut board = *self;
+            board.make(mv);
+            return board.is_current_in_check();
+        }
+
+        let (active, passive) = if self.is_white_turn() { (&self.white, &self.black) } else { (&self.black, &self.white) };
+
+        if passive.kings() == 0 {
+            return false;
+        }
+
+        let king_square_shift = passive.kings().trailing_zeros();
+
+        let source_square_mask = square_mask_from_shift(mv.get_source_square());
+        let target_square_shift = mv.get_target_square();
+        let target_square_mask = square_mask_from_shift(target_square_shift);
+
+        let mut full_occupancy = (active.full_occupancy() | passive.full_occupancy()) & !source_square_mask | target_square_mask;
+
+        if mv.is_en_passant_attack() {
+            full_occupancy &= !if self.is_white_turn() { target_square_mask << 8 } else { target_square_mask >> 8 };
+        }
+
+        let piece = if mv.is_promotion() { mv.get_promotion_piece() } else { mv.get_piece_moved() };
+        let piece_mask = |pieces: &[PieceBits]| if pieces.contains(&piece) { target_square_mask } else { 0 };
+
+        let king_mask = passive.kings();
+
+        let pawn_nonmagics = if self.is_white_turn() { &WHITE_PAWN_NONMAGICS } else { &BLACK_PAWN_NONMAGICS };
+
+        let direct_attacks = match piece {
+            PAWN => unsafe { pawn_nonmagics.get_attacks(target_square_shift) },
+            KNIGHT => unsafe { KNIGHT_NONMAGICS.get_attacks(target_square_shift) },
+            // Only possible for pseudo-legal moves, for which the opponent king counts as in check as well
+            KING => unsafe { KING_NONMAGICS.get_attacks(target_square_shift) },
+            _ => 0,
+        };
+
+        // Covers direct checks of the moved slider as well as discovered checks
+        let rooks_and_queens = (active.rooks() | active.queens()) & !source_square_mask | piece_mask(&[ROOK, QUEEN]);
+        let bishops_and_queens = (active.bishops() | active.queens()) & !source_square_mask | piece_mask(&[BISHOP, QUEEN]);
+
+        direct_attacks & king_mask != 0
+            || ROOK_MAGICS.get_attacks(king_square_shift, full_occupancy) & rooks_and_queens != 0
+            || BISHOP_MAGICS.get_attacks(king_square_shift, full_occupancy) & bishops_and_queens != 0
+    }
+
+    fn union_attacks<F: Fn(SquareShiftBits) -> OccupancyBits>(mut occupancy: OccupancyBits, attacks: F) -> OccupancyBits {
+        let mut result = 0;
+
+        while occupancy != 0 {
+            let (square_mask, square_shift) = mask_and_shift_from_lowest_one_bit(occupancy);
+            occupancy &= !square_mask;
+
+            result |= attacks(square_shift);
+        }
+
+        result
+    }
+
+    fn calculate_checkers(&self) -> OccupancyBits {
+        let (active, passive) = if self.turn == WHITE { (&self.white, &self.black) } else { (&self.black, &self.white) };
+
+        if active.kings() == 0 {
+            return 0;
+        }
+
+        let full_occupancy = active.full_occupancy() | passive.full_occupancy();
+
+        // Assume only one king
+        self.attackers_of_square(active.kings().trailing_zeros(), full_occupancy) & passive.full_occupancy()
     }
 
     fn _is_in_check_by_bits(&self, color_bits: ColorBits) -> bool {
@@ -894,63 +1398,302 @@ impl Bitboard {
     }
 }
 
-// Zobrist
+// Static Exchange Evaluation
 impl Bitboard {
-    /// Calculate the zobrist xor difference and zobrist pawn xor difference for a move
-    pub fn zobrist_xor(mv: Move) -> (ZobristHash, ZobristHash) {
-        let mut result: ZobristHash = 0;
-        let mut pawn_result: ZobristHash = 0;
-
-        let self_color = mv.get_side_to_move();
-        let opponent_color = opposite_color(self_color);
+    /// Statically evaluate the sequence of captures on the target square of `mv`, assuming both sides
+    /// always recapture with their least valuable attacker and may stop capturing at any point.
+    ///
+    /// Returns the net material gain for the side making `mv` in centipawns.
+    pub fn see(&self, mv: Move) -> i32 {
+        let target_square_shift = mv.get_target_square();
+        let target_square_mask = square_mask_from_shift(target_square_shift);
+        let promotion_occupancy = RANK_1_OCCUPANCY | RANK_8_OCCUPANCY;
 
-        pawn_result ^= Zobrist::BLACK_TO_MOVE_HASH;
+        let mut full_occupancy = self.white.full_occupancy() | self.black.full_occupancy();
+        full_occupancy &= !square_mask_from_shift(mv.get_source_square());
 
-        if mv.is_self_lost_king_side_castle() {
-            result ^= Zobrist::castle_hash(KING, self_color);
+        if mv.get_en_passant_attack() != 0 {
+            let captured_square_shift = if mv.get_side_to_move() == WHITE { target_square_shift + 8 } else { target_square_shift - 8 };
+            full_occupancy &= !square_mask_from_shift(captured_square_shift);
         }
 
-        if mv.is_self_lost_queen_side_castle() {
-            result ^= Zobrist::castle_hash(QUEEN, self_color);
-        }
+        let mut gain = [0; 32];
+        gain[0] = Self::PIECE_VALUES[mv.get_piece_attacked() as usize];
 
-        if mv.is_opponent_lost_king_side_castle() {
-            result ^= Zobrist::castle_hash(KING, opponent_color);
-        }
+        let mut piece_on_target = if mv.get_promotion_piece() == NO_PIECE {
+            mv.get_piece_moved()
+        } else {
+            gain[0] += Self::PIECE_VALUES[mv.get_promotion_piece() as usize] - Self::PIECE_VALUES[PAWN as usize];
+            mv.get_promotion_piece()
+        };
 
-        if mv.is_opponent_lost_queen_side_castle() {
-            result ^= Zobrist::castle_hash(QUEEN, opponent_color);
-        }
+        let mut color_bits = opposite_color(mv.get_side_to_move());
+        let mut depth = 0;
 
-        if mv.get_previous_en_passant_square() != NO_SQUARE {
-            pawn_result ^= Zobrist::en_passant_square_hash(mv.get_previous_en_passant_square());
-        }
+        loop {
+            let attackers = self.attackers_of_square(target_square_shift, full_occupancy) & full_occupancy;
+            let player_state = if color_bits == WHITE { &self.white } else { &self.black };
+            let active_attackers = attackers & player_state.full_occupancy();
 
-        if mv.get_next_en_passant_square() != NO_SQUARE {
-            pawn_result ^= Zobrist::en_passant_square_hash(mv.get_next_en_passant_square());
-        }
+            if active_attackers == 0 {
+                break;
+            }
 
-        let is_white_turn = self_color == WHITE;
+            let (attacker, attacker_mask) = Self::least_valuable_attacker(player_state, active_attackers);
 
-        let piece_moved = mv.get_piece_moved();
-        let piece_promoted = mv.get_promotion_piece();
-        let piece_attacked = mv.get_piece_attacked();
+            // The king may only capture if the opponent can't recapture
+            if attacker == KING && (attackers & !active_attackers) != 0 {
+                break;
+            }
+
+            depth += 1;
+            gain[depth] = Self::PIECE_VALUES[piece_on_target as usize] - gain[depth - 1];
+
+            // Neither side can improve by continuing the exchange
+            if (-gain[depth - 1]).max(gain[depth]) < 0 {
+                break;
+            }
+
+            piece_on_target = if attacker == PAWN && (target_square_mask & promotion_occupancy) != 0 {
+                gain[depth] += Self::PIECE_VALUES[QUEEN as usize] - Self::PIECE_VALUES[PAWN as usize];
+                QUEEN
+            } else {
+                attacker
+            };
+
+            full_occupancy &= !attacker_mask;
+            color_bits = opposite_color(color_bits);
+        }
+
+        while depth > 0 {
+            gain[depth - 1] = -(-gain[depth - 1]).max(gain[depth]);
+            depth -= 1;
+        }
+
+        gain[0]
+    }
+
+    /// Classify `mv` by the result of [`Bitboard::see`].
+    pub fn capture_classification(&self, mv: Move) -> CaptureClass {
+        match self.see(mv) {
+            value if value > 0 => CaptureClass::Winning,
+            0 => CaptureClass::Equal,
+            _ => CaptureClass::Losing,
+        }
+    }
+
+    /// Whether `mv` is classified as [`CaptureClass::Winning`].
+    pub fn is_winning_capture(&self, mv: Move) -> bool {
+        self.capture_classification(mv) == CaptureClass::Winning
+    }
+
+    /// Returns the pieces of both colors attacking `square_shift`, with sliding attacks blocked by `full_occupancy`.
+    fn attackers_of_square(&self, square_shift: SquareShiftBits, full_occupancy: OccupancyBits) -> OccupancyBits {
+        let rooks_and_queens = self.white.rooks() | self.white.queens() | self.black.rooks() | self.black.queens();
+        let bishops_and_queens = self.white.bishops() | self.white.queens() | self.black.bishops() | self.black.queens();
+
+        (ROOK_MAGICS.get_attacks(square_shift, full_occupancy) & rooks_and_queens)
+            | (BISHOP_MAGICS.get_attacks(square_shift, full_occupancy) & bishops_and_queens)
+            | (unsafe { KNIGHT_NONMAGICS.get_attacks(square_shift) } & (self.white.knights() | self.black.knights()))
+            | (unsafe { KING_NONMAGICS.get_attacks(square_shift) } & (self.white.kings() | self.black.kings()))
+            | (unsafe { BLACK_PAWN_NONMAGICS.get_attacks(square_shift) } & self.white.pawns())
+            | (unsafe { WHITE_PAWN_NONMAGICS.get_attacks(square_shift) } & self.black.pawns())
+    }
+
+    fn least_valuable_attacker(player_state: &PlayerState, attackers: OccupancyBits) -> (PieceBits, SquareMaskBits) {
+        for piece in PAWN..=KING {
+            let occupancy = player_state.occupancy(piece) & attackers;
+
+            if occupancy != 0 {
+                return (piece, occupancy & occupancy.wrapping_neg());
+            }
+        }
+
+        (NO_PIECE, 0)
+    }
+}
+
+// Pins
+impl Bitboard {
+    /// Returns all absolute pins against the king of `color` as (pinned piece, king, pinning piece) tuples.
+    pub fn absolute_pins(&self, color: &Color) -> Vec<(Square, Square, Square)> {
+        let (active, passive) = if color.index == WHITE { (&self.white, &self.black) } else { (&self.black, &self.white) };
+
+        if active.kings() == 0 {
+            return Vec::new();
+        }
+
+        let king_square_shift = active.kings().trailing_zeros();
+
+        self.single_blockers(king_square_shift, passive, active.full_occupancy())
+            .into_iter()
+            .map(|(blocker_shift, slider_shift)| (Self::square(blocker_shift), Self::square(king_square_shift), Self::square(slider_shift)))
+            .collect()
+    }
+
+    /// Returns all pieces of `color` that would reveal an attack on the enemy king if moved, paired with the piece whose attack is revealed.
+    pub fn discovered_attack_candidates(&self, color: &Color) -> Vec<(Square, Square)> {
+        let (active, passive) = if color.index == WHITE { (&self.white, &self.black) } else { (&self.black, &self.white) };
+
+        if passive.kings() == 0 {
+            return Vec::new();
+        }
+
+        self.single_blockers(passive.kings().trailing_zeros(), active, active.full_occupancy())
+            .into_iter()
+            .map(|(blocker_shift, slider_shift)| (Self::square(blocker_shift), Self::square(slider_shift)))
+            .collect()
+    }
+
+    /// Returns (blocker, slider) pairs for every slider of `sliders` that would attack `king_square_shift` if not for a single piece from
+    /// `blocker_occupancy` standing in between.
+    fn single_blockers(&self, king_square_shift: SquareShiftBits, sliders: &PlayerState, blocker_occupancy: OccupancyBits) -> Vec<(SquareShiftBits, SquareShiftBits)> {
+        let full_occupancy = self.white.full_occupancy() | self.black.full_occupancy();
+
+        let mut result = Vec::new();
+
+        for (magics, mut slider_occupancy) in [
+            (&ROOK_MAGICS, (sliders.rooks() | sliders.queens()) & ROOK_MAGICS.get_attacks(king_square_shift, 0)),
+            (&BISHOP_MAGICS, (sliders.bishops() | sliders.queens()) & BISHOP_MAGICS.get_attacks(king_square_shift, 0)),
+        ] {
+            while slider_occupancy != 0 {
+                let (slider_square_mask, slider_square_shift) = mask_and_shift_from_lowest_one_bit(slider_occupancy);
+                slider_occupancy &= !slider_square_mask;
+
+                let between = magics.get_attacks(king_square_shift, slider_square_mask) & magics.get_attacks(slider_square_shift, square_mask_from_shift(king_square_shift));
+                let blockers = between & full_occupancy;
+
+                if blockers.count_ones() == 1 && (blockers & blocker_occupancy) != 0 {
+                    result.push((blockers.trailing_zeros(), slider_square_shift));
+                }
+            }
+        }
+
+        result
+    }
+
+    const fn square(square_shift: SquareShiftBits) -> Square {
+        Square::from_index_unchecked(square_shift as usize)
+    }
+}
+
+// Threats
+impl Bitboard {
+    /// Returns all squares attacked by the pieces of `color`. Absolutely pinned pieces only attack along the line of their pin.
+    pub fn defended_squares(&self, color: &Color) -> OccupancyBits {
+        let active = if color.index == WHITE { &self.white } else { &self.black };
+        let full_occupancy = self.white.full_occupancy() | self.black.full_occupancy();
+        let pins = self.absolute_pins(color);
+
+        let mut result = 0;
+
+        for piece in PAWN..=KING {
+            let mut occupancy = active.occupancy(piece);
+
+            while occupancy != 0 {
+                let (square_mask, square_shift) = mask_and_shift_from_lowest_one_bit(occupancy);
+                occupancy &= !square_mask;
+
+                let pin_line = pins.iter()
+                    .find(|(pinned, _, _)| pinned.shift == square_shift)
+                    .map_or(!0, |(_, king, pinner)| Self::between(king.shift, pinner.shift) | pinner.mask);
+
+                result |= Self::piece_attacks(color.index, piece, square_shift, full_occupancy) & pin_line;
+            }
+        }
+
+        result
+    }
+
+    /// Returns all pieces of `color`, except the king, that are attacked by the enemy and not defended by a friendly piece.
+    pub fn hanging_pieces(&self, color: &Color) -> Vec<Square> {
+        let active = if color.index == WHITE { &self.white } else { &self.black };
+        let opponent = Color::from_index_unchecked(opposite_color(color.index) as usize);
+
+        let mut hanging = active.full_occupancy() & !active.kings() & self.defended_squares(&opponent) & !self.defended_squares(color);
+        let mut result = Vec::new();
+
+        while hanging != 0 {
+            let (square_mask, square_shift) = mask_and_shift_from_lowest_one_bit(hanging);
+            hanging &= !square_mask;
+            result.push(Self::square(square_shift));
+        }
+
+        result
+    }
+
+    fn piece_attacks(color_bits: ColorBits, piece: PieceBits, square_shift: SquareShiftBits, full_occupancy: OccupancyBits) -> OccupancyBits {
+        match piece {
+            PAWN if color_bits == WHITE => unsafe { WHITE_PAWN_NONMAGICS.get_attacks(square_shift) },
+            PAWN => unsafe { BLACK_PAWN_NONMAGICS.get_attacks(square_shift) },
+            KNIGHT => unsafe { KNIGHT_NONMAGICS.get_attacks(square_shift) },
+            BISHOP => BISHOP_MAGICS.get_attacks(square_shift, full_occupancy),
+            ROOK => ROOK_MAGICS.get_attacks(square_shift, full_occupancy),
+            QUEEN => BISHOP_MAGICS.get_attacks(square_shift, full_occupancy) | ROOK_MAGICS.get_attacks(square_shift, full_occupancy),
+            KING => unsafe { KING_NONMAGICS.get_attacks(square_shift) },
+            _ => 0,
+        }
+    }
+
+    /// Returns the squares strictly between two squares sharing a rank, file or diagonal.
+    fn between(square_shift_a: SquareShiftBits, square_shift_b: SquareShiftBits) -> OccupancyBits {
+        let magics = if square_shift_a / 8 == square_shift_b / 8 || square_shift_a % 8 == square_shift_b % 8 { &ROOK_MAGICS } else { &BISHOP_MAGICS };
+
+        magics.get_attacks(square_shift_a, square_mask_from_shift(square_shift_b)) & magics.get_attacks(square_shift_b, square_mask_from_shift(square_shift_a))
+    }
+}
+
+// Zobrist
+impl Bitboard {
+    /// Calculate the zobrist xor difference and zobrist pawn xor difference for a move
+    pub fn zobrist_xor(mv: Move) -> (ZobristHash, ZobristHash) {
+        let mut result: ZobristHash = 0;
+        let mut pawn_result: ZobristHash = 0;
+
+        let self_color = mv.get_side_to_move();
+        let opponent_color = opposite_color(self_color);
+
+        pawn_result ^= Zobrist::BLACK_TO_MOVE_HASH;
+
+        if mv.is_self_lost_king_side_castle() {
+            result ^= Zobrist::castle_hash(KING, self_color);
+        }
+
+        if mv.is_self_lost_queen_side_castle() {
+            result ^= Zobrist::castle_hash(QUEEN, self_color);
+        }
+
+        if mv.is_opponent_lost_king_side_castle() {
+            result ^= Zobrist::castle_hash(KING, opponent_color);
+        }
+
+        if mv.is_opponent_lost_queen_side_castle() {
+            result ^= Zobrist::castle_hash(QUEEN, opponent_color);
+        }
+
+        if mv.get_previous_en_passant_square() != NO_SQUARE {
+            pawn_result ^= Zobrist::en_passant_square_hash(mv.get_previous_en_passant_square());
+        }
+
+        if mv.get_next_en_passant_square() != NO_SQUARE {
+            pawn_result ^= Zobrist::en_passant_square_hash(mv.get_next_en_passant_square());
+        }
+
+        let is_white_turn = self_color == WHITE;
+
+        let piece_moved = mv.get_piece_moved();
+        let piece_promoted = mv.get_promotion_piece();
+        let piece_attacked = mv.get_piece_attacked();
         let source_square_shift = mv.get_source_square();
         let target_square_shift = mv.get_target_square();
 
         if mv.is_castle_move() {
-            let (rook_source_shift, king_source_shift, rook_target_shift, king_target_shift) = match target_square_shift {
-                C1 => (A1, E1, D1, C1),
-                G1 => (H1, E1, F1, G1),
-                C8 => (A8, E8, D8, C8),
-                G8 => (H8, E8, F8, G8),
-                _ => panic!(),
-            };
+            let (rook_source_shift, rook_target_shift) = Self::castle_rook_square_shifts(mv);
 
             result ^= Zobrist::piece_square_hash(ROOK, rook_source_shift, self_color);
             result ^= Zobrist::piece_square_hash(ROOK, rook_target_shift, self_color);
-            result ^= Zobrist::piece_square_hash(KING, king_source_shift, self_color);
-            result ^= Zobrist::piece_square_hash(KING, king_target_shift, self_color);
+            result ^= Zobrist::piece_square_hash(KING, source_square_shift, self_color);
+            result ^= Zobrist::piece_square_hash(KING, target_square_shift, self_color);
         } else if mv.is_en_passant_attack() {
             pawn_result ^= Zobrist::piece_square_hash(PAWN, source_square_shift, self_color);
             pawn_result ^= Zobrist::piece_square_hash(PAWN, target_square_shift, self_color);
@@ -984,6 +1727,38 @@ impl Bitboard {
         (result ^ pawn_result, pawn_result)
     }
 
+    pub const fn zobrist_hash(&self) -> ZobristHash {
+        self.zobrist_hash
+    }
+
+    pub const fn pawn_zobrist_hash(&self) -> ZobristHash {
+        self.zobrist_pawn_hash
+    }
+
+    /// Toggle the changes of `mv` in the running hashes, which is the same for make and unmake
+    fn apply_zobrist_xor(&mut self, mv: Move) {
+        let (xor, pawn_xor) = Self::zobrist_xor(mv);
+        self.zobrist_hash ^= xor;
+        self.zobrist_pawn_hash ^= pawn_xor;
+    }
+
+    /// Toggle the side to move and the en passant square `en_passant_square_shift` in the running hashes
+    fn apply_null_move_zobrist_xor(&mut self, en_passant_square_shift: SquareShiftBits) {
+        let mut xor = Zobrist::BLACK_TO_MOVE_HASH;
+
+        if en_passant_square_shift != NO_SQUARE {
+            xor ^= Zobrist::en_passant_square_hash(en_passant_square_shift);
+        }
+
+        self.zobrist_hash ^= xor;
+        self.zobrist_pawn_hash ^= xor;
+    }
+
+    fn recalculate_zobrist_hashes(&mut self) {
+        self.zobrist_hash = self.calculate_zobrist_hash();
+        self.zobrist_pawn_hash = self.calculate_zobrist_pawn_hash();
+    }
+
     /// Calculate the zobrist hash for the current state from scratch
     pub const fn calculate_zobrist_hash(&self) -> ZobristHash {
         Self::_zobrist_hash(&self.white, &self.black, self.turn, self.en_passant_square_shift)
@@ -1053,12 +1828,178 @@ impl Bitboard {
     }
 }
 
+// Material
+impl Bitboard {
+    /// Returns whether a hundred halfmoves passed without a capture or pawn move.
+    pub const fn is_fifty_move_draw(&self) -> bool {
+        self.halfmove_clock >= 100
+    }
+
+    /// Returns whether the position is drawn by the fifty-move rule or insufficient material.
+    pub fn is_draw(&self) -> bool {
+        self.is_fifty_move_draw() || self.is_insufficient_material()
+    }
+
+    /// Like [`Bitboard::is_draw`], additionally drawn if `is_repetition` as the board does not track previous positions.
+    pub fn is_draw_with_repetition(&self, is_repetition: bool) -> bool {
+        is_repetition || self.is_draw()
+    }
+
+    /// Returns the status of the game. Checkmate takes precedence over the fifty-move rule, repetitions are not detected
+    /// as the board does not track previous positions.
+    pub fn status(&mut self) -> GameStatus {
+        let moves = self.generate_pseudo_legal_moves();
+
+        if !self.is_any_move_legal(&moves) {
+            if self.is_current_in_check() { GameStatus::Checkmate } else { GameStatus::Stalemate }
+        } else if self.is_insufficient_material() {
+            GameStatus::DrawInsufficientMaterial
+        } else if self.is_fifty_move_draw() {
+            GameStatus::DrawFiftyMove
+        } else if self.is_current_in_check() {
+            GameStatus::Check
+        } else {
+            GameStatus::Ongoing
+        }
+    }
+
+    /// Returns whether neither side can possibly deliver checkmate, i.e. only kings remain besides either a single
+    /// knight or any number of bishops all standing on squares of the same color.
+    pub fn is_insufficient_material(&self) -> bool {
+        let heavy_pieces_and_pawns = [&self.white, &self.black].iter()
+            .map(|player_state| player_state.pawns() | player_state.rooks() | player_state.queens())
+            .fold(0, |acc, occupancy| acc | occupancy);
+
+        if heavy_pieces_and_pawns != 0 {
+            return false;
+        }
+
+        let knights = self.white.knights() | self.black.knights();
+        let bishops = self.white.bishops() | self.black.bishops();
+
+        if knights == 0 {
+            bishops & LIGHT_SQUARES_OCCUPANCY == 0 || bishops & DARK_SQUARES_OCCUPANCY == 0
+        } else {
+            bishops == 0 && knights.count_ones() == 1
+        }
+    }
+
+    /// Material value of the pieces of `color` without its king
+    pub const fn material(&self, color: Color) -> i32 {
+        self.material[color.index as usize]
+    }
+
+    /// Game phase in `0..=24`, 0 being early game and 24 being end game, as the taper factor of the piece square tables.
+    /// Knights and bishops count 1, rooks 2 and queens 4 towards the early game.
+    pub const fn phase(&self) -> u8 {
+        let phase = Self::TOTAL_PHASE_WEIGHT - self.phase_weight;
+
+        if phase < 0 { 0 } else { phase as u8 }
+    }
+
+    fn recalculate_material_and_phase(&mut self) {
+        self.material = [Self::calculate_material(&self.white), Self::calculate_material(&self.black)];
+        self.phase_weight = self.calculate_phase_weight();
+    }
+
+    /// Calculate the material of `player_state` from scratch
+    fn calculate_material(player_state: &PlayerState) -> i32 {
+        (PAWN..=QUEEN).map(|piece| player_state.occupancy(piece).count_ones() as i32 * Self::PIECE_VALUES[piece as usize]).sum()
+    }
+
+    /// Calculate the phase weight of both sides from scratch
+    fn calculate_phase_weight(&self) -> i32 {
+        [&self.white, &self.black].iter()
+            .flat_map(|player_state| (PAWN..=QUEEN).map(|piece| player_state.occupancy(piece).count_ones() as i32 * Self::PHASE_WEIGHTS[piece as usize]))
+            .sum()
+    }
+
+    /// Material value of the knights, bishops, rooks and queens of both sides
+    pub fn non_pawn_material(&self) -> i32 {
+        [&self.white, &self.black].iter()
+            .flat_map(|player_state| (KNIGHT..=QUEEN).map(|piece| player_state.occupancy(piece).count_ones() as i32 * Self::PIECE_VALUES[piece as usize]))
+            .sum()
+    }
+
+    /// Per side piece counts excluding kings, packed into four bits per piece. Independent of piece placement.
+    pub fn material_signature(&self) -> u64 {
+        let mut result = 0;
+
+        for (color, player_state) in [(WHITE, &self.white), (BLACK, &self.black)] {
+            for piece in PAWN..=QUEEN {
+                let count = u64::from(player_state.occupancy(piece).count_ones().min(0b1111));
+                result |= count << (4 * (5 * u64::from(color) + piece - PAWN));
+            }
+        }
+
+        result
+    }
+
+    /// Cheap hash of [`Bitboard::material_signature`] for keying material configuration tables
+    pub fn material_hash(&self) -> u32 {
+        (self.material_signature().wrapping_mul(0x9e37_79b9_7f4a_7c15) >> 32) as u32
+    }
+}
+
 // Helpers
 impl Bitboard {
     pub const fn ply_clock(&self) -> u16 {
         (2 * (self.fullmove_clock - 1) + self.turn) as u16
     }
 
+    pub fn castling_rights(&self) -> CastlingRights {
+        let mut result = CastlingRights::NONE;
+
+        for (color, player_state) in [(WHITE, &self.white), (BLACK, &self.black)] {
+            if player_state.king_side_castle {
+                result.add(color, KING);
+            }
+            if player_state.queen_side_castle {
+                result.add(color, QUEEN);
+            }
+        }
+
+        result
+    }
+
+    /// The castling availability field of a FEN, with the files of the castling rooks (Shredder-FEN) in Chess960
+    pub fn castling_availability(&self) -> String {
+        if !self.chess960 {
+            return self.castling_rights().to_fen();
+        }
+
+        let mut result = String::new();
+
+        for (player_state, base) in [(&self.white, 'A'), (&self.black, 'a')] {
+            if player_state.king_side_castle {
+                result.push((base as u8 + player_state.king_side_rook_file as u8) as char);
+            }
+            if player_state.queen_side_castle {
+                result.push((base as u8 + player_state.queen_side_rook_file as u8) as char);
+            }
+        }
+
+        if result.is_empty() { "-".to_string() } else { result }
+    }
+
+    pub const fn is_chess960(&self) -> bool {
+        self.chess960
+    }
+
+    /// Switch between the standard and Chess960 notation of castling rights and castle moves. Castling itself works for
+    /// any starting files either way.
+    pub fn set_chess960(&mut self, chess960: bool) {
+        self.chess960 = chess960;
+    }
+
+    pub fn set_castling_rights(&mut self, castling_rights: CastlingRights) {
+        self.white.king_side_castle = castling_rights.has(WHITE, KING);
+        self.white.queen_side_castle = castling_rights.has(WHITE, QUEEN);
+        self.black.king_side_castle = castling_rights.has(BLACK, KING);
+        self.black.queen_side_castle = castling_rights.has(BLACK, QUEEN);
+        self.zobrist_hash = self.calculate_zobrist_hash();
+    }
+
     #[inline(always)]
     const fn is_white_turn(&self) -> bool {
         self.turn == WHITE
@@ -1083,12 +2024,17 @@ impl Bitboard {
     }
 
     pub fn get_colored_piece(&self, square: Square) -> Option<ColoredPiece> {
+        self.piece_on(square).map(|(piece, color)| piece.to_color(color))
+    }
+
+    /// The piece on `square` and its color
+    pub fn piece_on(&self, square: Square) -> Option<(Piece, Color)> {
         let maybe_white = self.white.find_piece_struct_by_square_mask(square.mask);
         let maybe_black = self.black.find_piece_struct_by_square_mask(square.mask);
 
         match (maybe_white, maybe_black) {
-            (Some(piece), None) => Some(piece.to_white()),
-            (None, Some(piece)) => Some(piece.to_black()),
+            (Some(piece), None) => Some((piece, Color::WHITE)),
+            (None, Some(piece)) => Some((piece, Color::BLACK)),
             (None, None) => None,
             (Some(_), Some(_)) => panic!(),
         }
@@ -1122,15 +2068,24 @@ impl Bitboard {
 
     #[allow(clippy::wrong_self_convention)]
     pub fn is_any_move_non_quiescent(moves: &[Move]) -> bool {
-        moves.iter().any(|mv| mv.is_attack() || mv.is_promotion())
+        moves.iter().any(|mv| !mv.is_quiet())
     }
 
+    /// Node count of every legal move at `depth`
     pub fn perft(&mut self, depth: usize) -> Vec<(Move, u64)> {
         let mut result = Vec::new();
 
+        if depth == 0 {
+            return result;
+        }
+
         let mut buffer = Vec::new();
         self.generate_pseudo_legal_moves_with_buffer(&mut buffer);
 
+        if depth == 1 {
+            return buffer.into_iter().filter(|&mv| self.is_move_legal(mv)).map(|mv| (mv, 1)).collect();
+        }
+
         let mut next_buffer = Vec::new();
         for mv in buffer {
             self.make(mv);
@@ -1146,11 +2101,54 @@ impl Bitboard {
         result
     }
 
+    /// Node count of every legal move at `depth`, labeled with its UCI notation and sorted by it
+    pub fn perft_divide(&mut self, depth: usize) -> Vec<(String, u64)> {
+        let mut result = self.perft(depth).into_iter().map(|(mv, count)| (self.move_to_uci_string(mv), count)).collect::<Vec<_>>();
+        result.sort();
+        result
+    }
+
     fn _perft(&mut self, buffer: &mut Vec<Move>, depth: usize) -> u64 {
         if depth == 0 {
             return 1;
         }
 
+        self.generate_pseudo_legal_moves_with_buffer(buffer);
+
+        // Count the legal moves of the leaf layer directly instead of recursing into it
+        if depth == 1 {
+            return buffer.iter().filter(|&&mv| self.is_move_legal(mv)).count() as u64;
+        }
+
+        let mut count = 0;
+        let mut next_buffer = Vec::new();
+        for mv in buffer {
+            self.make(*mv);
+
+            if self.is_valid() {
+                count += self._perft(&mut next_buffer, depth - 1);
+                next_buffer.clear();
+            }
+
+            self.unmake(*mv);
+        }
+
+        count
+    }
+
+    /// Perft that asserts the incrementally updated zobrist hashes match the calculated ones at every node
+    pub fn perft_verified(&mut self, depth: usize) -> u64 {
+        self._perft_verified(&mut Vec::new(), depth)
+    }
+
+    fn _perft_verified(&mut self, buffer: &mut Vec<Move>, depth: usize) -> u64 {
+        assert_eq!(self.zobrist_hash(), self.calculate_zobrist_hash(), "zobrist hash diverged at {}", Fen::from(&*self).fen);
+        assert_eq!(self.pawn_zobrist_hash(), self.calculate_zobrist_pawn_hash(), "zobrist pawn hash diverged at {}", Fen::from(&*self).fen);
+
+        if depth == 0 {
+            return 1;
+        }
+
         let mut count = 0;
         let mut next_buffer = Vec::new();
         self.generate_pseudo_legal_moves_with_buffer(buffer);
@@ -1158,7 +2156,7 @@ impl Bitboard {
             self.make(*mv);
 
             if self.is_valid() {
-                count += self._perft(&mut next_buffer, depth - 1);
+                count += self._perft_verified(&mut next_buffer, depth - 1);
                 next_buffer.clear();
             }
 
@@ -1171,9 +2169,19 @@ impl Bitboard {
 
 // UCI and PGN conversions
 impl Bitboard {
+    /// UCI notation of `mv`. In Chess960, castle moves are notated as the king capturing its own rook.
+    pub fn move_to_uci_string(&self, mv: Move) -> String {
+        if self.chess960 && mv.is_castle_move() {
+            let (rook_source_square_shift, _) = Self::castle_rook_square_shifts(mv);
+            format!("{}{}", square_to_string(mv.get_source_square()), square_to_string(rook_source_square_shift))
+        } else {
+            mv.to_uci_string()
+        }
+    }
+
     pub fn find_uci(&mut self, uci: &str) -> Result<Move, MoveFromUciError> {
         let uci = uci.trim();
-        let result = self.generate_pseudo_legal_moves().into_iter().find(|mv| mv.to_uci_string() == uci).ok_or_else(|| MoveDoesNotExist(uci.to_string()))?;
+        let result = self.generate_pseudo_legal_moves().into_iter().find(|mv| self.move_to_uci_string(*mv) == uci).ok_or_else(|| MoveDoesNotExist(uci.to_string()))?;
 
         self.make(result);
         if !self.is_valid() {
@@ -1184,8 +2192,36 @@ impl Bitboard {
         Ok(result)
     }
 
-    pub fn make_uci(&mut self, uci: &str) -> Result<(), MoveFromUciError> {
-        let mv = self.find_uci(uci)?;
+    /// The legal move matching the source, target and promotion of `uci`, without formatting moves to UCI strings. In
+    /// Chess960, castle moves are matched as the king capturing its own rook.
+    pub fn resolve_uci_move(&mut self, uci: &UciMove) -> Result<Move, MoveFromUciError> {
+        let source_square_shift = uci.source.shift;
+        let target_square_shift = uci.target.shift;
+        let promotion_piece = uci.promote_to.map_or(NO_PIECE, |piece| PieceBits::from(piece.index));
+
+        let result = self.generate_pseudo_legal_moves()
+            .into_iter()
+            .find(|&mv| mv.get_source_square() == source_square_shift && self.uci_target_square(mv) == target_square_shift && mv.get_promotion_piece() == promotion_piece)
+            .ok_or_else(|| MoveDoesNotExist(uci.to_string()))?;
+
+        if !self.is_move_legal(result) {
+            return Err(MoveIsNotValid(result));
+        }
+
+        Ok(result)
+    }
+
+    /// Target square of `mv` in UCI notation, see [`Self::move_to_uci_string`]
+    fn uci_target_square(&self, mv: Move) -> SquareShiftBits {
+        if self.chess960 && mv.is_castle_move() {
+            Self::castle_rook_square_shifts(mv).0
+        } else {
+            mv.get_target_square()
+        }
+    }
+
+    pub fn make_uci(&mut self, uci: &str) -> Result<(), MoveFromUciError> {
+        let mv = self.find_uci(uci)?;
         self.make(mv);
 
         Ok(())
@@ -1212,15 +2248,80 @@ impl Bitboard {
         Ok(())
     }
 
+    /// Find the legal move of `san` in the current position, see [`Bitboard::pgn_to_bb`]
+    pub fn find_san(&mut self, san: &str) -> Result<Move, PgnParseError> {
+        self.pgn_to_bb(san.trim())
+    }
+
+    pub fn make_san(&mut self, san: &str) -> Result<(), PgnParseError> {
+        let mv = self.find_san(san)?;
+        self.make(mv);
+
+        Ok(())
+    }
+
+    /// Make all moves of `moves` in SAN. If any move can't be made, the board is left unchanged.
+    pub fn make_all_san(&mut self, moves: &[String]) -> Result<(), PgnParseError> {
+        let mut potential_unmake = Vec::new();
+
+        for san in moves {
+            match self.find_san(san) {
+                Ok(mv) => {
+                    self.make(mv);
+                    potential_unmake.push(mv);
+                }
+                Err(error) => {
+                    for mv in potential_unmake.iter().rev() {
+                        self.unmake(*mv);
+                    }
+                    return Err(error);
+                }
+            };
+        }
+
+        Ok(())
+    }
+
+    /// Convert a line of consecutive UCI moves, starting from the current position, to PGN. The board is left unchanged.
+    pub fn uci_line_to_pgn(&mut self, moves: &[String]) -> Result<Vec<String>, MoveFromUciError> {
+        let mut made = Vec::new();
+        let mut pgns = Vec::with_capacity(moves.len());
+        let mut error = None;
+
+        for uci in moves {
+            let uci = uci.trim();
+            let legal_moves = self.generate_legal_moves();
+
+            match legal_moves.iter().find(|mv| self.move_to_uci_string(**mv) == uci) {
+                Some(&mv) => {
+                    pgns.push(self.san_with_context(mv, &legal_moves));
+                    self.make(mv);
+                    made.push(mv);
+                }
+                None => {
+                    let pseudo_legal_move = self.generate_pseudo_legal_moves().into_iter().find(|mv| self.move_to_uci_string(*mv) == uci);
+                    error = Some(pseudo_legal_move.map_or_else(|| MoveDoesNotExist(uci.to_string()), MoveIsNotValid));
+                    break;
+                }
+            }
+        }
+
+        for mv in made.iter().rev() {
+            self.unmake(*mv);
+        }
+
+        error.map_or(Ok(pgns), Err)
+    }
+
     pub fn pgn_to_bb(&mut self, pgn: &str) -> Result<Move, PgnParseError> {
         let result = if let Some(captures) = PGN_REGEX.captures(pgn) {
-            let moves = self.generate_legal_moves();
+            let moves = self.generate_pseudo_legal_moves();
 
             if let Some(piece) = captures.name("piece") {
                 let takes = captures.name("takes");
                 let from_rank = captures.name("from_rank");
                 let from_file = captures.name("from_file");
-                let target = captures.name("target").ok_or(PgnParseError::Error)?;
+                let target = captures.name("target").ok_or_else(|| PgnParseError::MalformedNotation(pgn.to_string()))?;
 
 
                 let moves = moves
@@ -1352,61 +2453,77 @@ impl Bitboard {
 
                 Ok(moves)
             } else {
-                Err(PgnParseError::Error)
+                Err(PgnParseError::MalformedNotation(pgn.to_string()))
             }
         } else {
-            Err(PgnParseError::Error)
+            Err(PgnParseError::MalformedNotation(pgn.to_string()))
         };
 
-        match result {
-            Ok(result) => {
-                let moves = result.into_iter().filter(|mv| self.is_move_legal(*mv)).collect::<Vec<_>>();
-                if moves.len() != 1 {
-                    return Err(PgnParseError::Error);
-                }
-                Ok(moves[0])
-            }
-            Err(err) => Err(err)
+        let matching_moves = result?;
+
+        if matching_moves.is_empty() {
+            return Err(PgnParseError::NoMatchingMove(pgn.to_string()));
+        }
+
+        let moves = matching_moves.into_iter().filter(|mv| self.is_move_legal(*mv)).collect::<Vec<_>>();
+
+        match moves.len() {
+            0 => Err(PgnParseError::IllegalMove(pgn.to_string())),
+            1 => Ok(moves[0]),
+            count => Err(PgnParseError::AmbiguousMove(pgn.to_string(), count)),
         }
     }
 
-    #[allow(clippy::unwrap_used)]
     pub fn uci_to_pgn(&mut self, uci: &str) -> Result<String, MoveFromUciError> {
         let uci = uci.trim();
         let moves = self.generate_pseudo_legal_moves();
-        let result = *moves.iter().find(|mv| mv.to_uci_string() == uci).ok_or_else(|| MoveDoesNotExist(uci.to_string()))?;
+        let result = *moves.iter().find(|mv| self.move_to_uci_string(**mv) == uci).ok_or_else(|| MoveDoesNotExist(uci.to_string()))?;
 
-        self.make(result);
-        if !self.is_valid() {
+        if !self.is_move_legal(result) {
             return Err(MoveIsNotValid(result));
         }
 
+        let legal_moves = moves.into_iter().filter(|&mv| self.is_move_legal(mv)).collect::<Vec<_>>();
+
+        Ok(self.san_with_context(result, &legal_moves))
+    }
+
+    /// All legal moves of the current position with their SAN, generating the legal moves for disambiguation only once
+    pub fn san_moves(&mut self) -> Vec<(Move, String)> {
+        let legal_moves = self.generate_legal_moves();
+
+        legal_moves.iter().map(|&mv| (mv, self.san_with_context(mv, &legal_moves))).collect()
+    }
+
+    /// Convert the legal move `mv` to SAN, using the already generated `legal_moves` of the current position for disambiguation.
+    /// The board is left unchanged.
+    #[allow(clippy::unwrap_used)]
+    pub fn san_with_context(&mut self, mv: Move, legal_moves: &[Move]) -> String {
+        self.make(mv);
         let is_check = self.is_current_in_check();
         let is_mate = !self.is_any_move_legal(&self.generate_pseudo_legal_moves());
-        self.unmake(result);
-
+        self.unmake(mv);
 
-        let MoveStructs { from_square, to_square, from_piece, to_piece, promote_to } = MoveStructs::from(result);
+        let MoveStructs { from_square, to_square, from_piece, to_piece, promote_to } = MoveStructs::from(mv);
 
         let legal_moves_with_same_to_square_and_same_piece: Vec<_> =
-            moves
-                .into_iter()
-                .filter(|&mv| self.is_move_legal(mv))
-                .filter(|mv| mv.get_target_square() == result.get_target_square())
-                .filter(|mv| mv.get_piece_moved() == result.get_piece_moved())
+            legal_moves
+                .iter()
+                .filter(|other| other.get_target_square() == mv.get_target_square())
+                .filter(|other| other.get_piece_moved() == mv.get_piece_moved())
                 .collect();
 
         let any_share_source_rank =
             legal_moves_with_same_to_square_and_same_piece.iter()
-                .any(|mv| {
-                    let other_square = Square::from_index(mv.get_source_square() as usize).unwrap();
+                .any(|other| {
+                    let other_square = Square::from_index(other.get_source_square() as usize).unwrap();
                     other_square.rank == from_square.rank && other_square.file != from_square.file
                 });
 
         let any_share_source_file =
             legal_moves_with_same_to_square_and_same_piece.iter()
-                .any(|mv| {
-                    let other_square = Square::from_index(mv.get_source_square() as usize).unwrap();
+                .any(|other| {
+                    let other_square = Square::from_index(other.get_source_square() as usize).unwrap();
                     other_square.file == from_square.file && other_square.rank != from_square.rank
                 });
 
@@ -1420,8 +2537,12 @@ impl Bitboard {
         };
         let is_pawn_move = from_piece == Piece::PAWN;
 
+        // Another piece of the same kind can reach the target square, even if it shares neither file nor rank
+        let is_ambiguous = legal_moves_with_same_to_square_and_same_piece.len() > 1;
+
         let disambiguation_symbol = match (any_share_source_file, any_share_source_rank, is_pawn_move) {
-            (true, _, true) | (false, true, false) => { from_square.file.fen.to_string() }
+            (true, _, true) => { from_square.file.fen.to_string() }
+            (false, _, false) if is_ambiguous => { from_square.file.fen.to_string() }
             (true, true, false) => { format!("{}{}", from_square.file.fen, from_square.rank.fen) }
             (true, false, false) => { from_square.rank.fen.to_string() }
             (_, _, _) => { String::new() }
@@ -1432,44 +2553,19 @@ impl Bitboard {
         let promotion_piece = promotion_piece.map_or_else(String::new, |p| format!("={}", p.fen));
         let check_str = if is_mate { "#" } else if is_check { "+" } else { "" };
 
-        if matches!(from_piece, Piece::KING) {
-            let castle_move = match (from_square.file, to_square.file) {
-                (File::FILE_E, File::FILE_G) => Some("O-O"),
-                (File::FILE_E, File::FILE_C) => Some("O-O-O"),
-                _ => None
-            };
+        if mv.is_castle_move() {
+            let castle_move = if to_square.file == File::FILE_G { "O-O" } else { "O-O-O" };
 
-            if let Some(castle_move) = castle_move {
-                return Ok(format!("{}{}", castle_move, check_str));
-            }
+            return format!("{}{}", castle_move, check_str);
         }
 
-        Ok(format!("{}{}{}{}{}{}", piece, disambiguation_symbol, capture, target_square, promotion_piece, check_str))
+        format!("{}{}{}{}{}{}", piece, disambiguation_symbol, capture, target_square, promotion_piece, check_str)
     }
 }
 
 impl Display for Move {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "Move({}) {{ piece_moved = {}, piece_attacked = {}, self_lost_king_side_castle = {}, self_lost_queen_side_castle = {}, opponent_lost_king_side_castle = {}, opponent_lost_queen_side_castle = {}, castle_move = {}, en_passant_attack = {}, source_square = {}, target_square = {}, halfmove_reset = {}, previous_halfmove = {}, previous_en_passant_square = {}, next_en_passant_square = {}, promotion_piece = {}}}",
-            self.to_uci_string(),
-            piece_to_string(self.get_piece_moved()),
-            piece_to_string(self.get_piece_attacked()),
-            self.is_self_lost_king_side_castle(),
-            self.is_self_lost_queen_side_castle(),
-            self.is_opponent_lost_king_side_castle(),
-            self.is_opponent_lost_queen_side_castle(),
-            self.is_castle_move(),
-            self.is_en_passant_attack(),
-            square_to_string(self.get_source_square()),
-            square_to_string(self.get_target_square()),
-            self.is_halfmove_reset(),
-            self.get_previous_halfmove(),
-            square_to_string(self.get_previous_en_passant_square()),
-            square_to_string(self.get_next_en_passant_square()),
-            piece_to_string(self.get_promotion_piece()),
-        )
+        write!(f, "{}", self.to_verbose_string())
     }
 }
 
@@ -1479,6 +2575,14 @@ impl Default for Bitboard {
     }
 }
 
+/// Hashes the zobrist hash of the position. Equal boards hash equally, but boards only differing in their clocks
+/// collide.
+impl Hash for Bitboard {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.zobrist_hash.hash(state);
+    }
+}
+
 trait FenParseExt {
     fn parse_player_states(&self) -> (PlayerState, PlayerState);
     fn parse_turn(&self) -> ColorBits;
@@ -1519,10 +2623,13 @@ impl FenParseExt for Fen {
             }
         });
 
-        white.queen_side_castle = self.get_castling_availability().contains('Q');
-        white.king_side_castle = self.get_castling_availability().contains('K');
-        black.queen_side_castle = self.get_castling_availability().contains('q');
-        black.king_side_castle = self.get_castling_availability().contains('k');
+        for symbol in self.get_castling_availability().chars() {
+            if symbol.is_ascii_uppercase() {
+                white.add_castling_right(symbol, A1);
+            } else {
+                black.add_castling_right(symbol, A8);
+            }
+        }
 
         (white, black)
     }
@@ -1550,14 +2657,29 @@ impl From<&Fen> for Bitboard {
     fn from(fen: &Fen) -> Self {
         let (white, black) = fen.parse_player_states();
 
-        Self {
+        let mut result = Self {
             white,
             black,
             turn: fen.parse_turn(),
             en_passant_square_shift: fen.parse_en_passant_square_shift(),
             fullmove_clock: fen.parse_fullmove_clock(),
             halfmove_clock: fen.parse_halfmove_clock(),
-        }
+            checkers: 0,
+            zobrist_hash: 0,
+            zobrist_pawn_hash: 0,
+            material: [0; 2],
+            phase_weight: 0,
+            chess960: false,
+        };
+
+        result.checkers = result.calculate_checkers();
+        result.recalculate_zobrist_hashes();
+        result.recalculate_material_and_phase();
+        result.chess960 = fen.get_castling_availability().chars().any(|symbol| matches!(symbol.to_ascii_uppercase(), 'A'..='H'))
+            || result.white.has_chess960_castling_rights(A1)
+            || result.black.has_chess960_castling_rights(A8);
+
+        result
     }
 }
 
@@ -1604,18 +2726,7 @@ impl From<&Bitboard> for Fen {
         result.push(if bitboard.is_white_turn() { 'w' } else { 'b' });
         result.push(' ');
 
-        let castle = [
-            ('K', bitboard.white.king_side_castle),
-            ('Q', bitboard.white.queen_side_castle),
-            ('k', bitboard.black.king_side_castle),
-            ('q', bitboard.black.queen_side_castle)
-        ].iter().filter(|t| t.1).map(|t| t.0).collect::<String>();
-
-        if castle.is_empty() {
-            result.push('-');
-        } else {
-            result.push_str(&castle);
-        };
+        result.push_str(&bitboard.castling_availability());
 
         result.push(' ');
 
@@ -1636,6 +2747,110 @@ impl From<&Bitboard> for Fen {
 
 // Instantiation
 impl Bitboard {
+    /// The standard starting position, same as [`Bitboard::default`].
+    pub fn startpos() -> Self {
+        Self::default()
+    }
+
+    /// A board without any pieces, white to move and without castling rights. Place pieces with
+    /// [`Bitboard::set_piece`] and check the result with [`Bitboard::validate`].
+    pub fn empty() -> Self {
+        #[allow(clippy::unwrap_used)]
+        Self::from_player_states(PlayerState::default(), PlayerState::default(), Color::WHITE, None).unwrap()
+    }
+
+    /// Put `piece` of `color` on `square`, replacing any piece already there
+    pub fn set_piece(&mut self, piece: Piece, color: Color, square: Square) {
+        self.clear_square(square);
+
+        let player_state = if color.index == WHITE { &mut self.white } else { &mut self.black };
+        player_state.set_piece(piece, square);
+
+        self.recalculate_derived_state();
+    }
+
+    /// Remove the piece on `square`, if any
+    pub fn clear_square(&mut self, square: Square) {
+        self.white.clear_square(square);
+        self.black.clear_square(square);
+
+        self.recalculate_derived_state();
+    }
+
+    fn recalculate_derived_state(&mut self) {
+        self.checkers = self.calculate_checkers();
+        self.recalculate_zobrist_hashes();
+        self.recalculate_material_and_phase();
+    }
+
+    /// A board with only the two kings, white to move. Returns `None` if the kings share a square or are adjacent.
+    pub fn empty_with_kings(white_king: Square, black_king: Square) -> Option<Self> {
+        if white_king.distance(black_king) <= 1 {
+            return None;
+        }
+
+        let mut white = PlayerState::default();
+        white.set_piece(Piece::KING, white_king);
+
+        let mut black = PlayerState::default();
+        black.set_piece(Piece::KING, black_king);
+
+        Self::from_player_states(white, black, Color::WHITE, None)
+    }
+
+    /// A board with the pieces and castling rights of `white` and `black`, starting at the first move with `turn` to
+    /// move. Returns `None` if both players occupy the same square.
+    pub fn from_player_states(white: PlayerState, black: PlayerState, turn: Color, en_passant_square: Option<Square>) -> Option<Self> {
+        if white.full_occupancy() & black.full_occupancy() != 0 {
+            return None;
+        }
+
+        let mut result = Self {
+            white,
+            black,
+            turn: turn.index,
+            en_passant_square_shift: en_passant_square.map_or(NO_SQUARE, |square| square.shift),
+            fullmove_clock: 1,
+            halfmove_clock: 0,
+            checkers: 0,
+            zobrist_hash: 0,
+            zobrist_pawn_hash: 0,
+            material: [0; 2],
+            phase_weight: 0,
+            chess960: white.has_chess960_castling_rights(A1) || black.has_chess960_castling_rights(A8),
+        };
+
+        result.checkers = result.calculate_checkers();
+        result.recalculate_zobrist_hashes();
+        result.recalculate_material_and_phase();
+
+        Some(result)
+    }
+
+    /// The position with ranks flipped and colors swapped, so its evaluation is the negated evaluation of this position
+    pub fn mirror(&self) -> Self {
+        let mut result = Self {
+            white: self.black.mirror(),
+            black: self.white.mirror(),
+            turn: 1 - self.turn,
+            en_passant_square_shift: if self.en_passant_square_shift == NO_SQUARE { NO_SQUARE } else { self.en_passant_square_shift ^ 0b111_000 },
+            fullmove_clock: self.fullmove_clock,
+            halfmove_clock: self.halfmove_clock,
+            checkers: 0,
+            zobrist_hash: 0,
+            zobrist_pawn_hash: 0,
+            material: [0; 2],
+            phase_weight: 0,
+            chess960: self.chess960,
+        };
+
+        result.checkers = result.calculate_checkers();
+        result.recalculate_zobrist_hashes();
+        result.recalculate_material_and_phase();
+
+        result
+    }
+
     pub fn from_fen_string(fen: &str) -> Result<Self, FenParseError> {
         Fen::from_str(fen).map(Into::into)
     }
@@ -1643,6 +2858,66 @@ impl Bitboard {
     pub fn from_fen_string_unchecked(fen: &str) -> Self {
         Self::from_fen_string(fen).unwrap_or_else(|_| panic!("Illegal fen string {}", fen))
     }
+
+    /// Like [`Bitboard::from_fen_string`], but additionally verifies that a pawn of the previously moving player could
+    /// have double pushed past the en passant square. Inconsistent en passant squares are handled according to `policy`.
+    pub fn from_fen_validated(fen: &str, policy: EnPassantPolicy) -> Result<Self, FenValidationError> {
+        let mut result = Self::from_fen_string(fen).map_err(FenValidationError::FenParseError)?;
+
+        if !result.is_en_passant_square_consistent() {
+            match policy {
+                EnPassantPolicy::Reject => return Err(FenValidationError::InconsistentEnPassantSquare(square_to_string(result.en_passant_square_shift))),
+                EnPassantPolicy::Clear => result.en_passant_square_shift = NO_SQUARE,
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// The board of an EPD record and its operands by opcode. The halfmove and fullmove clocks default to `0` and `1`
+    /// if the record omits them.
+    pub fn from_epd(epd: &str) -> Result<(Self, HashMap<String, String>), EpdParseError> {
+        let epd = Epd::from_str(epd)?;
+
+        Ok((Self::from(&epd.fen), epd.operations().clone()))
+    }
+
+    /// The four position fields of this board followed by the `(opcode, operand)` pairs of `operations` as an EPD
+    /// record, string operands have to include their quotes
+    pub fn to_epd(&self, operations: &[(&str, &str)]) -> String {
+        let fen = Fen::from(self);
+        let position = format!("{} {} {} {}", fen.get_piece_placement(), fen.get_active_color(), fen.get_castling_availability(), fen.get_en_passant_target_square());
+
+        #[allow(clippy::unwrap_used)]
+        let epd = Epd::new(Fen::from_str(&position).unwrap(), operations.iter().map(|&(opcode, operand)| (opcode.to_string(), operand.to_string())).collect());
+
+        epd.to_string()
+    }
+
+    fn is_en_passant_square_consistent(&self) -> bool {
+        let en_passant_square_shift = self.en_passant_square_shift;
+
+        if en_passant_square_shift == NO_SQUARE {
+            return true;
+        }
+
+        let (en_passant_rank_occupancy, previous_player, pawn_square_shift, origin_square_shift) = if self.is_white_turn() {
+            (RANK_6_OCCUPANCY, &self.black, en_passant_square_shift + 8, en_passant_square_shift - 8)
+        } else {
+            (RANK_3_OCCUPANCY, &self.white, en_passant_square_shift - 8, en_passant_square_shift + 8)
+        };
+
+        let en_passant_square_mask = square_mask_from_shift(en_passant_square_shift);
+
+        if (en_passant_square_mask & en_passant_rank_occupancy) == 0 {
+            return false;
+        }
+
+        let full_occupancy = self.white.full_occupancy() | self.black.full_occupancy();
+        let empty_mask = en_passant_square_mask | square_mask_from_shift(origin_square_shift);
+
+        (previous_player.pawns() & square_mask_from_shift(pawn_square_shift)) != 0 && (full_occupancy & empty_mask) == 0
+    }
 }
 
 impl Display for Bitboard {
@@ -1702,16 +2977,19 @@ impl Display for Bitboard {
 
 #[cfg(test)]
 mod tests {
+    use std::collections::{HashMap, HashSet};
     use std::str::FromStr;
 
     use rand::prelude::{SliceRandom, StdRng};
     use rand::SeedableRng;
 
-    use inkayaku_core::constants::Piece;
-    use inkayaku_core::fen::Fen;
+    use inkayaku_core::constants::{Color, Piece, Rank, Square};
+    use inkayaku_core::fen::{Fen, FEN_STARTPOS_STRING};
+    use inkayaku_uci::UciMove;
 
-    use crate::board::Bitboard;
-    use crate::board::constants::PieceBits;
+    use crate::board::{Bitboard, CaptureClass, EnPassantPolicy, FenValidationError, GameStatus, Move, PgnParseError, PlayerState, PositionError};
+    use crate::board::MoveFromUciError::MoveDoesNotExist;
+    use crate::board::constants::{FILE_A_OCCUPANCY, NO_SQUARE, PieceBits, RANK_1_OCCUPANCY, RANK_2_OCCUPANCY, RANK_3_OCCUPANCY, RANK_6_OCCUPANCY};
 
     #[test]
     fn test_zobrist_consistency() {
@@ -1762,87 +3040,784 @@ mod tests {
 
 
     #[test]
-    fn test_zobrist_consistency_make_unmake() {
-        for _ in 0..1 {}
+    fn test_is_quiet() {
+        let mut board = Bitboard::from_fen_string_unchecked("4k3/1P6/8/3p4/4P3/8/8/4K1NR w - - 0 1");
+
+        for (uci, is_move_quiet, is_quiet) in [
+            ("g1f3", true, true),
+            ("e4d5", false, false),
+            ("b7b8q", false, false),
+            ("b7b8n", false, false),
+            // Quiet by its flags, but a check
+            ("h1h8", true, false),
+        ] {
+            let mv = board.find_uci(uci).unwrap();
+
+            assert_eq!(mv.is_quiet(), is_move_quiet, "{}", uci);
+            assert_eq!(board.is_quiet(mv), is_quiet, "{}", uci);
+        }
     }
 
     #[test]
-    fn test_ply_clock() {
-        let mut board = Bitboard::default();
-
-        for i in 0..100 {
-            assert_eq!(board.ply_clock(), i);
+    fn test_gives_check() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut checks = 0;
+
+        for fen in [
+            FEN_STARTPOS_STRING,
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1",
+            // Discovered check by en passant and by castling
+            "8/8/8/K2pP2q/8/8/8/7k w - d6 0 1",
+            "8/8/8/8/8/8/8/R3K2k w Q - 0 1",
+        ] {
+            for _ in 0..20 {
+                let mut board = Bitboard::from_fen_string_unchecked(fen);
+
+                for _ in 0..100 {
+                    let mut moves = board.generate_pseudo_legal_moves();
+
+                    for &mv in &moves {
+                        board.make(mv);
+                        let expected = board.is_current_in_check();
+                        board.unmake(mv);
+
+                        assert_eq!(board.gives_check(mv), expected, "{:?} --- fen: {}", mv, Fen::from(&board).fen);
+                        checks += usize::from(expected);
+                    }
 
-            let moves = board.generate_legal_moves();
+                    moves.retain(|&mv| board.is_move_legal(mv));
+                    moves.shuffle(&mut rng);
 
-            board.make(*moves.first().unwrap());
+                    match moves.first() {
+                        Some(&mv) => board.make(mv),
+                        None => break,
+                    }
+                }
+            }
         }
+
+        assert!(checks > 1000, "{}", checks);
     }
 
-    #[test]
-    #[ignore]
-    fn print_some_pgns() {
-        let mut board = Bitboard::from_fen_string_unchecked("r4rk1/ppqnpp1p/6pb/4p3/5P2/2N4Q/PPP2P1P/2KR3R b - - 1 16");
+    fn assert_material_and_phase_consistent(board: &Bitboard, message: &str) {
+        let mut expected = *board;
+        expected.recalculate_material_and_phase();
 
-        for mv in board.generate_legal_moves() {
-            println!("{}", mv.to_pgn_string(&mut board).unwrap());
-        }
+        assert_eq!(board.material, expected.material, "{}", message);
+        assert_eq!(board.phase_weight, expected.phase_weight, "{}", message);
     }
 
     #[test]
-    fn test_pgn1() {
-        let mut board = Bitboard::from_fen_string_unchecked("3q4/2P5/8/8/4Q2Q/k7/8/K6Q w - - 0 1");
-
-        assert_eq!(board.uci_to_pgn("e4e1"), Ok("Qee1".to_string()));
-        assert_eq!(board.uci_to_pgn("h4e1"), Ok("Qh4e1".to_string()));
-        assert_eq!(board.uci_to_pgn("h1e1"), Ok("Q1e1".to_string()));
-        assert_eq!(board.uci_to_pgn("c7c8q"), Ok("c8=Q".to_string()));
-        assert_eq!(board.uci_to_pgn("c7d8n"), Ok("cxd8=N".to_string()));
+    fn test_material_and_phase() {
+        let board = Bitboard::default();
+        assert_eq!(board.material(Color::WHITE), 8 * 100 + 2 * 320 + 2 * 330 + 2 * 500 + 900);
+        assert_eq!(board.material(Color::BLACK), board.material(Color::WHITE));
+        assert_eq!(board.phase(), 0);
+
+        assert_eq!(Bitboard::from_fen_string_unchecked("4k3/8/8/8/8/8/8/4K3 w - - 0 1").phase(), 24);
+        assert_eq!(Bitboard::from_fen_string_unchecked("4k3/8/8/8/8/8/8/R3K3 w - - 0 1").phase(), 22);
+        assert_eq!(Bitboard::from_fen_string_unchecked("QQQQkQQQ/8/8/8/8/8/8/4K3 w - - 0 1").phase(), 0);
+
+        // Capture promotion, en passant and a regular capture
+        for (fen, uci) in [
+            ("r3k3/1P6/8/8/8/8/8/4K3 w - - 0 1", "b7a8n"),
+            ("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1", "e5d6"),
+            ("4k3/8/8/3q4/8/8/3R4/4K3 w - - 0 1", "d2d5"),
+        ] {
+            let mut board = Bitboard::from_fen_string_unchecked(fen);
+            let before = board;
+            let mv = board.find_uci(uci).unwrap();
+
+            board.make(mv);
+            assert_material_and_phase_consistent(&board, fen);
+            board.unmake(mv);
+            assert_eq!(board, before, "{}", fen);
+        }
     }
 
     #[test]
-    fn test_pgn2() {
-        let mut board = Bitboard::from_fen_string_unchecked("8/8/5q2/4P1P1/8/k7/8/K7 w - - 0 1");
+    fn test_material_and_phase_consistency_make_unmake() {
+        let mut rng = StdRng::seed_from_u64(0);
 
-        assert_eq!(board.uci_to_pgn("e5f6"), Ok("exf6".to_string()));
-        assert_eq!(board.uci_to_pgn("g5f6"), Ok("gxf6".to_string()));
-    }
+        for fen in [FEN_STARTPOS_STRING, "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", "n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1"] {
+            for _ in 0..20 {
+                let mut board = Bitboard::from_fen_string_unchecked(fen);
 
-    #[test]
-    fn test_pgn3() {
-        let mut board = Bitboard::from_fen_string_unchecked("8/8/8/1PpP4/8/k7/8/K7 w - c6 0 2");
+                for _ in 1..200 {
+                    let mut moves = board.generate_legal_moves();
 
-        assert_eq!(board.uci_to_pgn("d5c6"), Ok("dxc6".to_string()));
-        assert_eq!(board.uci_to_pgn("b5c6"), Ok("bxc6".to_string()));
-    }
+                    for mv in &moves {
+                        board.make(*mv);
+                        assert_material_and_phase_consistent(&board, &format!("{:?} --- fen: {}", mv, fen));
+                        board.unmake(*mv);
+                    }
 
-    #[test]
-    fn test_pgn4() {
-        let mut board = Bitboard::from_fen_string_unchecked("1Q6/8/8/8/8/k1K1B3/8/8 w - - 0 1");
+                    assert_material_and_phase_consistent(&board, fen);
 
-        assert_eq!(board.uci_to_pgn("b8a8"), Ok("Qa8#".to_string()));
-        assert_eq!(board.uci_to_pgn("e3c5"), Ok("Bc5+".to_string()));
+                    moves.shuffle(&mut rng);
+
+                    match moves.first() {
+                        Some(mv) => board.make(*mv),
+                        None => break,
+                    }
+                }
+            }
+        }
     }
 
     #[test]
-    fn test_pgn5() {
-        let mut board = Bitboard::from_fen_string_unchecked("1q6/8/8/8/8/K1k1b3/8/8 b - - 0 1");
+    fn test_zobrist_consistency_make_unmake() {
+        let mut rng = StdRng::seed_from_u64(0);
 
-        assert_eq!(board.uci_to_pgn("b8a8"), Ok("Qa8#".to_string()));
-        assert_eq!(board.uci_to_pgn("e3c5"), Ok("Bc5+".to_string()));
-    }
+        for _ in 0..100 {
+            let mut board = Bitboard::default();
 
-    #[test]
-    fn test_pgn_castle_white() {
-        let mut board = Bitboard::from_fen_string_unchecked("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1");
+            for i in 1..200 {
+                let mut moves = board.generate_legal_moves();
 
-        assert_eq!(board.uci_to_pgn("e1g1"), Ok("O-O".to_string()));
-        assert_eq!(board.uci_to_pgn("e1c1"), Ok("O-O-O".to_string()));
-    }
+                let expected_base_hash = board.zobrist_hash();
+                let expected_base_pawn_hash = board.pawn_zobrist_hash();
+                for mv in &moves {
+                    board.make(*mv);
+                    assert_eq!(board.zobrist_hash(), board.calculate_zobrist_hash());
+                    assert_eq!(board.pawn_zobrist_hash(), board.calculate_zobrist_pawn_hash());
+                    board.unmake(*mv);
 
-    #[test]
-    fn test_pgn_castle_white_to_mate() {
-        let mut board = Bitboard::from_fen_string_unchecked("8/8/8/8/8/8/7R/k3K2R w K - 0 1");
+                    assert_eq!(board.zobrist_hash(), expected_base_hash);
+                    assert_eq!(board.pawn_zobrist_hash(), expected_base_pawn_hash);
+                }
+
+                moves.shuffle(&mut rng);
+
+                if let Some(mv) = moves.first() {
+                    let fen = Fen::from(&board).fen;
+                    board.make(*mv);
+
+                    assert_eq!(board.zobrist_hash(), board.calculate_zobrist_hash(), "failed hash after move #{}: {:?} --- fen: {}", i, mv, fen);
+                    assert_eq!(board.pawn_zobrist_hash(), board.calculate_zobrist_pawn_hash(), "failed pawn hash after move #{}: {:?} --- fen: {}", i, mv, fen);
+                } else {
+                    break;
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_cached_checkers_consistency() {
+        let mut rng = StdRng::seed_from_u64(0);
+
+        for _ in 0..200 {
+            let mut board = Bitboard::default();
+
+            for _ in 1..200 {
+                let mut moves = board.generate_legal_moves();
+
+                for mv in &moves {
+                    let checkers = board.checkers();
+                    board.make(*mv);
+                    assert_eq!(board.checkers(), board.calculate_checkers(), "failed after {:?} --- fen: {}", mv, Fen::from(&board).fen);
+                    assert_eq!(board.is_current_in_check(), board._is_in_check_by_bits(board.turn));
+                    board.unmake(*mv);
+                    assert_eq!(board.checkers(), checkers);
+                }
+
+                moves.shuffle(&mut rng);
+
+                if let Some(mv) = moves.first() {
+                    board.make(*mv);
+                    assert_eq!(board.checkers(), board.calculate_checkers());
+                } else {
+                    break;
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_startpos() {
+        assert_eq!(Bitboard::startpos(), Bitboard::default());
+    }
+
+    #[test]
+    fn test_generate_legal_moves_with_buffer() {
+        let mut buffer = Vec::new();
+
+        for fen in [
+            FEN_STARTPOS_STRING,
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3",
+        ] {
+            let mut board = Bitboard::from_fen_string_unchecked(fen);
+
+            // The buffer still contains the moves of the previous position
+            board.generate_legal_moves_with_buffer(&mut buffer);
+
+            let expected = board.generate_pseudo_legal_moves().into_iter()
+                .filter(|&mv| {
+                    board.make(mv);
+                    let is_valid = board.is_valid();
+                    board.unmake(mv);
+                    is_valid
+                })
+                .map(|mv| mv.bits)
+                .collect::<HashSet<_>>();
+            let actual = buffer.iter().map(|mv| mv.bits).collect::<HashSet<_>>();
+
+            assert_eq!(actual, expected, "{}", fen);
+            assert_eq!(buffer.len(), expected.len(), "{}", fen);
+        }
+    }
+
+    #[test]
+    fn test_mirror() {
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "rnbqkbnr/pp1ppppp/8/2p5/4P3/8/PPPP1PPP/RNBQKBNR w KQkq c6 0 2",
+            "rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3",
+            "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9",
+        ] {
+            let board = Bitboard::from_fen_string_unchecked(fen);
+            let mirrored = board.mirror();
+
+            assert_eq!(mirrored.mirror(), board, "{}", fen);
+            assert_eq!(mirrored.white.pawns(), board.black.pawns().swap_bytes(), "{}", fen);
+            assert_eq!(mirrored.is_current_in_check(), board.is_current_in_check(), "{}", fen);
+            assert_eq!(mirrored.zobrist_hash(), mirrored.calculate_zobrist_hash(), "{}", fen);
+        }
+
+        let board = Bitboard::from_fen_string_unchecked("rnbqkbnr/pp1ppppp/8/2p5/4P3/8/PPPP1PPP/RNBQKBNR w Kq c6 0 2");
+        let expected = Bitboard::from_fen_string_unchecked("rnbqkbnr/pppp1ppp/8/4p3/2P5/8/PP1PPPPP/RNBQKBNR b Qk c3 0 2");
+
+        assert_eq!(board.mirror(), expected);
+        assert_eq!(Bitboard::default().mirror().generate_legal_moves().len(), 20);
+    }
+
+    #[test]
+    fn test_from_player_states() {
+        let mut white = PlayerState::default();
+        white.set_piece(Piece::KING, Square::E1);
+        white.set_piece(Piece::ROOK, Square::H1);
+        white.set_piece(Piece::PAWN, Square::E4);
+        white.king_side_castle = true;
+
+        let mut black = PlayerState::default();
+        black.set_piece(Piece::KING, Square::E8);
+        black.set_piece(Piece::QUEEN, Square::D5);
+        black.set_piece(Piece::KNIGHT, Square::C6);
+        black.clear_square(Square::C6);
+
+        let board = Bitboard::from_player_states(white, black, Color::BLACK, Some(Square::E3)).unwrap();
+
+        assert_eq!(Fen::from(&board).fen, "4k3/8/8/3q4/4P3/8/8/4K2R b K e3 0 1");
+        assert_eq!(board, Bitboard::from_fen_string_unchecked("4k3/8/8/3q4/4P3/8/8/4K2R b K e3 0 1"));
+
+        // Replaces the pawn
+        white.set_piece(Piece::KNIGHT, Square::E4);
+        assert_eq!(Fen::from(&Bitboard::from_player_states(white, black, Color::WHITE, None).unwrap()).fen, "4k3/8/8/3q4/4N3/8/8/4K2R w K - 0 1");
+
+        white.set_piece(Piece::BISHOP, Square::D5);
+        assert_eq!(Bitboard::from_player_states(white, black, Color::WHITE, None), None);
+    }
+
+    #[test]
+    fn test_empty_with_kings() {
+        let mut board = Bitboard::empty_with_kings(Square::E1, Square::E8).unwrap();
+
+        assert_eq!(board, Bitboard::from_fen_string_unchecked("4k3/8/8/8/8/8/8/4K3 w - - 0 1"));
+        assert!(board.is_valid());
+        assert!(!board.is_current_in_check());
+        assert_eq!(board.generate_legal_moves().len(), 5);
+
+        assert_eq!(Bitboard::empty_with_kings(Square::E1, Square::E2), None);
+        assert_eq!(Bitboard::empty_with_kings(Square::E1, Square::E1), None);
+    }
+
+    #[test]
+    fn test_empty() {
+        let mut board = Bitboard::empty();
+
+        assert_eq!(Fen::from(&board).fen, "8/8/8/8/8/8/8/8 w - - 0 1");
+        assert!(board.validate().is_err());
+
+        board.set_piece(Piece::KING, Color::WHITE, Square::E1);
+        board.set_piece(Piece::KNIGHT, Color::WHITE, Square::D1);
+        board.set_piece(Piece::KING, Color::BLACK, Square::E8);
+        board.set_piece(Piece::PAWN, Color::BLACK, Square::D2);
+        // Replaces the knight and the pawn
+        board.set_piece(Piece::QUEEN, Color::WHITE, Square::D1);
+        board.clear_square(Square::D2);
+
+        assert_eq!(board, Bitboard::from_fen_string_unchecked("4k3/8/8/8/8/8/8/3QK3 w - - 0 1"));
+        assert_eq!(Fen::from(&board).fen, "4k3/8/8/8/8/8/8/3QK3 w - - 0 1");
+        assert_eq!(board.validate(), Ok(()));
+        assert_eq!(board.generate_legal_moves().len(), 21);
+    }
+
+    #[test]
+    fn test_ply_clock() {
+        let mut board = Bitboard::default();
+
+        for i in 0..100 {
+            assert_eq!(board.ply_clock(), i);
+
+            let moves = board.generate_legal_moves();
+
+            board.make(*moves.first().unwrap());
+        }
+    }
+
+    #[test]
+    fn test_halfmove_clock_castle_increments() {
+        for (fen, uci) in [
+            ("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 5 10", "e1g1"),
+            ("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 5 10", "e1c1"),
+            ("r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 5 10", "e8g8"),
+            ("r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 5 10", "e8c8"),
+        ] {
+            assert_halfmove_clock_after(fen, uci, 6);
+        }
+    }
+
+    #[test]
+    fn test_halfmove_clock_quiet_move_increments() {
+        assert_halfmove_clock_after("4k3/8/8/3p4/8/8/8/3RK3 w - - 12 40", "d1d2", 13);
+        assert_halfmove_clock_after("4k3/8/8/3p4/8/8/8/3RK3 b - - 12 40", "e8f7", 13);
+    }
+
+    #[test]
+    fn test_halfmove_clock_pawn_move_resets() {
+        assert_halfmove_clock_after("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 7 1", "e2e4", 0);
+        assert_halfmove_clock_after("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 7 1", "e2e3", 0);
+        assert_halfmove_clock_after("4k3/P7/8/8/8/8/8/4K3 w - - 7 40", "a7a8q", 0);
+    }
+
+    #[test]
+    fn test_halfmove_clock_capture_resets() {
+        assert_halfmove_clock_after("4k3/8/8/3p4/8/8/8/3RK3 w - - 12 40", "d1d5", 0);
+        assert_halfmove_clock_after("4k3/8/8/3pP3/8/8/8/4K3 w - d6 12 40", "e5d6", 0);
+    }
+
+    fn assert_halfmove_clock_after(fen: &str, uci: &str, expected: u32) {
+        let mut board = Bitboard::from_fen_string_unchecked(fen);
+        let halfmove_clock_before = board.halfmove_clock;
+
+        let mv = board.find_uci(uci).unwrap();
+
+        board.make(mv);
+        assert_eq!(board.halfmove_clock, expected, "halfmove clock after {} from {}", uci, fen);
+        board.unmake(mv);
+        assert_eq!(board.halfmove_clock, halfmove_clock_before, "halfmove clock after unmaking {} from {}", uci, fen);
+    }
+
+    #[test]
+    fn test_epd() {
+        let epd = r#"2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - bm Qg6; id "WAC.001";"#;
+        let (board, operations) = Bitboard::from_epd(epd).unwrap();
+
+        assert_eq!(board, Bitboard::from_fen_string_unchecked("2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - 0 1"));
+        assert_eq!(operations["bm"], "Qg6");
+        assert_eq!(operations["id"], r#""WAC.001""#);
+
+        assert_eq!(board.to_epd(&[("bm", &operations["bm"]), ("id", &operations["id"])]), epd);
+        assert!(Bitboard::from_epd("2rr3k/pp3pp1 w - - bm Qg6;").is_err());
+    }
+
+    #[test]
+    fn test_to_epd_omits_clocks() {
+        let board = Bitboard::from_fen_string_unchecked("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1");
+
+        assert_eq!(board.to_epd(&[]), "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3");
+    }
+
+    #[test]
+    fn test_from_fen_validated_keeps_valid_en_passant_square() {
+        let fen = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1";
+
+        for policy in [EnPassantPolicy::Reject, EnPassantPolicy::Clear] {
+            assert_eq!(Bitboard::from_fen_validated(fen, policy), Ok(Bitboard::from_fen_string_unchecked(fen)));
+        }
+    }
+
+    #[test]
+    fn test_from_fen_validated_handles_bogus_en_passant_square() {
+        let fen = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq e3 0 1";
+
+        assert_eq!(Bitboard::from_fen_validated(fen, EnPassantPolicy::Reject), Err(FenValidationError::InconsistentEnPassantSquare("e3".to_string())));
+        assert_eq!(Bitboard::from_fen_validated(fen, EnPassantPolicy::Clear).map(|bitboard| bitboard.en_passant_square_shift), Ok(NO_SQUARE));
+    }
+
+    #[test]
+    fn test_validate() {
+        assert_eq!(Bitboard::default().validate(), Ok(()));
+        assert_eq!(Bitboard::from_fen_string_unchecked("8/8/8/8/8/8/8/4K3 w - - 0 1").validate(), Err(PositionError::KingCount(Color::BLACK, 0)));
+        assert_eq!(Bitboard::from_fen_string_unchecked("4k3/8/8/8/8/8/8/3KK3 w - - 0 1").validate(), Err(PositionError::KingCount(Color::WHITE, 2)));
+        assert_eq!(Bitboard::from_fen_string_unchecked("4k3/8/8/8/8/8/8/P3K3 w - - 0 1").validate(), Err(PositionError::PawnOnBackRank(Square::A1)));
+        assert_eq!(Bitboard::from_fen_string_unchecked("3pk3/8/8/8/8/8/8/4K3 w - - 0 1").validate(), Err(PositionError::PawnOnBackRank(Square::D8)));
+        assert_eq!(Bitboard::from_fen_string_unchecked("4k3/8/8/8/8/8/8/4KR2 b - - 0 1").validate(), Ok(()));
+        assert_eq!(Bitboard::from_fen_string_unchecked("4k3/8/8/8/8/8/8/4R1K1 w - - 0 1").validate(), Err(PositionError::PassivePlayerInCheck));
+    }
+
+    #[test]
+    fn test_uci_line_to_pgn() {
+        let mut board = Bitboard::default();
+        let line = ["e2e4", "e7e5", "d1h5", "b8c6", "f1c4", "g8f6", "h5f7"].map(String::from);
+
+        let actual = board.uci_line_to_pgn(&line).unwrap();
+
+        assert_eq!(actual, vec!["e4", "e5", "Qh5", "Nc6", "Bc4", "Nf6", "Qxf7#"]);
+        assert_eq!(Fen::from(&board), Fen::default());
+    }
+
+    #[test]
+    fn test_uci_line_to_pgn_chess960_castles() {
+        let mut board = Bitboard::from_fen_string_unchecked("1r4kr/8/8/8/8/8/8/1R4KR w HBhb - 0 1");
+        let line = ["g1b1", "g8h8"].map(String::from);
+
+        assert_eq!(board.uci_line_to_pgn(&line).unwrap(), vec!["O-O-O", "O-O"]);
+        assert_eq!(board.uci_line_to_pgn(&["g1c1".to_string()]), Err(MoveDoesNotExist("g1c1".to_string())));
+    }
+
+    #[test]
+    fn test_san_with_context_matches_uci_to_pgn() {
+        let line = [
+            "e2e4", "e7e5", "g1f3", "b8c6", "f1b5", "a7a6", "b5a4", "g8f6", "e1g1", "f8e7", "f1e1", "b7b5", "a4b3", "d7d6", "c2c3", "e8g8",
+            "h2h3", "c6b8", "d2d4", "b8d7", "c3c4", "c7c6", "c4b5", "a6b5", "b1c3", "c8b7", "c1g5", "b5b4", "c3b1", "h7h6", "g5h4", "c6c5",
+            "d4e5", "f6e4", "h4e7", "d8e7", "e5d6", "e7f6", "b1d2", "e4d6", "d2c4", "d6c4", "b3c4", "d7b6", "f3e5", "a8e8", "c4f7", "f8f7",
+            "e5f7", "e8e1", "d1e1", "g8f7", "e1e3", "f6g5", "e3g5", "h6g5", "b2b3", "f7e6", "a2a3", "e6d6", "a3b4", "c5b4", "a1a5", "b6d5",
+            "f2f3", "b7c8", "g1f2", "c8f5", "a5a7", "g7g6", "a7a6", "d6c5", "f2e1", "d5f4", "g2g3", "f4h3", "e1d2", "c5b5", "a6d6", "b5c5",
+            "d6a6", "h3f2", "g3g4", "f5d3", "a6e6",
+        ].map(String::from);
+
+        let mut expected_board = Bitboard::default();
+        let mut expected = Vec::new();
+
+        for uci in &line {
+            expected.push(expected_board.uci_to_pgn(uci).unwrap());
+            expected_board.make_uci(uci).unwrap();
+        }
+
+        let mut board = Bitboard::default();
+        let mut actual = Vec::new();
+
+        for uci in &line {
+            let legal_moves = board.generate_legal_moves();
+            let mv = *legal_moves.iter().find(|mv| &mv.to_uci_string() == uci).unwrap();
+            actual.push(board.san_with_context(mv, &legal_moves));
+            board.make(mv);
+        }
+
+        assert_eq!(actual, expected);
+        assert_eq!(Bitboard::default().uci_line_to_pgn(&line).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_material_hash_ignores_placement() {
+        let board = Bitboard::from_fen_string_unchecked("4k3/pp3n2/8/8/8/8/PP2B3/4K3 w - - 0 1");
+        let rearranged = Bitboard::from_fen_string_unchecked("1n2k3/7p/6p1/8/3B4/P7/1P6/6K1 b - - 0 1");
+
+        assert_eq!(board.material_signature(), rearranged.material_signature());
+        assert_eq!(board.material_hash(), rearranged.material_hash());
+    }
+
+    #[test]
+    fn test_material_hash_differs_for_different_material() {
+        let hashes = [
+            "4k3/pp3n2/8/8/8/8/PP2B3/4K3 w - - 0 1",
+            "4k3/pp3b2/8/8/8/8/PP2B3/4K3 w - - 0 1",
+            "4k3/pp3n2/8/8/8/8/PP2N3/4K3 w - - 0 1",
+            "4k3/p4n2/8/8/8/8/PP2B3/4K3 w - - 0 1",
+            "4k3/pp6/8/8/8/8/PP2B3/4K3 w - - 0 1",
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        ].map(|fen| Bitboard::from_fen_string_unchecked(fen).material_hash());
+
+        for (i, hash) in hashes.iter().enumerate() {
+            assert!(!hashes[i + 1..].contains(hash));
+        }
+    }
+
+    #[test]
+    fn test_capture_classification_free_capture() {
+        assert_capture_classification("4k3/8/8/3p4/8/8/8/3RK3 w - - 0 1", "d1d5", CaptureClass::Winning, 100);
+    }
+
+    #[test]
+    fn test_capture_classification_equal_trade() {
+        assert_capture_classification("4k3/8/2p5/3n4/8/4N3/8/4K3 w - - 0 1", "e3d5", CaptureClass::Equal, 0);
+    }
+
+    #[test]
+    fn test_capture_classification_poisoned_capture() {
+        assert_capture_classification("4k3/2p5/3p4/8/8/8/3Q4/4K3 w - - 0 1", "d2d6", CaptureClass::Losing, -800);
+    }
+
+    #[test]
+    fn test_capture_classification_king_can_not_recapture() {
+        assert_capture_classification("4k3/5p2/8/8/2B5/8/8/4KQ2 w - - 0 1", "f1f7", CaptureClass::Winning, 100);
+        assert_capture_classification("4k3/5p2/8/8/8/8/8/4KQ2 w - - 0 1", "f1f7", CaptureClass::Losing, -800);
+    }
+
+    #[test]
+    fn test_see_x_ray() {
+        assert_capture_classification("3rk3/8/8/3p4/8/8/3R4/3RK3 w - - 0 1", "d2d5", CaptureClass::Winning, 100);
+        assert_capture_classification("3rk3/8/8/3p4/8/8/3R4/4K3 w - - 0 1", "d2d5", CaptureClass::Losing, -400);
+    }
+
+    #[test]
+    fn test_see_en_passant() {
+        assert_capture_classification("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1", "e5d6", CaptureClass::Winning, 100);
+        assert_capture_classification("4k3/2p5/8/3pP3/8/8/8/4K3 w - d6 0 1", "e5d6", CaptureClass::Equal, 0);
+    }
+
+    #[test]
+    fn test_see_promotion() {
+        assert_capture_classification("r3k3/1P6/8/8/8/8/8/4K3 w - - 0 1", "b7a8q", CaptureClass::Winning, 1300);
+        assert_capture_classification("r3k3/1P6/1n6/8/8/8/8/4K3 w - - 0 1", "b7a8q", CaptureClass::Winning, 400);
+    }
+
+    fn assert_capture_classification(fen: &str, uci: &str, expected_class: CaptureClass, expected_see: i32) {
+        let mut board = Bitboard::from_fen_string_unchecked(fen);
+        let mv = board.find_uci(uci).unwrap();
+
+        assert_eq!(board.see(mv), expected_see, "see of {} from {}", uci, fen);
+        assert_eq!(board.capture_classification(mv), expected_class, "capture classification of {} from {}", uci, fen);
+        assert_eq!(board.is_winning_capture(mv), expected_class == CaptureClass::Winning, "{} from {}", uci, fen);
+    }
+
+    #[test]
+    fn test_absolute_pins() {
+        let board = Bitboard::from_fen_string_unchecked("4k3/4r3/8/7b/8/8/4N3/4K3 w - - 0 1");
+
+        assert_eq!(board.absolute_pins(&Color::WHITE), vec![(Square::E2, Square::E1, Square::E7)]);
+        assert_eq!(board.absolute_pins(&Color::BLACK), vec![]);
+    }
+
+    #[test]
+    fn test_absolute_pins_ignores_doubly_blocked_lines() {
+        let board = Bitboard::from_fen_string_unchecked("4k3/4r3/8/8/4P3/8/4N3/4K3 w - - 0 1");
+
+        assert_eq!(board.absolute_pins(&Color::WHITE), vec![]);
+    }
+
+    #[test]
+    fn test_discovered_attack_candidates() {
+        let board = Bitboard::from_fen_string_unchecked("4k3/8/8/8/8/1B6/4N3/4R1K1 w - - 0 1");
+
+        assert_eq!(board.discovered_attack_candidates(&Color::WHITE), vec![(Square::E2, Square::E1)]);
+        assert_eq!(board.discovered_attack_candidates(&Color::BLACK), vec![]);
+    }
+
+    #[test]
+    fn test_hanging_pieces() {
+        let board = Bitboard::from_fen_string_unchecked("4k3/8/8/3n4/8/8/8/3RK3 w - - 0 1");
+
+        assert_eq!(board.hanging_pieces(&Color::BLACK), vec![Square::D5]);
+        assert_eq!(board.hanging_pieces(&Color::WHITE), vec![]);
+    }
+
+    #[test]
+    fn test_hanging_pieces_all_defended() {
+        let board = Bitboard::default();
+
+        assert_eq!(board.hanging_pieces(&Color::WHITE), vec![]);
+        assert_eq!(board.hanging_pieces(&Color::BLACK), vec![]);
+
+        let board = Bitboard::from_fen_string_unchecked("4k3/4p3/3n4/8/8/8/8/3RK3 w - - 0 1");
+
+        assert_eq!(board.hanging_pieces(&Color::BLACK), vec![]);
+    }
+
+    #[test]
+    fn test_defended_squares_respect_pins() {
+        // The knight on d2 is pinned by the bishop on b4, so it does not defend the rook on b3
+        let board = Bitboard::from_fen_string_unchecked("4k3/8/8/8/1b6/1R6/3N4/4K3 w - - 0 1");
+
+        assert_eq!(board.defended_squares(&Color::WHITE) & Square::B3.mask, 0);
+        assert_eq!(board.hanging_pieces(&Color::WHITE), vec![]);
+
+        let board = Bitboard::from_fen_string_unchecked("4k3/8/8/8/1b6/1R6/3N4/5K2 w - - 0 1");
+
+        assert_ne!(board.defended_squares(&Color::WHITE) & Square::B3.mask, 0);
+    }
+
+    #[test]
+    #[ignore]
+    fn print_some_pgns() {
+        let mut board = Bitboard::from_fen_string_unchecked("r4rk1/ppqnpp1p/6pb/4p3/5P2/2N4Q/PPP2P1P/2KR3R b - - 1 16");
+
+        for (_, san) in board.san_moves() {
+            println!("{}", san);
+        }
+    }
+
+    #[test]
+    fn test_move_with_board() {
+        let mut board = Bitboard::from_fen_string_unchecked("r1bqk2r/pppp1ppp/2n2n2/2b1p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4");
+        let castle = board.find_uci("e1g1").unwrap();
+        let knight = board.find_uci("f3g5").unwrap();
+
+        assert_eq!(castle.with_board(&board).to_string(), "O-O");
+        assert_eq!(knight.with_board(&board).to_string(), "Ng5");
+
+        board.make(castle);
+        // No longer legal
+        assert_eq!(knight.with_board(&board).to_string(), "f3g5");
+        assert!(castle.to_string().starts_with("Move(e1g1) { piece_moved = k"), "{}", castle);
+    }
+
+    #[test]
+    fn test_san_moves() {
+        let mut board = Bitboard::from_fen_string_unchecked("4k3/8/8/1N6/8/8/8/1N2KN2 w - - 0 1");
+
+        let actual = board.san_moves().into_iter().map(|(mv, san)| (mv.to_uci_string(), san)).collect::<HashMap<_, _>>();
+
+        assert_eq!(actual.len(), board.generate_legal_moves().len());
+        for (mv, san) in &actual {
+            assert_eq!(&board.uci_to_pgn(mv).unwrap(), san);
+        }
+
+        // Knights on the same file disambiguate by rank
+        assert_eq!(actual["b1c3"], "N1c3");
+        assert_eq!(actual["b5c3"], "N5c3");
+        // Knights on the same rank disambiguate by file
+        assert_eq!(actual["b1d2"], "Nbd2");
+        assert_eq!(actual["f1d2"], "Nfd2");
+        // Only one knight reaches the square
+        assert_eq!(actual["b5c7"], "Nc7+");
+        assert_eq!(actual["f1h2"], "Nh2");
+    }
+
+    #[test]
+    fn test_pgn1() {
+        let mut board = Bitboard::from_fen_string_unchecked("3q4/2P5/8/8/4Q2Q/k7/8/K6Q w - - 0 1");
+
+        assert_eq!(board.uci_to_pgn("e4e1"), Ok("Qee1".to_string()));
+        assert_eq!(board.uci_to_pgn("h4e1"), Ok("Qh4e1".to_string()));
+        assert_eq!(board.uci_to_pgn("h1e1"), Ok("Q1e1".to_string()));
+        assert_eq!(board.uci_to_pgn("c7c8q"), Ok("c8=Q".to_string()));
+        assert_eq!(board.uci_to_pgn("c7d8n"), Ok("cxd8=N".to_string()));
+    }
+
+    #[test]
+    fn test_pgn2() {
+        let mut board = Bitboard::from_fen_string_unchecked("8/8/5q2/4P1P1/8/k7/8/K7 w - - 0 1");
+
+        assert_eq!(board.uci_to_pgn("e5f6"), Ok("exf6".to_string()));
+        assert_eq!(board.uci_to_pgn("g5f6"), Ok("gxf6".to_string()));
+    }
+
+    #[test]
+    fn test_pgn3() {
+        let mut board = Bitboard::from_fen_string_unchecked("8/8/8/1PpP4/8/k7/8/K7 w - c6 0 2");
+
+        assert_eq!(board.uci_to_pgn("d5c6"), Ok("dxc6".to_string()));
+        assert_eq!(board.uci_to_pgn("b5c6"), Ok("bxc6".to_string()));
+    }
+
+    #[test]
+    fn test_pgn4() {
+        let mut board = Bitboard::from_fen_string_unchecked("1Q6/8/8/8/8/k1K1B3/8/8 w - - 0 1");
+
+        assert_eq!(board.uci_to_pgn("b8a8"), Ok("Qa8#".to_string()));
+        assert_eq!(board.uci_to_pgn("e3c5"), Ok("Bc5+".to_string()));
+    }
+
+    #[test]
+    fn test_pgn5() {
+        let mut board = Bitboard::from_fen_string_unchecked("1q6/8/8/8/8/K1k1b3/8/8 b - - 0 1");
+
+        assert_eq!(board.uci_to_pgn("b8a8"), Ok("Qa8#".to_string()));
+        assert_eq!(board.uci_to_pgn("e3c5"), Ok("Bc5+".to_string()));
+    }
+
+    #[test]
+    fn test_pgn_disambiguation_without_shared_file_or_rank() {
+        let mut board = Bitboard::from_fen_string_unchecked("1n4k1/8/5n2/8/8/8/8/6K1 b - - 0 1");
+
+        assert_eq!(board.uci_to_pgn("b8d7"), Ok("Nbd7".to_string()));
+        assert_eq!(board.uci_to_pgn("f6d7"), Ok("Nfd7".to_string()));
+        assert_eq!(board.uci_to_pgn("f6h5"), Ok("Nh5".to_string()));
+    }
+
+    #[test]
+    fn test_pgn_to_bb() {
+        let mut board = Bitboard::from_fen_string_unchecked("3q4/2P5/8/8/4Q2Q/k7/8/K6Q w - - 0 1");
+
+        assert_eq!(board.pgn_to_bb("Qee1"), Ok(board.find_uci("e4e1").unwrap()));
+        assert_eq!(board.pgn_to_bb("cxd8=N"), Ok(board.find_uci("c7d8n").unwrap()));
+        assert_eq!(board.pgn_to_bb("Qe1"), Err(PgnParseError::AmbiguousMove("Qe1".to_string(), 3)));
+        assert_eq!(board.pgn_to_bb("Nf3"), Err(PgnParseError::NoMatchingMove("Nf3".to_string())));
+        assert_eq!(board.pgn_to_bb("Qz9"), Err(PgnParseError::MalformedNotation("Qz9".to_string())));
+
+        let mut board = Bitboard::from_fen_string_unchecked("4r1k1/8/8/8/8/8/4R3/4K3 w - - 0 1");
+
+        assert_eq!(board.pgn_to_bb("Rd2"), Err(PgnParseError::IllegalMove("Rd2".to_string())));
+        assert_eq!(board.pgn_to_bb("Rxe8+"), Ok(board.find_uci("e2e8").unwrap()));
+    }
+
+    #[test]
+    fn test_move_bits_round_trip() {
+        for (fen, uci) in [
+            ("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1", "e1c1"),
+            ("rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3", "e5f6"),
+            ("3q4/2P5/8/8/8/k7/8/K7 w - - 0 1", "c7d8n"),
+        ] {
+            let mut board = Bitboard::from_fen_string_unchecked(fen);
+            let mv = board.find_uci(uci).unwrap();
+            let restored = Move::from_bits(mv.to_bits());
+
+            assert_eq!(restored, mv, "{}", fen);
+
+            board.make(restored);
+            assert_ne!(board, Bitboard::from_fen_string_unchecked(fen), "{}", fen);
+            board.unmake(Move::from(u64::from(mv)));
+            assert_eq!(board, Bitboard::from_fen_string_unchecked(fen), "{}", fen);
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_move_serde() {
+        let mv = Bitboard::default().find_uci("e2e4").unwrap();
+        let json = serde_json::to_string(&mv).unwrap();
+
+        assert_eq!(json, mv.to_bits().to_string());
+        assert_eq!(serde_json::from_str::<Move>(&json).unwrap(), mv);
+    }
+
+    #[test]
+    fn test_make_san() {
+        let mut board = Bitboard::default();
+
+        assert_eq!(board.find_san(" Nf3 "), Ok(board.find_uci("g1f3").unwrap()));
+        assert_eq!(board.make_san("e4"), Ok(()));
+        assert_eq!(board.make_all_san(&["e5", "Qh5", "Nc6", "Bc4", "Nf6", "Qxf7#"].map(String::from)), Ok(()));
+        assert_eq!(Fen::from(&board).fen, "r1bqkb1r/pppp1Qpp/2n2n2/4p3/2B1P3/8/PPPP1PPP/RNB1K1NR b KQkq - 0 4");
+    }
+
+    #[test]
+    fn test_make_all_san_rolls_back_on_error() {
+        let mut board = Bitboard::default();
+
+        assert_eq!(board.make_all_san(&["e4", "e5", "Ke3"].map(String::from)), Err(PgnParseError::NoMatchingMove("Ke3".to_string())));
+        assert_eq!(board, Bitboard::default());
+        assert_eq!(board.make_san("Nf6"), Err(PgnParseError::NoMatchingMove("Nf6".to_string())));
+    }
+
+    #[test]
+    fn test_pgn_castle_white() {
+        let mut board = Bitboard::from_fen_string_unchecked("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1");
+
+        assert_eq!(board.uci_to_pgn("e1g1"), Ok("O-O".to_string()));
+        assert_eq!(board.uci_to_pgn("e1c1"), Ok("O-O-O".to_string()));
+    }
+
+    #[test]
+    fn test_pgn_castle_white_to_mate() {
+        let mut board = Bitboard::from_fen_string_unchecked("8/8/8/8/8/8/7R/k3K2R w K - 0 1");
 
         assert_eq!(board.uci_to_pgn("e1g1"), Ok("O-O#".to_string()));
     }
@@ -1919,6 +3894,367 @@ mod tests {
             println!("{} x {} = {}", attacker.name, defender.name, mvv_lva);
         }
     }
-}
 
+    #[test]
+    fn test_is_square_attacked_by_pawns() {
+        let board = Bitboard::from_fen_string_unchecked("4k3/8/3p4/8/8/4P3/8/4K3 w - - 0 1");
+
+        assert!(board.is_square_attacked(Square::D4, Color::WHITE));
+        assert!(board.is_square_attacked(Square::F4, Color::WHITE));
+        assert!(!board.is_square_attacked(Square::E4, Color::WHITE));
+        assert!(!board.is_square_attacked(Square::D4, Color::BLACK));
+
+        assert!(board.is_square_attacked(Square::C5, Color::BLACK));
+        assert!(board.is_square_attacked(Square::E5, Color::BLACK));
+        assert!(!board.is_square_attacked(Square::D5, Color::BLACK));
+        assert!(!board.is_square_attacked(Square::C7, Color::BLACK));
+    }
+
+    #[test]
+    fn test_is_square_attacked_by_knights() {
+        let board = Bitboard::from_fen_string_unchecked("4k3/8/8/8/3N4/8/8/4K3 w - - 0 1");
+
+        for square in [Square::B3, Square::B5, Square::C2, Square::C6, Square::E2, Square::E6, Square::F3, Square::F5] {
+            assert!(board.is_square_attacked(square, Color::WHITE), "{}", square.fen);
+        }
+
+        assert!(!board.is_square_attacked(Square::D5, Color::WHITE));
+        assert!(!board.is_square_attacked(Square::B3, Color::BLACK));
+    }
+
+    #[test]
+    fn test_is_square_attacked_by_sliders() {
+        let board = Bitboard::from_fen_string_unchecked("4k3/8/8/8/1b1R2p1/8/8/4K3 w - - 0 1");
+
+        assert!(board.is_square_attacked(Square::D8, Color::WHITE));
+        assert!(board.is_square_attacked(Square::G4, Color::WHITE));
+        assert!(!board.is_square_attacked(Square::H4, Color::WHITE));
+        assert!(board.is_square_attacked(Square::B4, Color::WHITE));
+        assert!(!board.is_square_attacked(Square::A4, Color::WHITE));
+
+        assert!(board.is_square_attacked(Square::E1, Color::BLACK));
+        assert!(board.is_square_attacked(Square::D2, Color::BLACK));
+        assert!(!board.is_square_attacked(Square::D4, Color::BLACK));
+    }
+
+    #[test]
+    fn test_attacks_from() {
+        let squares = |squares: &[Square]| squares.iter().fold(0, |acc, square| acc | square.mask);
+
+        // Blocked by the pawns on d6 and f4, the own pawn on d6 is included as a defended square
+        let board = Bitboard::from_fen_string_unchecked("4k3/8/3P4/8/3Q1p2/8/8/4K3 w - - 0 1");
+        let expected = squares(&[
+            Square::D5, Square::D6, Square::D3, Square::D2, Square::D1,
+            Square::A4, Square::B4, Square::C4, Square::E4, Square::F4,
+            Square::C5, Square::B6, Square::A7, Square::E5, Square::F6, Square::G7, Square::H8,
+            Square::C3, Square::B2, Square::A1, Square::E3, Square::F2, Square::G1,
+        ]);
+        assert_eq!(board.attacks_from(Square::D4), expected);
+        assert_eq!(board.attacks_from(Square::F4), squares(&[Square::E3, Square::G3]));
+
+        let board = Bitboard::from_fen_string_unchecked("4k3/8/8/8/n7/8/8/4K3 b - - 0 1");
+        assert_eq!(board.attacks_from(Square::A4), squares(&[Square::B6, Square::C5, Square::C3, Square::B2]));
+        assert_eq!(board.attacks_from(Square::D4), 0);
+    }
+
+    #[test]
+    fn test_attacked_squares() {
+        let board = Bitboard::default();
+
+        // Every square of the first two ranks is guarded except the corners
+        let guarded = (RANK_1_OCCUPANCY | RANK_2_OCCUPANCY) & !Square::A1.mask & !Square::H1.mask;
+        assert_eq!(board.attacked_squares(Color::WHITE), RANK_3_OCCUPANCY | guarded);
+        assert_eq!(board.attacked_squares(Color::BLACK), RANK_6_OCCUPANCY | guarded.swap_bytes());
+
+        // The rook defends its king
+        let board = Bitboard::from_fen_string_unchecked("4k3/8/8/8/8/8/8/R3K3 w - - 0 1");
+        let expected = (FILE_A_OCCUPANCY & !Square::A1.mask) | Square::B1.mask | Square::C1.mask | Square::D1.mask | Square::E1.mask | Square::D2.mask | Square::E2.mask | Square::F2.mask | Square::F1.mask;
+        assert_eq!(board.attacked_squares(Color::WHITE), expected);
+    }
+
+    #[test]
+    fn test_piece_on() {
+        let board = Bitboard::default();
+        let back_rank = [Piece::ROOK, Piece::KNIGHT, Piece::BISHOP, Piece::QUEEN, Piece::KING, Piece::BISHOP, Piece::KNIGHT, Piece::ROOK];
+
+        for square in Square::VALUES {
+            let expected = match square.rank {
+                Rank::RANK_1 => Some((back_rank[square.file.index as usize], Color::WHITE)),
+                Rank::RANK_2 => Some((Piece::PAWN, Color::WHITE)),
+                Rank::RANK_7 => Some((Piece::PAWN, Color::BLACK)),
+                Rank::RANK_8 => Some((back_rank[square.file.index as usize], Color::BLACK)),
+                _ => None,
+            };
+
+            assert_eq!(board.piece_on(square), expected, "{:?}", square);
+        }
+    }
 
+    #[test]
+    fn test_non_pawn_material() {
+        assert_eq!(Bitboard::default().non_pawn_material(), 2 * (2 * 320 + 2 * 330 + 2 * 500 + 900));
+        assert_eq!(Bitboard::from_fen_string_unchecked("4k3/pppp4/8/8/8/8/4PPPP/R3K3 w - - 0 1").non_pawn_material(), 500);
+    }
+
+    #[test]
+    fn test_insufficient_material() {
+        for fen in [
+            "4k3/8/8/8/8/8/8/4K3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/2B1K3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/1N2K3 w - - 0 1",
+            "4kb2/8/8/8/8/8/8/2B1K3 w - - 0 1",
+            "2b1k3/8/8/8/8/8/8/3BK3 b - - 0 1",
+            "4k3/8/8/8/8/8/1B6/2B1K3 w - - 0 1",
+        ] {
+            assert!(Bitboard::from_fen_string_unchecked(fen).is_insufficient_material(), "{}", fen);
+        }
+    }
+
+    #[test]
+    fn test_sufficient_material() {
+        for fen in [
+            "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/R3K3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/3QK3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/2BBK3 w - - 0 1",
+            "2b1k3/8/8/8/8/8/8/2B1K3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/1NB1K3 w - - 0 1",
+            "4kn2/8/8/8/8/8/8/1N2K3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/NN2K3 w - - 0 1",
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        ] {
+            assert!(!Bitboard::from_fen_string_unchecked(fen).is_insufficient_material(), "{}", fen);
+        }
+    }
+
+    #[test]
+    fn test_fifty_move_draw() {
+        let mut board = Bitboard::from_fen_string_unchecked("4k3/4p3/8/8/8/8/4P3/R3K3 w - - 99 80");
+
+        assert!(!board.is_fifty_move_draw());
+        assert!(!board.is_draw());
+
+        let mv = board.find_uci("a1a2").unwrap();
+        board.make(mv);
+        assert!(board.is_fifty_move_draw());
+        assert!(board.is_draw());
+        board.unmake(mv);
+
+        assert!(!board.is_fifty_move_draw());
+
+        for uci in ["e2e4", "a1a7"] {
+            let mut board = Bitboard::from_fen_string_unchecked("4k3/p3p3/8/8/8/8/4P3/R3K3 w - - 100 80");
+            assert!(board.is_fifty_move_draw());
+
+            let mv = board.find_uci(uci).unwrap();
+            board.make(mv);
+            assert!(!board.is_fifty_move_draw(), "{}", uci);
+        }
+    }
+
+    #[test]
+    fn test_status() {
+        for (fen, expected) in [
+            ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", GameStatus::Ongoing),
+            ("rnbqkbnr/ppppp2p/5p2/6pQ/4P3/8/PPPP1PPP/RNB1KBNR b KQkq - 1 3", GameStatus::Checkmate),
+            ("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", GameStatus::Ongoing),
+            ("R5k1/5ppp/8/8/8/8/8/6K1 b - - 1 1", GameStatus::Checkmate),
+            ("4k3/8/8/8/8/8/8/4R1K1 b - - 0 1", GameStatus::Check),
+            ("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1", GameStatus::Stalemate),
+            ("4k3/8/8/8/8/8/8/2B1K3 b - - 0 1", GameStatus::DrawInsufficientMaterial),
+            ("4k3/p7/8/8/8/8/P7/4K3 w - - 100 80", GameStatus::DrawFiftyMove),
+            ("R5k1/5ppp/8/8/8/8/8/6K1 b - - 100 80", GameStatus::Checkmate),
+        ] {
+            assert_eq!(Bitboard::from_fen_string_unchecked(fen).status(), expected, "{}", fen);
+        }
+    }
+
+    #[test]
+    fn test_is_draw_with_repetition() {
+        let board = Bitboard::default();
+
+        assert!(!board.is_draw());
+        assert!(!board.is_draw_with_repetition(false));
+        assert!(board.is_draw_with_repetition(true));
+        assert!(Bitboard::from_fen_string_unchecked("4k3/8/8/8/8/8/8/4K3 w - - 0 1").is_draw_with_repetition(false));
+    }
+
+    #[test]
+    fn test_copy_is_independent() {
+        let mut board = Bitboard::from_fen_string_unchecked("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1");
+        let backup = board;
+
+        for mv in board.generate_legal_moves() {
+            board.make(mv);
+            assert_ne!(board, backup);
+            board.unmake(mv);
+
+            assert_eq!(board, backup, "{}", mv.to_uci_string());
+        }
+
+        let mv = board.find_uci("e1g1").unwrap();
+        board.make(mv);
+
+        assert_eq!(Fen::from(&backup).fen, "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1");
+    }
+
+    #[test]
+    fn test_hash_transpositions() {
+        let play = |moves: &[&str]| {
+            let mut board = Bitboard::default();
+            for uci in moves {
+                let mv = board.find_uci(uci).unwrap();
+                board.make(mv);
+            }
+            board
+        };
+
+        let mut positions = HashSet::new();
+        positions.insert(play(&["g1f3", "g8f6", "b1c3", "b8c6"]));
+        positions.insert(play(&["b1c3", "b8c6", "g1f3", "g8f6"]));
+        positions.insert(play(&["b1c3", "g8f6", "g1f3", "b8c6"]));
+
+        assert_eq!(positions.len(), 1);
+
+        positions.insert(play(&["g1f3", "g8f6", "f3g1", "f6g8"]));
+        positions.insert(Bitboard::default());
+
+        assert_eq!(positions.len(), 3);
+    }
+
+    #[test]
+    fn test_null_move_round_trip() {
+        for fen in [
+            FEN_STARTPOS_STRING,
+            "rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 3",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R b KQkq - 7 12",
+        ] {
+            let mut board = Bitboard::from_fen_string_unchecked(fen);
+            let backup = board;
+
+            let undo = board.make_null();
+
+            assert_ne!(board.turn, backup.turn);
+            assert_eq!(board.en_passant_square_shift, NO_SQUARE);
+            assert_eq!(board.halfmove_clock, backup.halfmove_clock + 1);
+            assert_eq!(board.zobrist_hash(), board.calculate_zobrist_hash(), "{}", fen);
+            assert_eq!(board.pawn_zobrist_hash(), board.calculate_zobrist_pawn_hash(), "{}", fen);
+
+            board.unmake_null(undo);
+
+            assert_eq!(Fen::from(&board).fen, fen);
+            assert_eq!(board.zobrist_hash(), backup.zobrist_hash());
+            assert_eq!(board, backup);
+        }
+    }
+
+    #[test]
+    fn test_null_move_passes_turn() {
+        let mut board = Bitboard::from_fen_string_unchecked("4k3/8/8/8/8/8/8/R3K3 b - - 0 1");
+        board.make_null();
+
+        assert_eq!(Fen::from(&board).fen, "4k3/8/8/8/8/8/8/R3K3 w - - 1 2");
+
+        let mv = board.find_uci("a1a8").unwrap();
+        board.make(mv);
+
+        assert!(board.is_current_in_check());
+    }
+
+    #[test]
+    fn test_perft_divide() {
+        let mut board = Bitboard::default();
+        let divide = board.perft_divide(3);
+
+        assert_eq!(divide.len(), 20);
+        assert_eq!(divide.first(), Some(&("a2a3".to_string(), 380)));
+        assert_eq!(divide.last(), Some(&("h2h4".to_string(), 420)));
+        assert!(divide.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        assert_eq!(divide.iter().map(|(_, count)| count).sum::<u64>(), 8902);
+
+        let mut board = Bitboard::from_fen_string_unchecked("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1");
+        let total = board.perft(3).iter().map(|(_, count)| count).sum::<u64>();
+
+        assert_eq!(total, 97862);
+        assert_eq!(board.perft_divide(3).iter().map(|(_, count)| count).sum::<u64>(), total);
+        assert!(board.perft_divide(0).is_empty());
+    }
+
+    #[test]
+    fn test_chess960_fen_round_trip() {
+        for fen in [
+            "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9",
+            "2nnrbkr/p1qppppp/8/1ppb4/6PP/3PP3/PPP2P2/BQNNRBKR w HEhe - 1 9",
+            "qbbnnrkr/2pp2pp/p7/1p2pp2/8/P3PP2/1PPP1KPP/QBBNNR1R w hf - 0 9",
+        ] {
+            let board = Bitboard::from_fen_string_unchecked(fen);
+
+            assert!(board.is_chess960());
+            assert_eq!(Fen::from(&board).fen, fen);
+        }
+
+        let board = Bitboard::default();
+        assert!(!board.is_chess960());
+        assert_eq!(Fen::from(&board).fen, FEN_STARTPOS_STRING);
+    }
+
+    #[test]
+    fn test_chess960_x_fen_castling_uses_outermost_rook() {
+        let board = Bitboard::from_fen_string_unchecked("rr4kr/8/8/8/8/8/8/RR4KR w KQkq - 0 1");
+
+        assert!(board.is_chess960());
+        assert_eq!(board.white.queen_side_rook_file, 0);
+        assert_eq!(board.white.king_side_rook_file, 7);
+        assert_eq!(board.castling_availability(), "HAha");
+    }
+
+    #[test]
+    fn test_chess960_castle_moves() {
+        for (uci, expected_fen) in [
+            ("g1h1", "4k3/8/8/8/8/8/8/1R3RK1 b - - 1 1"),
+            ("g1b1", "4k3/8/8/8/8/8/8/2KR3R b - - 1 1"),
+        ] {
+            let mut board = Bitboard::from_fen_string_unchecked("4k3/8/8/8/8/8/8/1R4KR w HB - 0 1");
+            let original_fen = Fen::from(&board).fen;
+
+            let mv = board.find_uci(uci).unwrap();
+            assert!(mv.is_castle_move());
+            assert_eq!(board.move_to_uci_string(mv), uci);
+
+            board.make(mv);
+            assert_eq!(Fen::from(&board).fen, expected_fen);
+            assert_eq!(board.zobrist_hash(), board.calculate_zobrist_hash());
+
+            board.unmake(mv);
+            assert_eq!(Fen::from(&board).fen, original_fen);
+        }
+    }
+
+    #[test]
+    fn test_resolve_uci_move() {
+        let mut board = Bitboard::from_fen_string_unchecked("r3k2r/1P6/8/8/8/8/8/R3K2R w KQkq - 0 1");
+
+        for uci in ["b7b8q", "b7b8n", "b7a8r", "e1g1", "e1c1", "a1a8"] {
+            assert_eq!(board.resolve_uci_move(&UciMove::from_str(uci).unwrap()), board.find_uci(uci), "{}", uci);
+        }
+
+        assert!(board.resolve_uci_move(&UciMove::from_str("e1g1").unwrap()).unwrap().is_castle_move());
+        assert_eq!(board.resolve_uci_move(&UciMove::from_str("b7b8").unwrap()), Err(MoveDoesNotExist("b7b8".to_string())));
+
+        let mut chess960 = Bitboard::from_fen_string_unchecked("4k3/8/8/8/8/8/8/1R4KR w HB - 0 1");
+
+        for uci in ["g1h1", "g1b1"] {
+            let mv = chess960.resolve_uci_move(&UciMove::from_str(uci).unwrap()).unwrap();
+            assert!(mv.is_castle_move());
+            assert_eq!(chess960.move_to_uci_string(mv), uci);
+        }
+    }
+
+    #[test]
+    fn test_chess960_castling_rook_does_not_shield_king_target() {
+        let mut board = Bitboard::from_fen_string_unchecked("4k3/8/8/8/8/8/8/rRK5 w B - 0 1");
+
+        assert!(!board.is_current_in_check());
+        assert!(board.generate_legal_moves().iter().all(|mv| !mv.is_castle_move()));
+    }
+}
@@ -0,0 +1,118 @@
+use std::fmt::{Display, Formatter};
+
+use crate::board::constants::{BLACK, ColorBits, KING, PieceBits, QUEEN, WHITE};
+
+/// Castling rights of both players. Sides are identified by [`KING`] and [`QUEEN`], colors by [`WHITE`] and [`BLACK`].
+#[derive(Eq, PartialEq, Hash, Copy, Clone, Debug, Default)]
+pub struct CastlingRights {
+    bits: u8,
+}
+
+impl CastlingRights {
+    pub const NONE: Self = Self { bits: 0 };
+    pub const ALL: Self = Self { bits: 0b1111 };
+
+    /// In FEN order
+    const FEN_SYMBOLS: [(char, ColorBits, PieceBits); 4] = [
+        ('K', WHITE, KING),
+        ('Q', WHITE, QUEEN),
+        ('k', BLACK, KING),
+        ('q', BLACK, QUEEN),
+    ];
+
+    pub const fn from_bits(bits: u8) -> Self {
+        Self { bits: bits & Self::ALL.bits }
+    }
+
+    pub const fn bits(&self) -> u8 {
+        self.bits
+    }
+
+    #[inline(always)]
+    const fn mask(color: ColorBits, side: PieceBits) -> u8 {
+        (if side == KING { 0b01 } else { 0b10 }) << (2 * color)
+    }
+
+    pub const fn has(&self, color: ColorBits, side: PieceBits) -> bool {
+        (self.bits & Self::mask(color, side)) != 0
+    }
+
+    pub fn add(&mut self, color: ColorBits, side: PieceBits) {
+        self.bits |= Self::mask(color, side);
+    }
+
+    pub fn remove(&mut self, color: ColorBits, side: PieceBits) {
+        self.bits &= !Self::mask(color, side);
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        self.bits == 0
+    }
+
+    /// Parse the castling availability field of a FEN. Unknown symbols are ignored.
+    pub fn from_fen(castling_availability: &str) -> Self {
+        let mut result = Self::NONE;
+
+        for (symbol, color, side) in Self::FEN_SYMBOLS {
+            if castling_availability.contains(symbol) {
+                result.add(color, side);
+            }
+        }
+
+        result
+    }
+
+    /// Returns the castling availability field of a FEN, `-` if no player may castle.
+    pub fn to_fen(&self) -> String {
+        if self.is_empty() {
+            return "-".to_string();
+        }
+
+        Self::FEN_SYMBOLS.iter()
+            .filter(|(_, color, side)| self.has(*color, *side))
+            .map(|(symbol, _, _)| symbol)
+            .collect()
+    }
+}
+
+impl Display for CastlingRights {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_fen())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use inkayaku_core::fen::Fen;
+
+    use crate::board::Bitboard;
+    use crate::board::castling_rights::CastlingRights;
+    use crate::board::constants::{BLACK, KING, QUEEN, WHITE};
+
+    #[test]
+    fn test_round_trip_through_fen() {
+        for bits in 0..16 {
+            let castling_rights = CastlingRights::from_bits(bits);
+            let fen_string = format!("r3k2r/8/8/8/8/8/8/R3K2R w {} - 0 1", castling_rights.to_fen());
+
+            let bitboard = Bitboard::from_fen_string_unchecked(&fen_string);
+
+            assert_eq!(bitboard.castling_rights(), castling_rights, "{}", fen_string);
+            assert_eq!(CastlingRights::from_fen(&castling_rights.to_fen()), castling_rights);
+            assert_eq!(Fen::from(&bitboard).fen, fen_string);
+        }
+    }
+
+    #[test]
+    fn test_has_and_remove() {
+        let mut castling_rights = CastlingRights::ALL;
+        castling_rights.remove(WHITE, QUEEN);
+        castling_rights.remove(BLACK, KING);
+
+        assert!(castling_rights.has(WHITE, KING));
+        assert!(!castling_rights.has(WHITE, QUEEN));
+        assert!(!castling_rights.has(BLACK, KING));
+        assert!(castling_rights.has(BLACK, QUEEN));
+        assert_eq!(castling_rights.to_fen(), "Kq");
+    }
+}
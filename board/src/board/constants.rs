@@ -18,40 +18,42 @@ pub const DRAW: ColorBits = 2;
 
 // MSB . . . . . . . . . . . . . . . . . . . . . . . . . . . . . . . . . . . . LSB
 //
-// xxxxxxxxxxx x xxx xxxxxx xxxxxx xxxxxxxxxxxx x xxxxxx xxxxxx x x x x x x xxx xxx
-// UNUSED      |  |     |       |         |     |    |      |   | | | | | |  |   |
-//             |  |     |       |         |     |    |      |   | | | | | |  |   |
-//             |  |     |       |         |     |    |      |   | | | | | |  |    --> Piece moved
-//             |  |     |       |         |     |    |      |   | | | | | |  |
-//             |  |     |       |         |     |    |      |   | | | | | |   ------> Piece attacked
-//             |  |     |       |         |     |    |      |   | | | | | |
-//             |  |     |       |         |     |    |      |   | | | | |  ---------> Self lost king side castle
-//             |  |     |       |         |     |    |      |   | | | | |
-//             |  |     |       |         |     |    |      |   | | | |  -----------> Self lost queen side castle
-//             |  |     |       |         |     |    |      |   | | | |
-//             |  |     |       |         |     |    |      |   | | |  -------------> Opponent lost king side castle
-//             |  |     |       |         |     |    |      |   | | |
-//             |  |     |       |         |     |    |      |   | |  ---------------> Opponent lost queen side castle
-//             |  |     |       |         |     |    |      |   | |
-//             |  |     |       |         |     |    |      |   |  -----------------> Is castle move
-//             |  |     |       |         |     |    |      |   |
-//             |  |     |       |         |     |    |      |    -------------------> Is en passant attack
-//             |  |     |       |         |     |    |      |
-//             |  |     |       |         |     |    |       -----------------------> Source square
-//             |  |     |       |         |     |    |
-//             |  |     |       |         |     |     ------------------------------> Target square
-//             |  |     |       |         |     |
-//             |  |     |       |         |      ------------------------------------> Halfmove reset
-//             |  |     |       |         |
-//             |  |     |       |          ------------------------------------------> Previous halfmove
-//             |  |     |       |
-//             |  |     |        ----------------------------------------------------> Previous en passant square } Technically you can use files
-//             |  |     |
-//             |  |      ------------------------------------------------------------> Next en passant square     } and get that information from the pawn move
-//             |  |
-//             |   ------------------------------------------------------------------> Promotion Piece
-//             |
-//              ---------------------------------------------------------------------> Side to move
+// xxxxxxxx xxx x xxx xxxxxx xxxxxx xxxxxxxxxxxx x xxxxxx xxxxxx x x x x x x xxx xxx
+// UNUSED    |  |  |     |       |         |     |    |      |   | | | | | |  |   |
+//           |  |  |     |       |         |     |    |      |   | | | | | |  |   |
+//           |  |  |     |       |         |     |    |      |   | | | | | |  |    --> Piece moved
+//           |  |  |     |       |         |     |    |      |   | | | | | |  |
+//           |  |  |     |       |         |     |    |      |   | | | | | |   ------> Piece attacked
+//           |  |  |     |       |         |     |    |      |   | | | | | |
+//           |  |  |     |       |         |     |    |      |   | | | | |  ---------> Self lost king side castle
+//           |  |  |     |       |         |     |    |      |   | | | | |
+//           |  |  |     |       |         |     |    |      |   | | | |  -----------> Self lost queen side castle
+//           |  |  |     |       |         |     |    |      |   | | | |
+//           |  |  |     |       |         |     |    |      |   | | |  -------------> Opponent lost king side castle
+//           |  |  |     |       |         |     |    |      |   | | |
+//           |  |  |     |       |         |     |    |      |   | |  ---------------> Opponent lost queen side castle
+//           |  |  |     |       |         |     |    |      |   | |
+//           |  |  |     |       |         |     |    |      |   |  -----------------> Is castle move
+//           |  |  |     |       |         |     |    |      |   |
+//           |  |  |     |       |         |     |    |      |    -------------------> Is en passant attack
+//           |  |  |     |       |         |     |    |      |
+//           |  |  |     |       |         |     |    |       -----------------------> Source square
+//           |  |  |     |       |         |     |    |
+//           |  |  |     |       |         |     |     ------------------------------> Target square
+//           |  |  |     |       |         |     |
+//           |  |  |     |       |         |      ------------------------------------> Halfmove reset
+//           |  |  |     |       |         |
+//           |  |  |     |       |          ------------------------------------------> Previous halfmove
+//           |  |  |     |       |
+//           |  |  |     |        ----------------------------------------------------> Previous en passant square } Technically you can use files
+//           |  |  |     |
+//           |  |  |      ------------------------------------------------------------> Next en passant square     } and get that information from the pawn move
+//           |  |  |
+//           |  |   ------------------------------------------------------------------> Promotion Piece
+//           |  |
+//           |   ---------------------------------------------------------------------> Side to move
+//           |
+//            ------------------------------------------------------------------------> Castle rook file
 
 
 pub const EARLY: GameStageBits = 0;
@@ -82,6 +84,7 @@ pub const PREVIOUS_EN_PASSANT_SQUARE_MASK: MaskBits = 0b111111000000000000000000
 pub const NEXT_EN_PASSANT_SQUARE_MASK: MaskBits = 0b1111110000000000000000000000000000000000000000000;
 pub const PROMOTION_PIECE_MASK: MaskBits = 0b1110000000000000000000000000000000000000000000000000;
 pub const SIDE_TO_MOVE_MASK: MaskBits = 0b10000000000000000000000000000000000000000000000000000;
+pub const CASTLE_ROOK_FILE_MASK: MaskBits = 0b11100000000000000000000000000000000000000000000000000000;
 
 pub const PIECE_MOVED_SHIFT: ShiftBits = PIECE_MOVED_MASK.trailing_zeros();
 pub const PIECE_ATTACKED_SHIFT: ShiftBits = PIECE_ATTACKED_MASK.trailing_zeros();
@@ -99,6 +102,7 @@ pub const PREVIOUS_EN_PASSANT_SQUARE_SHIFT: ShiftBits = PREVIOUS_EN_PASSANT_SQUA
 pub const NEXT_EN_PASSANT_SQUARE_SHIFT: ShiftBits = NEXT_EN_PASSANT_SQUARE_MASK.trailing_zeros();
 pub const PROMOTION_PIECE_SHIFT: ShiftBits = PROMOTION_PIECE_MASK.trailing_zeros();
 pub const SIDE_TO_MOVE_SHIFT: ShiftBits = SIDE_TO_MOVE_MASK.trailing_zeros();
+pub const CASTLE_ROOK_FILE_SHIFT: ShiftBits = CASTLE_ROOK_FILE_MASK.trailing_zeros();
 
 // todo hmm this could lead to problems
 pub const NO_SQUARE: SquareShiftBits = 0;
@@ -232,19 +236,6 @@ pub const F1_MASK: SquareMaskBits = 1 << F1;
 pub const G1_MASK: SquareMaskBits = 1 << G1;
 pub const H1_MASK: SquareMaskBits = 1 << H1;
 
-pub const WHITE_QUEEN_SIDE_CASTLE_EMPTY_OCCUPANCY: OccupancyBits = B1_MASK | C1_MASK | D1_MASK;
-pub const WHITE_KING_SIDE_CASTLE_EMPTY_OCCUPANCY: OccupancyBits = F1_MASK | G1_MASK;
-
-pub const BLACK_QUEEN_SIDE_CASTLE_EMPTY_OCCUPANCY: OccupancyBits = B8_MASK | C8_MASK | D8_MASK;
-pub const BLACK_KING_SIDE_CASTLE_EMPTY_OCCUPANCY: OccupancyBits = F8_MASK | G8_MASK;
-
-
-pub const WHITE_QUEEN_SIDE_CASTLE_CHECK_OCCUPANCY: OccupancyBits = C1_MASK | D1_MASK | E1_MASK;
-pub const WHITE_KING_SIDE_CASTLE_CHECK_OCCUPANCY: OccupancyBits = E1_MASK | F1_MASK | G1_MASK;
-
-pub const BLACK_QUEEN_SIDE_CASTLE_CHECK_OCCUPANCY: OccupancyBits = C8_MASK | D8_MASK | E8_MASK;
-pub const BLACK_KING_SIDE_CASTLE_CHECK_OCCUPANCY: OccupancyBits = E8_MASK | F8_MASK | G8_MASK;
-
 pub const RANK_1_OCCUPANCY: OccupancyBits = A1_MASK | B1_MASK | C1_MASK | D1_MASK | E1_MASK | F1_MASK | G1_MASK | H1_MASK;
 pub const RANK_2_OCCUPANCY: OccupancyBits = A2_MASK | B2_MASK | C2_MASK | D2_MASK | E2_MASK | F2_MASK | G2_MASK | H2_MASK;
 pub const RANK_3_OCCUPANCY: OccupancyBits = A3_MASK | B3_MASK | C3_MASK | D3_MASK | E3_MASK | F3_MASK | G3_MASK | H3_MASK;
@@ -263,6 +254,24 @@ pub const FILE_F_OCCUPANCY: OccupancyBits = F1_MASK | F2_MASK | F3_MASK | F4_MAS
 pub const FILE_G_OCCUPANCY: OccupancyBits = G1_MASK | G2_MASK | G3_MASK | G4_MASK | G5_MASK | G6_MASK | G7_MASK | G8_MASK;
 pub const FILE_H_OCCUPANCY: OccupancyBits = H1_MASK | H2_MASK | H3_MASK | H4_MASK | H5_MASK | H6_MASK | H7_MASK | H8_MASK;
 
+/// Squares whose file and rank index sum to an even number, starting with a8
+pub const LIGHT_SQUARES_OCCUPANCY: OccupancyBits = light_squares_occupancy();
+pub const DARK_SQUARES_OCCUPANCY: OccupancyBits = !LIGHT_SQUARES_OCCUPANCY;
+
+const fn light_squares_occupancy() -> OccupancyBits {
+    let mut result = 0;
+    let mut square_shift = 0;
+
+    while square_shift < 64 {
+        if (square_shift % 8 + square_shift / 8) % 2 == 0 {
+            result |= 1 << square_shift;
+        }
+        square_shift += 1;
+    }
+
+    result
+}
+
 pub const CASTLE_MOVE_TRUE_MASK: u64 = CASTLE_MOVE_MASK;
 pub const CASTLE_MOVE_FALSE_MASK: u64 = 0;
 
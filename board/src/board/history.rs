@@ -0,0 +1,120 @@
+use std::ops::Deref;
+
+use crate::board::{Bitboard, Move};
+
+/// A [`Bitboard`] that records the Zobrist hashes of previous positions on [`BoardWithHistory::make`] to detect
+/// repetitions. The board itself doesn't track them so the search isn't slowed down, which uses its own history.
+///
+/// The board can only be changed through [`BoardWithHistory::make`] and [`BoardWithHistory::unmake`], so the history
+/// always matches it.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct BoardWithHistory {
+    board: Bitboard,
+    /// Hashes of the positions before each move made
+    history: Vec<u64>,
+}
+
+impl BoardWithHistory {
+    pub const fn new(board: Bitboard) -> Self {
+        Self { board, history: Vec::new() }
+    }
+
+    pub fn make(&mut self, mv: Move) {
+        self.history.push(self.board.zobrist_hash);
+        self.board.make(mv);
+    }
+
+    pub fn unmake(&mut self, mv: Move) {
+        self.board.unmake(mv);
+        self.history.pop();
+    }
+
+    /// Returns whether the current position occurred at least twice before since the last capture or pawn move with the
+    /// same player to move
+    pub fn is_threefold_repetition(&self) -> bool {
+        let plies = (self.board.halfmove_clock as usize).min(self.history.len());
+
+        self.history[self.history.len() - plies..]
+            .iter()
+            .rev()
+            .skip(1)
+            .step_by(2)
+            .filter(|&&hash| hash == self.board.zobrist_hash)
+            .count() >= 2
+    }
+
+    /// Returns whether the position is drawn by threefold repetition, the fifty-move rule or insufficient material
+    pub fn is_draw(&self) -> bool {
+        self.board.is_draw_with_repetition(self.is_threefold_repetition())
+    }
+
+    pub const fn board(&self) -> &Bitboard {
+        &self.board
+    }
+}
+
+impl Deref for BoardWithHistory {
+    type Target = Bitboard;
+
+    fn deref(&self) -> &Self::Target {
+        &self.board
+    }
+}
+
+impl Bitboard {
+    /// This board recording its history from now on, see [`BoardWithHistory`]
+    pub const fn with_history(self) -> BoardWithHistory {
+        BoardWithHistory::new(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::{Bitboard, BoardWithHistory, Move};
+
+    /// Find `uci` on a copy, the board with history can only be changed by making moves
+    fn find_uci(board: &BoardWithHistory, uci: &str) -> Move {
+        let mut board = *board.board();
+        board.find_uci(uci).unwrap()
+    }
+
+    #[test]
+    fn test_knight_shuffle() {
+        let mut board = Bitboard::default().with_history();
+        let mut moves = Vec::new();
+
+        for (index, uci) in "g1f3 g8f6 f3g1 f6g8 g1f3 g8f6 f3g1 f6g8".split_whitespace().enumerate() {
+            assert!(!board.is_threefold_repetition(), "{}", index);
+
+            let mv = find_uci(&board, uci);
+            board.make(mv);
+            moves.push(mv);
+        }
+
+        assert!(board.is_threefold_repetition());
+        assert!(board.is_draw());
+
+        board.unmake(moves.pop().unwrap());
+        assert!(!board.is_threefold_repetition());
+        assert_eq!(board.board(), &Bitboard::from_fen_string_unchecked("rnbqkb1r/pppppppp/5n2/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 7 4"));
+    }
+
+    #[test]
+    fn test_halfmove_clock_exceeds_history() {
+        let mut board = Bitboard::from_fen_string_unchecked("4k3/8/8/8/8/8/8/R3K3 w - - 60 80").with_history();
+
+        for uci in "a1a2 e8d8 a2a1 d8e8 a1a2 e8d8 a2a1".split_whitespace() {
+            assert!(!board.is_threefold_repetition());
+
+            let mv = find_uci(&board, uci);
+            board.make(mv);
+        }
+
+        assert!(!board.is_threefold_repetition());
+
+        let mv = find_uci(&board, "d8e8");
+        board.make(mv);
+
+        assert!(board.is_threefold_repetition());
+    }
+}
@@ -0,0 +1,63 @@
+use std::vec::IntoIter;
+
+use crate::board::{Bitboard, Move};
+
+/// Lazily yields the legal moves of a position. The pseudo legal moves are generated once, legality is only checked
+/// for the moves actually requested, so short circuiting callers don't check the remaining moves.
+pub struct MoveGen<'a> {
+    bitboard: &'a mut Bitboard,
+    pseudo_legal_moves: IntoIter<Move>,
+}
+
+impl<'a> MoveGen<'a> {
+    pub(crate) fn new(bitboard: &'a mut Bitboard) -> Self {
+        let pseudo_legal_moves = bitboard.generate_pseudo_legal_moves().into_iter();
+        Self { bitboard, pseudo_legal_moves }
+    }
+}
+
+impl Iterator for MoveGen<'_> {
+    type Item = Move;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let bitboard = &mut self.bitboard;
+        self.pseudo_legal_moves.find(|&mv| bitboard.is_move_legal(mv))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.pseudo_legal_moves.size_hint().1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::Bitboard;
+
+    #[test]
+    fn test_legal_moves_iter() {
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3",
+        ] {
+            let mut board = Bitboard::from_fen_string_unchecked(fen);
+
+            let expected = board.generate_legal_moves();
+            let actual = board.legal_moves_iter().collect::<Vec<_>>();
+
+            assert_eq!(actual, expected, "{}", fen);
+        }
+    }
+
+    #[test]
+    fn test_legal_moves_iter_short_circuits() {
+        let mut board = Bitboard::from_fen_string_unchecked("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1");
+        let original = board;
+
+        let first = board.legal_moves_iter().next();
+
+        assert_eq!(first, board.generate_legal_moves().first().copied());
+        assert_eq!(board, original);
+    }
+}
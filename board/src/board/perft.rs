@@ -0,0 +1,132 @@
+use crate::board::{Bitboard, Move};
+
+/// Node count of a perft run and the number of leaf moves of each kind, as in the published detailed perft tables
+#[derive(Debug, Default, Eq, PartialEq, Copy, Clone)]
+pub struct PerftResult {
+    pub nodes: u64,
+    /// Captures, including en passant captures
+    pub captures: u64,
+    pub en_passant: u64,
+    pub castles: u64,
+    pub promotions: u64,
+    pub checks: u64,
+    /// Checks given by a piece other than the moved one, castle checks by the rook are direct checks
+    pub discovery_checks: u64,
+    pub double_checks: u64,
+    pub checkmates: u64,
+}
+
+impl Bitboard {
+    /// Count the leaf nodes `depth` plies deep and classify the moves leading to them
+    pub fn perft_detailed(&mut self, depth: usize) -> PerftResult {
+        let mut result = PerftResult::default();
+
+        if depth == 0 {
+            result.nodes = 1;
+        } else {
+            self.perft_detailed_recursive(&mut result, &mut Vec::new(), depth);
+        }
+
+        result
+    }
+
+    fn perft_detailed_recursive(&mut self, result: &mut PerftResult, buffer: &mut Vec<Move>, depth: usize) {
+        self.generate_pseudo_legal_moves_with_buffer(buffer);
+
+        let mut next_buffer = Vec::new();
+        for &mv in buffer.iter() {
+            self.make(mv);
+
+            if self.is_valid() {
+                if depth == 1 {
+                    self.count_perft_leaf(mv, result);
+                } else {
+                    self.perft_detailed_recursive(result, &mut next_buffer, depth - 1);
+                    next_buffer.clear();
+                }
+            }
+
+            self.unmake(mv);
+        }
+    }
+
+    /// Classify `mv`, which has just been made
+    fn count_perft_leaf(&mut self, mv: Move, result: &mut PerftResult) {
+        result.nodes += 1;
+        result.captures += u64::from(mv.is_attack());
+        result.en_passant += u64::from(mv.is_en_passant_attack());
+        result.castles += u64::from(mv.is_castle_move());
+        result.promotions += u64::from(mv.is_promotion());
+
+        if self.checkers == 0 {
+            return;
+        }
+
+        result.checks += 1;
+
+        if self.checkers.count_ones() > 1 {
+            result.double_checks += 1;
+        }
+
+        if !mv.is_castle_move() && self.checkers & !(1 << mv.get_target_square()) != 0 {
+            result.discovery_checks += 1;
+        }
+
+        if !self.is_any_move_legal(&self.generate_pseudo_legal_moves()) {
+            result.checkmates += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::Bitboard;
+    use crate::board::perft::PerftResult;
+
+    #[allow(clippy::too_many_arguments)]
+    const fn expect(nodes: u64, captures: u64, en_passant: u64, castles: u64, promotions: u64, checks: u64, discovery_checks: u64, double_checks: u64, checkmates: u64) -> PerftResult {
+        PerftResult { nodes, captures, en_passant, castles, promotions, checks, discovery_checks, double_checks, checkmates }
+    }
+
+    fn assert_perft_detailed(fen: &str, expected: &[PerftResult]) {
+        let mut board = Bitboard::from_fen_string_unchecked(fen);
+
+        for (depth, expected) in expected.iter().enumerate() {
+            assert_eq!(&board.perft_detailed(depth + 1), expected, "Failed for {} at depth {}", fen, depth + 1);
+        }
+    }
+
+    #[test]
+    fn test_start_position() {
+        assert_perft_detailed("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", &[
+            expect(20, 0, 0, 0, 0, 0, 0, 0, 0),
+            expect(400, 0, 0, 0, 0, 0, 0, 0, 0),
+            expect(8_902, 34, 0, 0, 0, 12, 0, 0, 0),
+            expect(197_281, 1_576, 0, 0, 0, 469, 0, 0, 8),
+        ]);
+    }
+
+    #[test]
+    fn test_kiwipete() {
+        assert_perft_detailed("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", &[
+            expect(48, 8, 0, 2, 0, 0, 0, 0, 0),
+            expect(2_039, 351, 1, 91, 0, 3, 0, 0, 0),
+            expect(97_862, 17_102, 45, 3_162, 0, 993, 0, 0, 1),
+        ]);
+    }
+
+    #[test]
+    fn test_position_3() {
+        assert_perft_detailed("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", &[
+            expect(14, 1, 0, 0, 0, 2, 0, 0, 0),
+            expect(191, 14, 0, 0, 0, 10, 0, 0, 0),
+            expect(2_812, 209, 2, 0, 0, 267, 3, 0, 0),
+            expect(43_238, 3_348, 123, 0, 0, 1_680, 106, 0, 17),
+        ]);
+    }
+
+    #[test]
+    fn test_depth_0() {
+        assert_eq!(Bitboard::default().perft_detailed(0), PerftResult { nodes: 1, ..PerftResult::default() });
+    }
+}
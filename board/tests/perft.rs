@@ -1,49 +1,18 @@
 extern crate core;
 
-use std::fmt::{Debug, Formatter};
 use std::io::Read;
 use std::process::{Command, Stdio};
 use std::str::from_utf8;
 use std::thread::sleep;
 use std::time::Duration;
 
+use inkayaku_board::PerftResult;
 use inkayaku_core::fen::Fen;
 
-#[derive(PartialEq, Eq, Copy, Clone)]
-struct PerftResult {
-    nodes: u64,
-    captures: u64,
-    en_passant: u64,
-    castles: u64,
-    promotions: u64,
-    checks: u64,
-    discovery_checks: u64,
-    double_checks: u64,
-    checkmates: u64,
-}
-
-impl Debug for PerftResult {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "PerftResult {{ nodes: {} }}",
-            self.nodes
-        )
-    }
-}
-
-impl PerftResult {
-    pub const EMPTY: Self = Self::new();
-
-    pub const fn new() -> Self {
-        Self { nodes: 0, captures: 0, en_passant: 0, castles: 0, promotions: 0, checks: 0, discovery_checks: 0, double_checks: 0, checkmates: 0 }
-    }
-}
-
-const fn expect(nodes: u64) -> PerftResult {
+fn expect(nodes: u64) -> PerftResult {
     PerftResult {
         nodes,
-        ..PerftResult::EMPTY
+        ..PerftResult::default()
     }
 }
 
@@ -149,8 +118,7 @@ mod perft_debug {
         let fen: Fen = fen_str.parse().unwrap();
         let mut bitboard = Bitboard::from(&fen);
 
-        let moves = bitboard.perft(depth);
-        let actual: HashSet<(String, u64)> = HashSet::from_iter(moves.iter().map(|t| (t.0.to_uci_string(), t.1)));
+        let actual: HashSet<(String, u64)> = HashSet::from_iter(bitboard.perft_divide(depth));
         let expected: HashSet<(String, u64)> = HashSet::from_iter(REFERENCE_ENGINE.perft(&fen, depth));
 
         let actual_moves = actual.iter().map(|t| t.0.clone()).collect::<HashSet<_>>();
@@ -170,13 +138,13 @@ mod perft_debug {
         if has_excess {
             println!("EXCESS:");
             for x in excess.iter() {
-                println!("{}", &moves.iter().find(|&mv| &mv.0.to_uci_string() == x).unwrap().0);
+                println!("{}", x);
             }
         }
         if has_missing {
             println!("MISSING:");
             for x in missing.iter() {
-                println!("{}", &moves.iter().find(|&mv| &mv.0.to_uci_string() == x).unwrap().0);
+                println!("{}", x);
             }
         }
 
@@ -199,12 +167,12 @@ mod perft_debug {
 
 
             let string = &wrong_count.first().unwrap().0;
-            let option = &moves.iter().find(|&mv| &mv.0.to_uci_string() == string).unwrap().0;
+            let option = bitboard.find_uci(string).unwrap();
 
 
-            println!("Going deeper into {}: ", option.to_uci_string());
+            println!("Going deeper into {}: ", string);
             println!("{}", bitboard);
-            bitboard.make(*option);
+            bitboard.make(option);
             println!("{}", bitboard);
 
             let deep_fen = Fen::from(&bitboard);
@@ -228,10 +196,9 @@ mod perft_debug {
 
 #[cfg(test)]
 mod perft {
-    use std::usize;
     use std::time::SystemTime;
 
-    use inkayaku_board::{Bitboard, Move};
+    use inkayaku_board::Bitboard;
 
     use crate::{expect, PerftResult};
 
@@ -365,40 +332,49 @@ mod perft {
         )
     }
 
+    #[test]
+    fn perft_chess960() {
+        for (fen_string, nodes) in [
+            ("bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9", [21, 528, 12_189, 326_672]),
+            ("2nnrbkr/p1qppppp/8/1ppb4/6PP/3PP3/PPP2P2/BQNNRBKR w HEhe - 1 9", [21, 807, 18_002, 667_366]),
+            ("b1q1rrkb/pppppppp/3nn3/8/P7/1PPP4/4PPPP/BQNNRKRB w GEge - 3 9", [20, 479, 10_471, 273_318]),
+            ("qbbnnrkr/2pp2pp/p7/1p2pp2/8/P3PP2/1PPP1KPP/QBBNNR1R w hf - 0 9", [22, 593, 13_440, 382_958]),
+            ("1nbbnrkr/p1p1ppp1/3p4/1p3P1p/3Pq2P/8/PPP1P1P1/QNBBNRKR w HFhf - 0 9", [28, 1_120, 31_058, 1_171_749]),
+        ] {
+            run_perft(fen_string, &nodes.map(expect));
+        }
+    }
+
+    #[test]
+    fn perft_verified() {
+        for (fen_string, nodes) in [
+            ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", 197_281),
+            ("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq -", 4_085_603),
+            ("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - -", 43_238),
+            ("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1", 422_333),
+            ("r2q1rk1/pP1p2pp/Q4n2/bbp1p3/Np6/1B3NBn/pPPP1PPP/R3K2R b KQ - 0 1", 422_333),
+            ("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8", 2_103_487),
+            ("r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10", 3_894_594),
+            ("bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9", 326_672),
+        ] {
+            let mut board = Bitboard::from_fen_string_unchecked(fen_string);
+
+            assert_eq!(board.perft_verified(4), nodes, "Failed for {}", fen_string);
+        }
+    }
+
     fn run_perft(fen_string: &str, expect: &[PerftResult]) {
         let mut board = Bitboard::from_fen_string_unchecked(fen_string);
 
         let n = expect.iter().filter(|result| result.nodes < LIMIT).count();
         let actual =
             (1..=n)
-                .map(|index| {
-                    let mut result = PerftResult::new();
-                    _run_perft_recursive(&mut board, &mut result, &mut Vec::new(), index);
-                    result
-                })
+                .map(|index| crate::expect(board.perft_detailed(index).nodes))
                 .collect::<Vec<_>>();
 
         assert_eq!(actual, expect.iter().cloned().take(n).collect::<Vec<_>>(), "Failed for {}", fen_string);
-    }
 
-    fn _run_perft_recursive(board: &mut Bitboard, result: &mut PerftResult, buffer: &mut Vec<Move>, current_depth: usize) {
-        if current_depth == 0 {
-            result.nodes += 1;
-            return;
-        }
-
-        board.generate_pseudo_legal_moves_with_buffer(buffer);
-
-        let mut next_buffer = Vec::new();
-        for mv in buffer {
-            board.make(*mv);
-
-            if board.is_valid() {
-                _run_perft_recursive(board, result, &mut next_buffer, current_depth - 1);
-                next_buffer.clear();
-            }
-
-            board.unmake(*mv);
-        }
+        let bulk_nodes = board.perft(n).iter().map(|(_, nodes)| nodes).sum::<u64>();
+        assert_eq!(bulk_nodes, actual.last().map_or(0, |result| result.nodes), "Bulk perft failed for {}", fen_string);
     }
 }
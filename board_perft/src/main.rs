@@ -1,50 +1,18 @@
 extern crate core;
 
-use std::fmt::{Debug, Formatter};
-
-#[derive(PartialEq, Eq, Copy, Clone)]
-struct PerftResult {
-    nodes: u64,
-    captures: u64,
-    en_passant: u64,
-    castles: u64,
-    promotions: u64,
-    checks: u64,
-    discovery_checks: u64,
-    double_checks: u64,
-    checkmates: u64,
-}
-
-impl Debug for PerftResult {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "PerftResult {{ nodes: {} }}",
-            self.nodes
-        )
-    }
-}
-
-impl PerftResult {
-    pub const EMPTY: Self = Self::new();
-
-    pub const fn new() -> Self {
-        Self { nodes: 0, captures: 0, en_passant: 0, castles: 0, promotions: 0, checks: 0, discovery_checks: 0, double_checks: 0, checkmates: 0 }
-    }
-}
+use inkayaku_board::PerftResult;
 
-const fn expect(nodes: u64) -> PerftResult {
+fn expect(nodes: u64) -> PerftResult {
     PerftResult {
         nodes,
-        ..PerftResult::EMPTY
+        ..PerftResult::default()
     }
 }
 
 pub mod perft {
     use std::time::{Duration, SystemTime};
-    use std::usize;
 
-    use inkayaku_board::{Bitboard, Move};
+    use inkayaku_board::Bitboard;
 
     use crate::{expect, PerftResult};
 
@@ -176,11 +144,7 @@ pub mod perft {
         let n = expect.len();
         let actual =
             (1..=n)
-                .map(|index| {
-                    let mut result = PerftResult::new();
-                    run_perft_recursive(&mut board, &mut result, &mut Vec::new(), index);
-                    result
-                })
+                .map(|index| crate::expect(board.perft(index).iter().map(|(_, nodes)| nodes).sum()))
                 .collect::<Vec<_>>();
 
         let nodes: u64 = expect.iter().map(|e| e.nodes).sum();
@@ -189,27 +153,6 @@ pub mod perft {
         let nps = nodes as f64 / start.elapsed().unwrap_or(Duration::ZERO).as_micros() as f64;
         println!("{:?} - {:.1} MM NPS", start.elapsed(), nps);
     }
-
-    fn run_perft_recursive(board: &mut Bitboard, result: &mut PerftResult, buffer: &mut Vec<Move>, current_depth: usize) {
-        if current_depth == 0 {
-            result.nodes += 1;
-            return;
-        }
-
-        board.generate_pseudo_legal_moves_with_buffer(buffer);
-
-        let mut next_buffer = Vec::new();
-        for mv in buffer {
-            board.make(*mv);
-
-            if board.is_valid() {
-                run_perft_recursive(board, result, &mut next_buffer, current_depth - 1);
-                next_buffer.clear();
-            }
-
-            board.unmake(*mv);
-        }
-    }
 }
 
 fn main() {
@@ -19,6 +19,28 @@ impl Piece {
 
     pub const VALUES: [Self; 6] = [Self::PAWN, Self::KNIGHT, Self::BISHOP, Self::ROOK, Self::QUEEN, Self::KING];
 
+    /// Material value in centipawns, the king has none
+    pub const fn value(&self) -> i32 {
+        match self.index {
+            1 => 100,
+            2 => 320,
+            3 => 330,
+            4 => 500,
+            5 => 900,
+            _ => 0,
+        }
+    }
+
+    /// Contribution to the early game phase, 24 with all pieces on the board
+    pub const fn phase_weight(&self) -> i32 {
+        match self.index {
+            2 | 3 => 1,
+            4 => 2,
+            5 => 4,
+            _ => 0,
+        }
+    }
+
     pub const fn to_color(&self, color: Color) -> ColoredPiece {
         match color.index {
             0 => self.to_white(),
@@ -68,3 +90,22 @@ impl Piece {
         Self::VALUES[index - 1]
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::constants::piece::Piece;
+
+    #[test]
+    fn test_value() {
+        let actual = Piece::VALUES.map(|piece| piece.value());
+
+        assert_eq!(actual, [100, 320, 330, 500, 900, 0]);
+    }
+
+    #[test]
+    fn test_phase_weight() {
+        let actual = Piece::VALUES.map(|piece| piece.phase_weight());
+
+        assert_eq!(actual, [0, 1, 1, 2, 4, 0]);
+    }
+}
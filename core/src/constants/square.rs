@@ -1,4 +1,5 @@
 use std::fmt::{Debug, Formatter};
+use std::iter::successors;
 
 use crate::constants::{to_square_index_from_indices, to_square_index_from_structs};
 use crate::constants::direction::Direction;
@@ -217,6 +218,19 @@ impl Square {
         Self::from_indices_unchecked(file.index as usize, rank.index as usize)
     }
 
+    /// Number of king moves between `self` and `other`, the chebyshev distance
+    pub const fn distance(self, other: Self) -> u8 {
+        let file_distance = self.file.index.abs_diff(other.file.index);
+        let rank_distance = self.rank.index.abs_diff(other.rank.index);
+
+        if file_distance > rank_distance { file_distance } else { rank_distance }
+    }
+
+    /// Sum of the file and rank distances between `self` and `other`
+    pub const fn manhattan(self, other: Self) -> u8 {
+        self.file.index.abs_diff(other.file.index) + self.rank.index.abs_diff(other.rank.index)
+    }
+
     pub const fn translate(&self, direction: &Direction) -> Option<Self> {
         let file = self.file.index as i32 + direction.delta_file;
         let rank = self.rank.index as i32 + direction.delta_rank;
@@ -227,6 +241,43 @@ impl Square {
             Self::from_indices(file as usize, rank as usize)
         }
     }
+
+    /// The squares reached by repeatedly translating `self` by `direction`, excluding `self`, until the edge of the board
+    pub fn ray(self, direction: Direction) -> impl Iterator<Item = Self> {
+        successors(self.translate(&direction), move |square| square.translate(&direction))
+    }
 }
 
+#[cfg(test)]
+mod test {
+    use crate::constants::direction::Direction;
+    use crate::constants::square::Square;
+
+    #[test]
+    fn test_distances() {
+        for (from, to, chebyshev, manhattan) in [
+            (Square::A1, Square::H8, 7, 14),
+            (Square::H1, Square::A8, 7, 14),
+            (Square::A1, Square::H1, 7, 7),
+            (Square::A8, Square::A1, 7, 7),
+            (Square::E4, Square::E5, 1, 1),
+            (Square::E4, Square::F5, 1, 2),
+            (Square::E4, Square::D3, 1, 2),
+            (Square::E4, Square::F6, 2, 3),
+            (Square::D5, Square::D5, 0, 0),
+        ] {
+            assert_eq!(from.distance(to), chebyshev, "{:?} {:?}", from, to);
+            assert_eq!(to.distance(from), chebyshev, "{:?} {:?}", to, from);
+            assert_eq!(from.manhattan(to), manhattan, "{:?} {:?}", from, to);
+            assert_eq!(to.manhattan(from), manhattan, "{:?} {:?}", to, from);
+        }
+    }
 
+    #[test]
+    fn test_ray() {
+        assert_eq!(Square::A1.ray(Direction::NORTH_EAST).collect::<Vec<_>>(), vec![Square::B2, Square::C3, Square::D4, Square::E5, Square::F6, Square::G7, Square::H8]);
+        assert_eq!(Square::E4.ray(Direction::WEST).collect::<Vec<_>>(), vec![Square::D4, Square::C4, Square::B4, Square::A4]);
+        assert_eq!(Square::H5.ray(Direction::EAST).count(), 0);
+        assert_eq!(Square::A1.ray(Direction::SOUTH_WEST).count(), 0);
+    }
+}
@@ -0,0 +1,216 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use crate::fen::{Fen, FenParseError};
+
+/// Extended position description, a position followed by operations like `bm Nf3;` or `id "WAC.001";`, as used by
+/// test suites
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Epd {
+    pub fen: Fen,
+    /// Operands by opcode, including the quotes of string operands
+    operations: HashMap<String, String>,
+    /// Opcodes in the order they were parsed in, to round trip
+    opcodes: Vec<String>,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum EpdParseError {
+    MissingPositionFields(String),
+    InvalidFen(FenParseError),
+    /// An operation without opcode or with an unterminated string operand
+    InvalidOperation(String),
+}
+
+impl Epd {
+    /// An EPD of `fen` with the `(opcode, operand)` pairs of `operations` in order, string operands include their quotes
+    pub fn new(fen: Fen, operations: Vec<(String, String)>) -> Self {
+        Self {
+            fen,
+            opcodes: operations.iter().map(|(opcode, _)| opcode.clone()).collect(),
+            operations: operations.into_iter().collect(),
+        }
+    }
+
+    pub fn operations(&self) -> &HashMap<String, String> {
+        &self.operations
+    }
+
+    pub fn operation(&self, opcode: &str) -> Option<&str> {
+        self.operations.get(opcode).map(String::as_str)
+    }
+
+    /// The best moves of the `bm` operation in SAN
+    pub fn best_moves(&self) -> Vec<String> {
+        self.moves("bm")
+    }
+
+    /// The moves to avoid of the `am` operation in SAN
+    pub fn avoid_moves(&self) -> Vec<String> {
+        self.moves("am")
+    }
+
+    /// The `id` operation without quotes
+    pub fn id(&self) -> Option<&str> {
+        self.operation("id").map(|id| id.trim_matches('"'))
+    }
+
+    fn moves(&self, opcode: &str) -> Vec<String> {
+        self.operation(opcode).map(|moves| moves.split_whitespace().map(str::to_string).collect()).unwrap_or_default()
+    }
+
+    /// Split `s` into the position and the operations, the halfmove and fullmove clocks are optional
+    fn split_position(s: &str) -> Result<(&str, &str), EpdParseError> {
+        let mut end = 0;
+
+        for field in 0..6 {
+            let rest = &s[end..];
+            let start = end + (rest.len() - rest.trim_start().len());
+            let field_end = s[start..].find(char::is_whitespace).map_or(s.len(), |index| start + index);
+            let token = &s[start..field_end];
+
+            if token.is_empty() || (field >= 4 && !token.chars().all(|c| c.is_ascii_digit())) {
+                break;
+            }
+
+            end = field_end;
+        }
+
+        let position = s[..end].trim();
+
+        if position.split_whitespace().count() < 4 {
+            return Err(EpdParseError::MissingPositionFields(s.to_string()));
+        }
+
+        Ok((position, &s[end..]))
+    }
+
+    fn parse_operations(s: &str) -> Result<Vec<(String, String)>, EpdParseError> {
+        let mut result = Vec::new();
+        let mut current = String::new();
+        let mut is_quoted = false;
+
+        for c in s.chars() {
+            match c {
+                '"' => {
+                    is_quoted = !is_quoted;
+                    current.push(c);
+                }
+                ';' if !is_quoted => {
+                    result.push(Self::parse_operation(&current)?);
+                    current.clear();
+                }
+                _ => current.push(c),
+            }
+        }
+
+        if is_quoted {
+            return Err(EpdParseError::InvalidOperation(current));
+        }
+
+        // The last operation may omit its semicolon
+        if !current.trim().is_empty() {
+            result.push(Self::parse_operation(&current)?);
+        }
+
+        Ok(result)
+    }
+
+    fn parse_operation(operation: &str) -> Result<(String, String), EpdParseError> {
+        let operation = operation.trim();
+
+        match operation.split_once(char::is_whitespace) {
+            Some((opcode, operand)) => Ok((opcode.to_string(), operand.trim().to_string())),
+            None if !operation.is_empty() => Ok((operation.to_string(), String::new())),
+            None => Err(EpdParseError::InvalidOperation(operation.to_string())),
+        }
+    }
+}
+
+impl FromStr for Epd {
+    type Err = EpdParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (position, operations) = Self::split_position(s.trim())?;
+
+        let fen = Fen::from_str(position).map_err(EpdParseError::InvalidFen)?;
+        let operations = Self::parse_operations(operations)?;
+
+        Ok(Self::new(fen, operations))
+    }
+}
+
+impl Display for Epd {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.fen.fen)?;
+
+        for opcode in &self.opcodes {
+            match self.operations.get(opcode).map(String::as_str) {
+                Some("") | None => write!(f, " {};", opcode)?,
+                Some(operand) => write!(f, " {} {};", opcode, operand)?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use crate::epd::{Epd, EpdParseError};
+    use crate::fen::Fen;
+
+    #[test]
+    fn test_parse() {
+        let epd = Epd::from_str(r#"2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - bm Qg6; id "WAC.001";"#).unwrap();
+
+        assert_eq!(epd.fen.fen, "2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - -");
+        assert_eq!(epd.best_moves(), vec!["Qg6".to_string()]);
+        assert!(epd.avoid_moves().is_empty());
+        assert_eq!(epd.id(), Some("WAC.001"));
+        assert_eq!(epd.operations().len(), 2);
+    }
+
+    #[test]
+    fn test_parse_multiple_moves_and_clocks() {
+        let epd = Epd::from_str(r#"r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3 bm Bb5 Bc4; am Qe2; c0 "quoted; semicolon";"#).unwrap();
+
+        assert_eq!(epd.fen.get_halfmove_clock(), "2");
+        assert_eq!(epd.fen.get_fullmove_clock(), "3");
+        assert_eq!(epd.best_moves(), vec!["Bb5".to_string(), "Bc4".to_string()]);
+        assert_eq!(epd.avoid_moves(), vec!["Qe2".to_string()]);
+        assert_eq!(epd.operation("c0"), Some(r#""quoted; semicolon""#));
+        assert_eq!(epd.id(), None);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for epd in [
+            r#"2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - bm Qg6; id "WAC.001";"#,
+            r#"r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3 am Qe2; bm Bb5 Bc4; c0 "quoted; semicolon"; noop;"#,
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq -",
+        ] {
+            assert_eq!(Epd::from_str(epd).unwrap().to_string(), epd);
+        }
+    }
+
+    #[test]
+    fn test_new() {
+        let fen = Fen::from_str("2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - -").unwrap();
+        let epd = Epd::new(fen, vec![("bm".to_string(), "Qg6".to_string()), ("id".to_string(), r#""WAC.001""#.to_string())]);
+
+        assert_eq!(epd.to_string(), r#"2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - bm Qg6; id "WAC.001";"#);
+        assert_eq!(epd.id(), Some("WAC.001"));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(Epd::from_str("8/8/8/8 w"), Err(EpdParseError::MissingPositionFields("8/8/8/8 w".to_string())));
+        assert!(matches!(Epd::from_str("8/8/8/8/8/8/8/7 w - - bm Ka1;"), Err(EpdParseError::InvalidFen(_))));
+        assert!(matches!(Epd::from_str(r#"8/8/8/8/8/8/8/8 w - - id "unterminated;"#), Err(EpdParseError::InvalidOperation(_))));
+        assert!(matches!(Epd::from_str("8/8/8/8/8/8/8/8 w - - bm Ka1; ;"), Err(EpdParseError::InvalidOperation(_))));
+    }
+}
@@ -38,7 +38,7 @@ lazy_static! {
 
 fn _construct_fen_regex() -> Regex {
     #[allow(clippy::unwrap_used)]
-    Regex::new(r"^([PNBRQKpnbrqk1-8]{1,8}(?:/[PNBRQKpnbrqk1-8]{1,8}){7}) ([bw]) (KQ?k?q?|Qk?q?|kq?|q|-) ([a-h][1-8]|-)(?: (\d+) (\d+))?$").unwrap()
+    Regex::new(r"^([PNBRQKpnbrqk1-8]{1,8}(?:/[PNBRQKpnbrqk1-8]{1,8}){7}) ([bw]) (KQ?k?q?|Qk?q?|kq?|q|[A-H]{1,2}[a-h]{0,2}|[a-h]{1,2}|-) ([a-h][1-8]|-)(?: (\d+) (\d+))?$").unwrap()
 }
 
 lazy_static! {
@@ -270,6 +270,22 @@ mod tests {
         )
     }
 
+    #[test]
+    fn fen_ok_shredder_castling() {
+        test(
+            "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9",
+            Ok(ExtractedFen::new(
+                "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9",
+                "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR",
+                "w",
+                "HFhf",
+                "-",
+                "2",
+                "9",
+            )),
+        )
+    }
+
     #[test]
     fn fen_err_2() {
         test(
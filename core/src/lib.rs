@@ -1,4 +1,5 @@
 extern crate core;
 
+pub mod epd;
 pub mod fen;
 pub mod constants;
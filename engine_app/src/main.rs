@@ -3,12 +3,14 @@ use std::io::stdin;
 use std::sync::Arc;
 
 use inkayaku_engine_core::Engine;
-use inkayaku_uci::{UciEngine, UciTx};
+use inkayaku_uci::{Info, UciEngine, UciTx};
 use inkayaku_uci::console::{ConsoleUciRx, ConsoleUciTx};
-use inkayaku_uci::console::ConsoleUciRxError::CommandParseError;
+use inkayaku_uci::console::ConsoleUciRxError::{CommandParseError, CommandParseWarning};
 use inkayaku_uci::parser::ParserError::UnknownCommand;
 use inkayaku_uci::UciCommand::SetDebug;
 
+mod selftest;
+
 #[cfg(feature = "debug")]
 const DEBUG_DEFAULT: bool = true;
 #[cfg(not(feature = "debug"))]
@@ -27,7 +29,9 @@ fn main() {
                 }
                 engine.borrow_mut().accept(command);
             }
+            Err(CommandParseError(UnknownCommand(command))) if command == "selftest" => run_selftest(),
             Err(CommandParseError(UnknownCommand(command))) => eprintln!("Unknown Command: {}", command),
+            Err(CommandParseWarning(warning)) => tx.info(&Info { string: Some(format!("Ignored token: {:?}", warning)), ..Info::EMPTY }),
             Err(error) => eprintln!("Failed to parse command: {:?}", error),
         }
     };
@@ -36,6 +40,18 @@ fn main() {
     rx.start();
 }
 
+fn run_selftest() {
+    let results = selftest::run(&selftest::SUITE);
+
+    for result in &results {
+        let actual = result.actual.as_ref().map_or_else(|| "0000".to_string(), ToString::to_string);
+        print_ln(&format!("selftest {} {} (expected {}, found {})", if result.passed() { "passed" } else { "FAILED" }, result.name, result.expected, actual));
+    }
+
+    let passed = results.iter().filter(|result| result.passed()).count();
+    print_ln(&format!("selftest {}/{} passed", passed, results.len()));
+}
+
 fn read_line() -> Result<String, std::io::Error> {
     let mut result = String::new();
     stdin().read_line(&mut result)?;
@@ -0,0 +1,71 @@
+use std::str::FromStr;
+use std::sync::Arc;
+use std::sync::mpsc::channel;
+
+use inkayaku_core::fen::Fen;
+use inkayaku_engine_core::Engine;
+use inkayaku_uci::{Go, UciCommand, UciEngine, UciMove, UciTxCommand};
+use inkayaku_uci::command::CommandUciTx;
+
+/// A position with a known best move that has to be found within `depth`
+pub struct SelfTestCase {
+    pub name: &'static str,
+    pub fen: &'static str,
+    pub best_move: &'static str,
+    pub depth: u64,
+}
+
+pub struct SelfTestResult {
+    pub name: &'static str,
+    pub expected: &'static str,
+    pub actual: Option<UciMove>,
+}
+
+impl SelfTestResult {
+    pub fn passed(&self) -> bool {
+        self.actual.as_ref().is_some_and(|actual| actual.to_string() == self.expected)
+    }
+}
+
+pub const SUITE: [SelfTestCase; 5] = [
+    SelfTestCase { name: "back rank mate in 1", fen: "6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", best_move: "a1a8", depth: 3 },
+    SelfTestCase { name: "scholar's mate in 1", fen: "r1bqkb1r/pppp1ppp/2n2n2/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 4 4", best_move: "h5f7", depth: 3 },
+    SelfTestCase { name: "rook sacrifice mate in 2", fen: "kbK5/pp6/1P6/8/8/8/8/R7 w - - 0 1", best_move: "a1a6", depth: 4 },
+    SelfTestCase { name: "hanging queen", fen: "4k3/8/8/3q4/8/8/8/3RK3 w - - 0 1", best_move: "d1d5", depth: 4 },
+    SelfTestCase { name: "knight fork", fen: "4k3/8/8/1q6/4N3/8/8/4K3 w - - 0 1", best_move: "e4d6", depth: 4 },
+];
+
+/// Search every case of `suite` on a fresh engine and collect the results
+pub fn run(suite: &[SelfTestCase]) -> Vec<SelfTestResult> {
+    let (tx, rx) = channel();
+    let mut engine = Engine::new(Arc::new(CommandUciTx::new(tx)), false);
+
+    let result = suite.iter().map(|case| {
+        #[allow(clippy::unwrap_used)]
+        let fen = Fen::from_str(case.fen).unwrap();
+
+        engine.accept(UciCommand::UciNewGame);
+        engine.accept(UciCommand::PositionFrom { fen, moves: vec![] });
+        engine.accept(UciCommand::Go { go: Go { depth: Some(case.depth), ..Go::default() } });
+
+        let actual = rx.iter().find_map(|command| if let UciTxCommand::BestMove { best_move, .. } = command { Some(best_move) } else { None }).flatten();
+
+        SelfTestResult { name: case.name, expected: case.best_move, actual }
+    }).collect();
+
+    engine.accept(UciCommand::Quit);
+
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use crate::selftest::{run, SUITE};
+
+    #[test]
+    fn test_suite_passes() {
+        for result in run(&SUITE) {
+            assert!(result.passed(), "{} expected {} but found {:?}", result.name, result.expected, result.actual);
+        }
+    }
+}
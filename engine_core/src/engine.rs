@@ -11,15 +11,40 @@ use UciCommand::Go as GoCommand;
 use crate::engine::heuristic::simple::SimpleHeuristic;
 use crate::engine::move_order::MvvLvaMoveOrder;
 use crate::engine::search::{EngineOptions, Search, SearchMessage};
-use crate::engine::search::SearchMessage::{UciDebug, UciPonderHit, UciQuit, UciStop};
+use crate::engine::search::SearchMessage::{UciDebug, UciPonderHit, UciQuit, UciSetOption, UciStop};
 
+mod book;
 mod heuristic;
 mod move_order;
 mod zobrist_history;
 mod metrics;
 mod search;
 mod table;
+mod tablebase;
 
+/// The engine, answering the [`UciCommand`]s it accepts through `uci_tx` while searching on a separate thread.
+///
+/// Record the output with a [`inkayaku_uci::CommandUciTx`] to test an integration:
+///
+/// ```
+/// use std::sync::Arc;
+///
+/// use inkayaku_core::fen::Fen;
+/// use inkayaku_engine_core::Engine;
+/// use inkayaku_uci::{CommandUciTx, Go, UciCommand, UciEngine, UciTxCommand};
+///
+/// let (uci_tx, rx) = CommandUciTx::channel();
+/// let mut engine = Engine::new(Arc::new(uci_tx), false);
+///
+/// engine.accept(UciCommand::UciNewGame);
+/// engine.accept(UciCommand::PositionFrom { fen: Fen::default(), moves: vec![] });
+/// engine.accept(UciCommand::Go { go: Go { depth: Some(2), ..Go::default() } });
+///
+/// // Search results arrive asynchronously, `bestmove` is sent last
+/// let best_move = rx.iter().find_map(|command| if let UciTxCommand::BestMove { best_move, .. } = command { Some(best_move) } else { None });
+///
+/// assert!(best_move.flatten().is_some());
+/// ```
 pub struct Engine<T: UciTx + Send + Sync + 'static> {
     uci_tx: Arc<T>,
     debug: bool,
@@ -37,7 +62,7 @@ impl<T: UciTx + Send + Sync + 'static> Engine<T> {
 
     fn start_search_thread(search_rx: Receiver<SearchMessage>, uci_tx: Arc<T>, debug: bool) -> JoinHandle<()> {
         thread::spawn(move || {
-            Search::new(uci_tx, search_rx, SimpleHeuristic, MvvLvaMoveOrder, EngineOptions { debug, ..EngineOptions::default() }).idle();
+            Search::new(uci_tx, search_rx, SimpleHeuristic::default(), MvvLvaMoveOrder, EngineOptions { debug, ..EngineOptions::default() }).idle();
         })
     }
 }
@@ -50,6 +75,23 @@ impl<T: UciTx + Send + Sync + 'static> UciEngine for Engine<T> {
             Uci => {
                 self.uci_tx.id_name("Inkayaku");
                 self.uci_tx.id_author("Marvin Kuhnke (see https://github.com/marvk/rust-chess)");
+
+                let defaults = EngineOptions::default();
+                self.uci_tx.option_spin("Hash", defaults.hash_size_mb as i32, 1, 65536);
+                self.uci_tx.option_button("Clear Hash");
+                self.uci_tx.option_spin("Threads", defaults.threads as i32, 1, 64);
+                self.uci_tx.option_spin("Contempt", defaults.contempt_factor, -100, 100);
+                self.uci_tx.option_spin("Tempo", defaults.tempo, 0, 100);
+                self.uci_tx.option_check("Ponder", defaults.ponder);
+                self.uci_tx.option_spin("MultiPV", defaults.multi_pv as i32, 1, 256);
+                self.uci_tx.option_check("UCI_ShowRefutations", defaults.show_refutations);
+                self.uci_tx.option_check("ShowWDL", defaults.show_wdl);
+                self.uci_tx.option_spin("WDLScale", defaults.wdl_scale, 1, 1000);
+                self.uci_tx.option_string("SyzygyPath", defaults.syzygy_path.as_deref().unwrap_or("<empty>"));
+                self.uci_tx.option_spin("SyzygyProbeLimit", defaults.syzygy_probe_limit as i32, 0, 7);
+                self.uci_tx.option_check("OwnBook", defaults.own_book);
+                self.uci_tx.option_string("BookFile", defaults.book_file.as_deref().unwrap_or("<empty>"));
+
                 self.uci_tx.uci_ok();
             }
             SetDebug { debug } => {
@@ -60,10 +102,10 @@ impl<T: UciTx + Send + Sync + 'static> UciEngine for Engine<T> {
                 self.uci_tx.ready_ok();
             }
             SetOption { name } => {
-                todo!()
+                self.search_tx.send(UciSetOption(name, String::new())).unwrap();
             }
             SetOptionValue { name, value } => {
-                todo!()
+                self.search_tx.send(UciSetOption(name, value)).unwrap();
             }
             RegisterLater => {}
             Register { .. } => {
@@ -95,15 +137,21 @@ impl<T: UciTx + Send + Sync + 'static> UciEngine for Engine<T> {
 
 #[cfg(test)]
 mod test {
+    use std::iter;
     use std::str::FromStr;
-    use std::sync::Arc;
+    use std::sync::{Arc, Mutex};
     use std::sync::mpsc::channel;
+    use std::thread::sleep;
+    use std::time::{Duration, Instant};
 
+    use inkayaku_board::Bitboard;
     use inkayaku_core::fen::Fen;
     use inkayaku_uci::{UciEngine, Go, Score, UciCommand, UciMove, UciTxCommand};
     use inkayaku_uci::command::CommandUciTx;
+    use inkayaku_uci::console::ConsoleUciTx;
 
     use crate::engine::Engine;
+    use crate::engine::zobrist_history::ZobristHistory;
 
     #[test]
     fn test_threefold() {
@@ -159,6 +207,163 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_uci_advertises_options() {
+        let (tx, rx) = channel();
+        let mut engine = Engine::new(Arc::new(CommandUciTx::new(tx)), false);
+        engine.accept(UciCommand::Uci);
+
+        let commands = rx.iter().take_while(|command| !matches!(command, UciTxCommand::Ok)).collect::<Vec<_>>();
+
+        assert!(commands.contains(&UciTxCommand::OptionSpin { name: "Hash".to_string(), default: 16, min: 1, max: 65536 }));
+        assert!(commands.contains(&UciTxCommand::OptionButton { name: "Clear Hash".to_string() }));
+        assert!(commands.contains(&UciTxCommand::OptionSpin { name: "Threads".to_string(), default: 1, min: 1, max: 64 }));
+        assert!(commands.contains(&UciTxCommand::OptionSpin { name: "Contempt".to_string(), default: 20, min: -100, max: 100 }));
+        assert!(commands.contains(&UciTxCommand::OptionSpin { name: "Tempo".to_string(), default: 10, min: 0, max: 100 }));
+        assert!(commands.contains(&UciTxCommand::OptionCheck { name: "Ponder".to_string(), default: false }));
+        assert!(commands.contains(&UciTxCommand::OptionCheck { name: "UCI_ShowRefutations".to_string(), default: false }));
+        assert!(commands.contains(&UciTxCommand::OptionCheck { name: "ShowWDL".to_string(), default: false }));
+        assert!(commands.contains(&UciTxCommand::OptionSpin { name: "MultiPV".to_string(), default: 1, min: 1, max: 256 }));
+        assert!(commands.contains(&UciTxCommand::OptionString { name: "SyzygyPath".to_string(), default: "<empty>".to_string() }));
+        assert!(commands.contains(&UciTxCommand::OptionSpin { name: "SyzygyProbeLimit".to_string(), default: 7, min: 0, max: 7 }));
+
+        engine.accept(UciCommand::SetOption { name: "Clear Hash".to_string() });
+        engine.accept(UciCommand::SetOptionValue { name: "Unknown".to_string(), value: "42".to_string() });
+        engine.accept(UciCommand::IsReady);
+
+        assert!(rx.iter().any(|command| command == UciTxCommand::ReadyOk));
+    }
+
+    #[test]
+    fn test_ponder_move_fallback_on_short_pv() {
+        let (tx, rx) = channel();
+        let mut engine = Engine::new(Arc::new(CommandUciTx::new(tx)), false);
+        engine.accept(UciCommand::UciNewGame);
+
+        // Kh7 is the only legal move, after which white mates with Rh1
+        let fen = Fen::from_str("7k/5K2/8/8/8/8/8/6R1 b - - 0 1").unwrap();
+        engine.accept(UciCommand::PositionFrom { fen, moves: vec![] });
+        engine.accept(UciCommand::Go { go: Go { depth: Some(1), ..Go::default() } });
+
+        let best_move = rx.iter().find(|m| matches!(m, UciTxCommand::BestMove {..})).unwrap();
+
+        assert_eq!(best_move, UciTxCommand::BestMove {
+            best_move: Some(UciMove::from_str("h8h7").unwrap()),
+            ponder_move: Some(UciMove::from_str("g1h1").unwrap()),
+        });
+    }
+
+    #[test]
+    fn test_ponder_hit() {
+        let (tx, rx) = channel();
+        let mut engine = Engine::new(Arc::new(CommandUciTx::new(tx)), false);
+        engine.accept(UciCommand::UciNewGame);
+
+        // The clocks allow for about 30ms of thinking, which only starts counting after ponderhit
+        let moves = vec![UciMove::from_str("e2e4").unwrap(), UciMove::from_str("e7e5").unwrap()];
+        engine.accept(UciCommand::PositionFrom { fen: Fen::default(), moves });
+        engine.accept(UciCommand::Go { go: Go { ponder: true, white_time: Some(Duration::from_millis(1000)), black_time: Some(Duration::from_millis(1000)), ..Go::default() } });
+
+        sleep(Duration::from_millis(300));
+        assert!(!rx.try_iter().any(|command| matches!(command, UciTxCommand::BestMove { .. })));
+
+        let ponder_hit_at = Instant::now();
+        engine.accept(UciCommand::PonderHit);
+
+        let best_move = rx.iter().find(|command| matches!(command, UciTxCommand::BestMove { .. }));
+
+        assert!(matches!(best_move, Some(UciTxCommand::BestMove { best_move: Some(_), .. })));
+        assert!(ponder_hit_at.elapsed() < Duration::from_secs(1), "search took {:?} after ponderhit", ponder_hit_at.elapsed());
+    }
+
+    #[test]
+    fn test_ponder_stop() {
+        let (tx, rx) = channel();
+        let mut engine = Engine::new(Arc::new(CommandUciTx::new(tx)), false);
+        engine.accept(UciCommand::UciNewGame);
+
+        engine.accept(UciCommand::PositionFrom { fen: Fen::default(), moves: vec![UciMove::from_str("e2e4").unwrap()] });
+        engine.accept(UciCommand::Go { go: Go { ponder: true, move_time: Some(Duration::from_millis(10)), ..Go::default() } });
+
+        sleep(Duration::from_millis(100));
+        assert!(!rx.try_iter().any(|command| matches!(command, UciTxCommand::BestMove { .. })));
+
+        engine.accept(UciCommand::Stop);
+
+        assert!(rx.iter().any(|command| matches!(command, UciTxCommand::BestMove { best_move: Some(_), .. })));
+    }
+
+    #[test]
+    fn test_threads_stop() {
+        let (tx, rx) = channel();
+        let mut engine = Engine::new(Arc::new(CommandUciTx::new(tx)), false);
+        engine.accept(UciCommand::SetOptionValue { name: "Threads".to_string(), value: "2".to_string() });
+        engine.accept(UciCommand::UciNewGame);
+
+        engine.accept(UciCommand::PositionFrom { fen: Fen::default(), moves: vec![] });
+        engine.accept(UciCommand::Go { go: Go { infinite: true, ..Go::default() } });
+
+        sleep(Duration::from_millis(200));
+        assert!(!rx.try_iter().any(|command| matches!(command, UciTxCommand::BestMove { .. })));
+
+        engine.accept(UciCommand::Stop);
+
+        // Helpers that don't stop would keep the main thread from ever sending its best move
+        let best_move = iter::from_fn(|| rx.recv_timeout(Duration::from_secs(10)).ok())
+            .find(|command| matches!(command, UciTxCommand::BestMove { .. }));
+        assert!(matches!(best_move, Some(UciTxCommand::BestMove { best_move: Some(_), .. })));
+    }
+
+    #[test]
+    fn test_position_after_stop_without_waiting() {
+        let (tx, rx) = channel();
+        let mut engine = Engine::new(Arc::new(CommandUciTx::new(tx)), false);
+        engine.accept(UciCommand::UciNewGame);
+
+        engine.accept(UciCommand::PositionFrom { fen: Fen::default(), moves: vec![] });
+        engine.accept(UciCommand::Go { go: Go { infinite: true, ..Go::default() } });
+        sleep(Duration::from_millis(100));
+
+        // Sent before the search answered the stop with its best move
+        engine.accept(UciCommand::Stop);
+        engine.accept(UciCommand::PositionFrom { fen: Fen::from_str("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap(), moves: vec![] });
+        engine.accept(UciCommand::Go { go: Go { depth: Some(2), ..Go::default() } });
+
+        let best_moves = iter::from_fn(|| rx.recv_timeout(Duration::from_secs(10)).ok())
+            .filter_map(|command| if let UciTxCommand::BestMove { best_move, .. } = command { Some(best_move) } else { None })
+            .take(2)
+            .collect::<Vec<_>>();
+
+        assert_eq!(best_moves.len(), 2);
+        assert_eq!(best_moves[1], Some(UciMove::from_str("a1a8").unwrap()));
+    }
+
+    #[test]
+    fn test_null_best_move_when_checkmated() {
+        assert_eq!(best_move_line("rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3"), "bestmove 0000");
+    }
+
+    #[test]
+    fn test_null_best_move_when_stalemated() {
+        assert_eq!(best_move_line("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1"), "bestmove 0000");
+    }
+
+    fn best_move_line(fen: &str) -> String {
+        let (tx, rx) = channel();
+        let tx = Mutex::new(tx);
+        let uci_tx = ConsoleUciTx::new(move |line: &str| tx.lock().unwrap().send(line.to_string()).unwrap(), |_: &str| {}, false);
+        let mut engine = Engine::new(Arc::new(uci_tx), false);
+
+        // Search a regular position first, so a stale principal variation can't leak into the ponder move
+        engine.accept(UciCommand::UciNewGame);
+        engine.accept(UciCommand::PositionFrom { fen: Fen::default(), moves: vec![] });
+        engine.accept(UciCommand::Go { go: Go { depth: Some(3), ..Go::default() } });
+        engine.accept(UciCommand::PositionFrom { fen: Fen::from_str(fen).unwrap(), moves: vec![] });
+        engine.accept(UciCommand::Go { go: Go { depth: Some(3), ..Go::default() } });
+
+        rx.iter().filter(|line| line.starts_with("bestmove")).nth(1).unwrap()
+    }
+
     #[test]
     fn test_threefold_1() {
         let fen = Fen::from_str("5rk1/5r2/p7/2pNp1q1/2P1P2p/1P3P1P/P4RP1/5RK1 w - - 0 28").unwrap();
@@ -167,7 +372,7 @@ mod test {
             "d5b6", "g5e3", "b6d5",
         ];
         let move_to_draw = "e3g5";
-        _test_threefold(moves, fen, move_to_draw);
+        assert_eq!(_test_repetition(moves, fen, move_to_draw), Score::Centipawn { score: 0 });
     }
 
     #[test]
@@ -175,7 +380,7 @@ mod test {
         let fen = Fen::default();
         let moves = vec!["e2e4", "b8c6", "g1f3", "g8f6", "e4e5", "f6d5", "d2d4", "d7d6", "c2c4", "d5b6", "b1c3", "c8g4", "c1f4", "e7e6", "d1d3", "g4f3", "g2f3", "d6e5", "d4e5", "d8d4", "d3d4", "c6d4", "e1c1", "c7c5", "f4g3", "e8e7", "f3f4", "d4f5", "f1d3", "f5g3", "h2g3", "f7f6", "c1d2", "f6e5", "f4e5", "b6d7", "f2f4", "d7b6", "d2e3", "e7f7", "g3g4", "f8e7", "a2a4", "a7a5", "d1e1", "a8d8", "g4g5", "d8a8", "h1h3", "h7h6", "h3f3", "h6g5", "f4g5", "f7e8", "f3g3", "h8h2", "e1e2", "h2e2", "d3e2", "e8d7", "g3g4", "a8h8", "e2f3", "h8h3", "b2b3", "d7c7", "c3b5", "c7d7", "g4g2", "d7c8", "e3f4", "h3h4", "g2g4", "h4h3", "g4g2", "h3h4", "g2g4", "h4h3"];
         let move_to_draw = "g4g2";
-        _test_threefold(moves, fen, move_to_draw);
+        assert_eq!(_test_repetition(moves, fen, move_to_draw), Score::Centipawn { score: 0 });
     }
 
     #[test]
@@ -184,16 +389,33 @@ mod test {
         let moves = vec![
             "h8g8",
             "d5b6", "g5e3", "b6d5", "e3g5",
-            "d5b6", "g5e3",
+            "d5b6", "g5e3", "b6d5",
         ];
-        let move_to_draw = "b6d5";
-        _test_threefold(moves, fen, move_to_draw);
+        let move_to_draw = "e3g5";
+        assert_eq!(_test_repetition(moves, fen, move_to_draw), Score::Centipawn { score: 0 });
+    }
+
+    #[test]
+    fn test_twofold_is_not_threefold() {
+        let mut board = Bitboard::from(&Fen::from_str("5r1k/5r2/p7/2pNp1q1/2P1P2p/1P3P1P/P4RP1/5RK1 b - - 0 28").unwrap());
+        let mut history = ZobristHistory::default();
+        history.set(board.ply_clock(), board.zobrist_hash());
+
+        // The position after b6d5 occurs for the second time, only e3g5 repeats a position for the third time
+        for (index, uci) in ["h8g8", "d5b6", "g5e3", "b6d5", "e3g5", "d5b6", "g5e3", "b6d5", "e3g5"].into_iter().enumerate() {
+            board.make_uci(uci).unwrap();
+            history.set(board.ply_clock(), board.zobrist_hash());
+
+            assert_eq!(history.is_threefold_repetition(board.ply_clock(), board.halfmove_clock), index == 8, "{}", uci);
+        }
     }
 
-    fn _test_threefold(moves: Vec<&str>, fen: Fen, move_to_draw: &str) {
+    /// The score after searching `move_to_draw`, without contempt so draws are scored as zero
+    fn _test_repetition(moves: Vec<&str>, fen: Fen, move_to_draw: &str) -> Score {
         let (tx, rx) = channel();
         let mut engine = Engine::new(Arc::new(CommandUciTx::new(tx)), false);
 
+        engine.accept(UciCommand::SetOptionValue { name: "Contempt".to_string(), value: "0".to_string() });
         engine.accept(UciCommand::UciNewGame);
         let uci_moves = moves.into_iter().map(|s| UciMove::parse(s).unwrap()).collect();
         engine.accept(UciCommand::PositionFrom { fen, moves: uci_moves });
@@ -208,10 +430,6 @@ mod test {
             }
         }
 
-        if let Some(UciTxCommand::Info { info }) = commands.into_iter().filter(|c| matches!(c, UciTxCommand::Info {..})).last() {
-            assert_eq!(info.score, Some(Score::Centipawn { score: 0 }));
-        } else {
-            assert_eq!(true, false, "No score was send");
-        }
+        commands.into_iter().rev().find_map(|c| if let UciTxCommand::Info { info } = c { info.score } else { None }).expect("No score was sent")
     }
 }
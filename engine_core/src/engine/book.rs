@@ -0,0 +1,286 @@
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use rand::Rng;
+
+use inkayaku_board::{Bitboard, Move, PlayerState};
+use inkayaku_board::constants::{KING, NO_PIECE, NO_SQUARE, PAWN, SquareShiftBits, WHITE};
+
+use crate::engine::book::keys::{CASTLE_OFFSET, EN_PASSANT_OFFSET, RANDOM_64, TURN_OFFSET};
+
+mod keys;
+
+/// Size of an entry in a Polyglot book file: key, move, weight and learn, all big endian
+const ENTRY_SIZE: usize = 16;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct BookEntry {
+    /// Move in Polyglot encoding, castling as the king capturing its rook
+    pub mv: u16,
+    pub weight: u16,
+    pub learn: u32,
+}
+
+/// Opening book in the Polyglot `.bin` format, mapping Polyglot hashes of positions to weighted moves
+#[derive(Default, Debug)]
+pub struct PolyglotBook {
+    /// Entries sorted by key
+    entries: Vec<(u64, BookEntry)>,
+}
+
+impl PolyglotBook {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, BookError> {
+        let path = path.as_ref();
+        let bytes = fs::read(path).map_err(|error| BookError::Io(path.to_path_buf(), error))?;
+
+        Self::from_bytes(&bytes)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, BookError> {
+        if !bytes.len().is_multiple_of(ENTRY_SIZE) {
+            return Err(BookError::InvalidLength(bytes.len()));
+        }
+
+        let mut entries = bytes
+            .chunks_exact(ENTRY_SIZE)
+            .map(|chunk| {
+                let key = u64::from_be_bytes(chunk[0..8].try_into().unwrap_or_default());
+                let mv = u16::from_be_bytes([chunk[8], chunk[9]]);
+                let weight = u16::from_be_bytes([chunk[10], chunk[11]]);
+                let learn = u32::from_be_bytes(chunk[12..16].try_into().unwrap_or_default());
+
+                (key, BookEntry { mv, weight, learn })
+            })
+            .collect::<Vec<_>>();
+
+        // Books are sorted by key already, sort anyway so lookups can rely on it
+        entries.sort_by_key(|&(key, _)| key);
+
+        Ok(Self { entries })
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The legal book moves of the position on `board` with their weights. Entries without a matching legal move are
+    /// skipped.
+    pub fn moves(&self, board: &mut Bitboard) -> Vec<(Move, u16)> {
+        let key = polyglot_hash(board);
+        let start = self.entries.partition_point(|&(entry_key, _)| entry_key < key);
+        let entries = self.entries[start..].iter().take_while(|&&(entry_key, _)| entry_key == key);
+
+        let legal_moves = board.generate_legal_moves();
+
+        entries
+            .filter_map(|(_, entry)| {
+                legal_moves
+                    .iter()
+                    .find(|&&mv| encode_move(mv) == entry.mv)
+                    .map(|&mv| (mv, entry.weight))
+            })
+            .collect()
+    }
+
+    /// Choose a book move of the position on `board` at random, proportional to the weights
+    pub fn choose_move<R: Rng>(&self, board: &mut Bitboard, rng: &mut R) -> Option<Move> {
+        let moves = self.moves(board);
+        let total_weight = moves.iter().map(|&(_, weight)| u32::from(weight)).sum::<u32>();
+
+        if total_weight == 0 {
+            return moves.first().map(|&(mv, _)| mv);
+        }
+
+        let mut remaining = rng.gen_range(0..total_weight);
+
+        for (mv, weight) in moves {
+            if remaining < u32::from(weight) {
+                return Some(mv);
+            }
+            remaining -= u32::from(weight);
+        }
+
+        None
+    }
+}
+
+/// Polyglot square index, `8 * rank + file` starting at a1, of the board's square shift, which starts at a8
+const fn polyglot_square(square_shift: SquareShiftBits) -> usize {
+    (square_shift ^ 56) as usize
+}
+
+/// The Polyglot hash of the position on `board`, which differs from the board's own Zobrist hash
+pub fn polyglot_hash(board: &Bitboard) -> u64 {
+    let mut result = 0;
+
+    for (state, is_white) in [(&board.white, 1), (&board.black, 0)] {
+        for piece in PAWN..=KING {
+            let kind = 2 * (piece as usize - 1) + is_white;
+            let mut occupancy = state.occupancy(piece);
+
+            while occupancy != 0 {
+                let square_shift = occupancy.trailing_zeros() as SquareShiftBits;
+                occupancy &= occupancy - 1;
+
+                result ^= RANDOM_64[64 * kind + polyglot_square(square_shift)];
+            }
+        }
+    }
+
+    let castling_rights = [board.white.king_side_castle, board.white.queen_side_castle, board.black.king_side_castle, board.black.queen_side_castle];
+
+    for (index, _) in castling_rights.iter().enumerate().filter(|(_, &right)| right) {
+        result ^= RANDOM_64[CASTLE_OFFSET + index];
+    }
+
+    if is_en_passant_capture_possible(board) {
+        result ^= RANDOM_64[EN_PASSANT_OFFSET + (board.en_passant_square_shift % 8) as usize];
+    }
+
+    if board.turn == WHITE {
+        result ^= RANDOM_64[TURN_OFFSET];
+    }
+
+    result
+}
+
+/// Polyglot only hashes the en passant file if a pawn of the side to move stands next to the pawn that just moved
+fn is_en_passant_capture_possible(board: &Bitboard) -> bool {
+    let en_passant_square_shift = board.en_passant_square_shift;
+
+    if en_passant_square_shift == NO_SQUARE {
+        return false;
+    }
+
+    let (active, pawn_square_shift): (&PlayerState, SquareShiftBits) = if board.turn == WHITE {
+        (&board.white, en_passant_square_shift + 8)
+    } else {
+        (&board.black, en_passant_square_shift - 8)
+    };
+
+    let file = pawn_square_shift % 8;
+    let left = if file > 0 { 1 << (pawn_square_shift - 1) } else { 0 };
+    let right = if file < 7 { 1 << (pawn_square_shift + 1) } else { 0 };
+
+    active.pawns() & (left | right) != 0
+}
+
+/// Encode `mv` like Polyglot, castle moves are encoded as the king capturing its own rook
+fn encode_move(mv: Move) -> u16 {
+    let source_square_shift = mv.get_source_square();
+
+    let target_square_shift = if mv.is_castle_move() {
+        source_square_shift - source_square_shift % 8 + mv.get_castle_rook_file()
+    } else {
+        mv.get_target_square()
+    };
+
+    let promotion = match mv.get_promotion_piece() {
+        NO_PIECE => 0,
+        // Knight to queen are encoded as 1 to 4
+        piece => piece - 1,
+    };
+
+    (polyglot_square(target_square_shift) | polyglot_square(source_square_shift) << 6 | (promotion as usize) << 12) as u16
+}
+
+#[derive(Debug)]
+pub enum BookError {
+    InvalidLength(usize),
+    Io(PathBuf, io::Error),
+}
+
+impl Display for BookError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidLength(length) => write!(f, "book length {} is not a multiple of {}", length, ENTRY_SIZE),
+            Self::Io(path, error) => write!(f, "failed to read {}: {}", path.display(), error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use inkayaku_board::Bitboard;
+
+    use crate::engine::book::{BookError, PolyglotBook, polyglot_hash};
+
+    /// Reference hashes from the Polyglot book format specification
+    #[test]
+    fn test_polyglot_hash() {
+        for (fen, expected) in [
+            ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", 0x463b96181691fc9c),
+            ("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1", 0x823c9b50fd114196),
+            ("rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 2", 0x0756b94461c50fb0),
+            ("rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR b KQkq - 0 2", 0x662fafb965db29d4),
+            ("rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3", 0x22a48b5a8e47ff78),
+            ("rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPPKPPP/RNBQ1BNR b kq - 0 3", 0x652a607ca3f242c1),
+            ("rnbq1bnr/ppp1pkpp/8/3pPp2/8/8/PPPPKPPP/RNBQ1BNR w - - 0 4", 0x00fdd303c946bdd9),
+            ("rnbqkbnr/p1pppppp/8/8/PpP4P/8/1P1PPPP1/RNBQKBNR b KQkq c3 0 3", 0x3c8123ea7b067637),
+            ("rnbqkbnr/p1pppppp/8/8/P6P/R1p5/1P1PPPP1/1NBQKBNR b Kkq - 0 4", 0x5c3f9b829b279560),
+        ] {
+            assert_eq!(polyglot_hash(&Bitboard::from_fen_string_unchecked(fen)), expected, "{}", fen);
+        }
+    }
+
+    fn entry(key: u64, mv: u16, weight: u16) -> Vec<u8> {
+        [key.to_be_bytes().as_slice(), &mv.to_be_bytes(), &weight.to_be_bytes(), &0_u32.to_be_bytes()].concat()
+    }
+
+    /// Polyglot encoding of a move from `from` to `to` in UCI notation, without promotion
+    fn mv(uci: &str) -> u16 {
+        let square = |s: &[u8]| u16::from(s[0] - b'a') + 8 * u16::from(s[1] - b'1');
+        square(&uci.as_bytes()[2..4]) | square(&uci.as_bytes()[0..2]) << 6
+    }
+
+    #[test]
+    fn test_moves() {
+        let mut board = Bitboard::default();
+        let key = polyglot_hash(&board);
+
+        let bytes = [
+            entry(key, mv("e2e4"), 30),
+            entry(key, mv("d2d4"), 10),
+            // Not legal in the starting position
+            entry(key, mv("e2e5"), 10),
+            entry(key + 1, mv("g1f3"), 10),
+        ].concat();
+
+        let sut = PolyglotBook::from_bytes(&bytes).unwrap();
+        let actual = sut.moves(&mut board).into_iter().map(|(mv, weight)| (mv.to_uci_string(), weight)).collect::<Vec<_>>();
+
+        assert_eq!(actual, vec![("e2e4".to_string(), 30), ("d2d4".to_string(), 10)]);
+
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..10 {
+            let chosen = sut.choose_move(&mut board, &mut rng).unwrap().to_uci_string();
+            assert!(chosen == "e2e4" || chosen == "d2d4");
+        }
+    }
+
+    #[test]
+    fn test_castle_move() {
+        let mut board = Bitboard::from_fen_string_unchecked("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1");
+        let key = polyglot_hash(&board);
+
+        let sut = PolyglotBook::from_bytes(&[entry(key, mv("e1h1"), 1), entry(key, mv("e1a1"), 1)].concat()).unwrap();
+        let actual = sut.moves(&mut board).into_iter().map(|(mv, _)| mv.to_uci_string()).collect::<Vec<_>>();
+
+        assert_eq!(actual, vec!["e1g1".to_string(), "e1c1".to_string()]);
+    }
+
+    #[test]
+    fn test_invalid_length() {
+        assert!(matches!(PolyglotBook::from_bytes(&[0; 17]), Err(BookError::InvalidLength(17))));
+        assert!(PolyglotBook::from_bytes(&[]).unwrap().is_empty());
+    }
+}
@@ -1,5 +1,6 @@
-use inkayaku_board::{Bitboard, PlayerState};
-use inkayaku_board::constants::{BLACK, WHITE, ZobristHash};
+use inkayaku_board::Bitboard;
+use inkayaku_board::constants::{BLACK, FILE_A_OCCUPANCY, OccupancyBits, WHITE};
+use inkayaku_board::mask_and_shift_from_lowest_one_bit;
 use inkayaku_uci::Score;
 use inkayaku_uci::Score::{Centipawn, Mate};
 
@@ -19,12 +20,12 @@ pub trait Heuristic {
     fn is_checkmate(&self, value: i32) -> bool {
         value > self.win_score() - Self::MAX_FULL_MOVES || value < self.loss_score() + Self::MAX_FULL_MOVES
     }
-    fn evaluate(&self, bitboard: &Bitboard, zobrist_pawn_hash: ZobristHash, legal_moves_remaining: bool) -> i32 {
+    fn evaluate(&self, bitboard: &Bitboard, pawn_eval: &PawnEval, legal_moves_remaining: bool) -> i32 {
         if legal_moves_remaining {
-            if bitboard.halfmove_clock >= Self::MAX_HALF_MOVES {
+            if bitboard.halfmove_clock >= Self::MAX_HALF_MOVES || bitboard.is_insufficient_material() {
                 self.draw_score()
             } else {
-                self.evaluate_ongoing(bitboard, zobrist_pawn_hash)
+                self.evaluate_ongoing(bitboard, pawn_eval)
             }
         } else {
             match (bitboard.is_current_in_check(), bitboard.turn) {
@@ -34,6 +35,8 @@ pub trait Heuristic {
             }
         }
     }
+    /// Set the bonus for the side to move, heuristics without a tempo term ignore it
+    fn set_tempo(&mut self, _tempo: i32) {}
     fn score_from_value(&self, value: i32, bitboard: &Bitboard) -> Score {
         if value.abs() > self.win_score() / 2 {
             let offset = i32::from(value > 0 && bitboard.turn == WHITE);
@@ -44,7 +47,74 @@ pub trait Heuristic {
         }
     }
 
-    fn evaluate_ongoing(&self, bitboard: &Bitboard, zobrist_pawn_hash: ZobristHash) -> i32;
+    fn evaluate_ongoing(&self, bitboard: &Bitboard, pawn_eval: &PawnEval) -> i32;
+}
+
+/// Pawn structure of a position, as the difference between white and black. Only depends on the pawns, so it is cached
+/// by the pawn zobrist hash.
+#[derive(Eq, PartialEq, Copy, Clone, Debug, Default)]
+pub struct PawnEval {
+    /// Pawns on a file with another own pawn, not counting the first pawn of each file
+    pub doubled: i32,
+    /// Pawns without own pawns on the adjacent files
+    pub isolated: i32,
+    /// Pawns without enemy pawns in front of them on the same or adjacent files
+    pub passed: i32,
+}
+
+impl PawnEval {
+    pub fn from_pawns(white_pawns: OccupancyBits, black_pawns: OccupancyBits) -> Self {
+        Self {
+            doubled: Self::doubled(white_pawns) - Self::doubled(black_pawns),
+            isolated: Self::isolated(white_pawns) - Self::isolated(black_pawns),
+            passed: Self::passed(white_pawns, black_pawns, WHITE) - Self::passed(black_pawns, white_pawns, BLACK),
+        }
+    }
+
+    fn doubled(pawns: OccupancyBits) -> i32 {
+        (0..8).map(|file| (pawns & (FILE_A_OCCUPANCY << file)).count_ones().saturating_sub(1) as i32).sum()
+    }
+
+    fn isolated(pawns: OccupancyBits) -> i32 {
+        (0..8)
+            .filter(|&file| pawns & Self::adjacent_files(file) == 0)
+            .map(|file| (pawns & (FILE_A_OCCUPANCY << file)).count_ones() as i32)
+            .sum()
+    }
+
+    fn passed(mut pawns: OccupancyBits, enemy_pawns: OccupancyBits, color: u32) -> i32 {
+        let mut passed = 0;
+
+        while pawns != 0 {
+            let (mask, shift) = mask_and_shift_from_lowest_one_bit(pawns);
+            pawns &= !mask;
+
+            let file = shift % 8;
+            let row = shift / 8;
+
+            // Row 0 is the eighth rank, so white pawns advance towards lower rows
+            let front = if color == WHITE {
+                (1_u64 << (8 * row)) - 1
+            } else if row < 7 {
+                !((1_u64 << (8 * (row + 1))) - 1)
+            } else {
+                0
+            };
+
+            if enemy_pawns & front & (Self::adjacent_files(file) | (FILE_A_OCCUPANCY << file)) == 0 {
+                passed += 1;
+            }
+        }
+
+        passed
+    }
+
+    fn adjacent_files(file: u32) -> OccupancyBits {
+        let left = if file > 0 { FILE_A_OCCUPANCY << (file - 1) } else { 0 };
+        let right = if file < 7 { FILE_A_OCCUPANCY << (file + 1) } else { 0 };
+
+        left | right
+    }
 }
 
 const fn mirror_and_flip_sign<const M: usize, const T: usize>(tables: [[[i32; 64]; M]; T]) -> [[[i32; 64]; M]; T] {
@@ -87,42 +157,49 @@ const fn mirror_and_flip_sign<const M: usize, const T: usize>(tables: [[[i32; 64
     result
 }
 
-struct PieceCount {
-    pawns: u32,
-    knights: u32,
-    bishops: u32,
-    rooks: u32,
-    queens: u32,
-}
+/// Returns the taper factor in `0..=255`, 0 being early game and 255 being end game
+fn taper_factor(phase: u8) -> u8 {
+    let result = (i32::from(phase) * 255 + 24) / 24 - 1;
 
-impl PieceCount {
-    pub const fn count_from(player_state: &PlayerState) -> Self {
-        Self {
-            pawns: player_state.pawns().count_ones(),
-            knights: player_state.knights().count_ones(),
-            bishops: player_state.bishops().count_ones(),
-            rooks: player_state.rooks().count_ones(),
-            queens: player_state.queens().count_ones(),
-        }
-    }
+    result.clamp(0, 255) as u8
 }
 
-struct PieceCounts {
-    white: PieceCount,
-    black: PieceCount,
+/// Interpolate between the early game value `early` and the end game value `late` by `taper_factor`
+const fn taper(early: i32, late: i32, taper_factor: u8) -> i32 {
+    (early * (255 - taper_factor as i32) + late * taper_factor as i32) / 255
 }
 
-impl PieceCounts {
-    pub const fn count_from(bitboard: &Bitboard) -> Self {
-        Self {
-            white: PieceCount::count_from(&bitboard.white),
-            black: PieceCount::count_from(&bitboard.black),
-        }
+#[cfg(test)]
+mod test {
+    use inkayaku_board::Bitboard;
+
+    use crate::engine::heuristic::PawnEval;
+
+    fn pawn_eval(fen: &str) -> PawnEval {
+        let bitboard = Bitboard::from_fen_string_unchecked(fen);
+        PawnEval::from_pawns(bitboard.white.pawns(), bitboard.black.pawns())
+    }
+
+    #[test]
+    fn test_start_position() {
+        assert_eq!(pawn_eval("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"), PawnEval::default());
     }
 
-    const fn pawns(&self) -> u32 { self.white.pawns + self.black.pawns }
-    const fn knights(&self) -> u32 { self.white.knights + self.black.knights }
-    const fn bishops(&self) -> u32 { self.white.bishops + self.black.bishops }
-    const fn rooks(&self) -> u32 { self.white.rooks + self.black.rooks }
-    const fn queens(&self) -> u32 { self.white.queens + self.black.queens }
+    #[test]
+    fn test_doubled_and_isolated() {
+        let pawn_eval = pawn_eval("4k3/pp3ppp/8/8/8/2P5/2P2PPP/4K3 w - - 0 1");
+
+        assert_eq!(pawn_eval.doubled, 1);
+        assert_eq!(pawn_eval.isolated, 2);
+        // The a7 pawn is passed
+        assert_eq!(pawn_eval.passed, -1);
+    }
+
+    #[test]
+    fn test_passed() {
+        assert_eq!(pawn_eval("4k3/5ppp/8/3P4/8/8/5PPP/4K3 w - - 0 1").passed, 1);
+        assert_eq!(pawn_eval("4k3/5ppp/2p5/3P4/8/8/5PPP/4K3 w - - 0 1").passed, 0);
+        assert_eq!(pawn_eval("4k3/5ppp/8/8/3p4/8/5PPP/4K3 b - - 0 1").passed, -1);
+        assert_eq!(pawn_eval("4k3/5ppp/8/8/3p4/4P3/5PPP/4K3 b - - 0 1").passed, 0);
+    }
 }
@@ -1,14 +1,6 @@
 use inkayaku_board::Bitboard;
-use inkayaku_board::constants::ZobristHash;
 
-use crate::engine::heuristic::{Heuristic, mirror_and_flip_sign, PieceCounts};
-use crate::engine::table::HashTable;
-
-const QUEEN_VALUE: u32 = 900;
-const ROOK_VALUE: u32 = 500;
-const BISHOP_VALUE: u32 = 330;
-const KNIGHT_VALUE: u32 = 320;
-const PAWN_VALUE: u32 = 100;
+use crate::engine::heuristic::{Heuristic, mirror_and_flip_sign, PawnEval, taper_factor};
 
 // @formatter:off
 
@@ -98,43 +90,15 @@ const WHITE_TABLES: [[[i32; 64]; 6]; 2] = [
 
 const BLACK_TABLES: [[[i32; 64]; 6]; 2] = mirror_and_flip_sign(WHITE_TABLES);
 
-struct PawnEval {}
-
-pub struct ImprovedHeuristic {
-    pawn_state_table: HashTable<ZobristHash, PawnEval>,
-}
+pub struct ImprovedHeuristic {}
 
 impl ImprovedHeuristic {}
 
 impl Heuristic for ImprovedHeuristic {
-    fn evaluate_ongoing(&self, bitboard: &Bitboard, zobrist_pawn_hash: ZobristHash) -> i32 {
-        let counts = PieceCounts::count_from(bitboard);
-
-        let taper_factor = taper_factor(&counts);
+    fn evaluate_ongoing(&self, bitboard: &Bitboard, _pawn_eval: &PawnEval) -> i32 {
+        let taper_factor = taper_factor(bitboard.phase());
 
 
         todo!()
     }
 }
-
-/// Returns the taper factor in `0..=255`, 0 being early game and 255 being end game
-fn taper_factor(counts: &PieceCounts) -> u8 {
-    const PAWN_PHASE: i32 = 0;
-    const KNIGHT_PHASE: i32 = 1;
-    const BISHOP_PHASE: i32 = 1;
-    const ROOK_PHASE: i32 = 2;
-    const QUEEN_PHASE: i32 = 4;
-    const TOTAL_PHASE: i32 = PAWN_PHASE * 16 + KNIGHT_PHASE * 4 + BISHOP_PHASE * 4 + ROOK_PHASE * 4 + QUEEN_PHASE * 2;
-
-    let phase = TOTAL_PHASE
-        - counts.pawns() as i32 * PAWN_PHASE
-        - counts.knights() as i32 * KNIGHT_PHASE
-        - counts.bishops() as i32 * BISHOP_PHASE
-        - counts.rooks() as i32 * ROOK_PHASE
-        - counts.queens() as i32 * QUEEN_PHASE
-        ;
-
-    let result = (phase * 255 + TOTAL_PHASE) / TOTAL_PHASE - 1;
-
-    result.clamp(0, 255) as u8
-}
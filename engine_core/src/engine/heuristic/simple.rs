@@ -1,14 +1,9 @@
 use inkayaku_board::{Bitboard, PlayerState};
-use inkayaku_board::constants::{BISHOP, GameStageBits, KING, KNIGHT, LATE, MID, OccupancyBits, PAWN, QUEEN, ROOK, ZobristHash};
+use inkayaku_board::constants::{BISHOP, BLACK, ColorBits, FILE_A_OCCUPANCY, GameStageBits, KING, KNIGHT, LATE, MID, OccupancyBits, PAWN, QUEEN, RANK_8_OCCUPANCY, ROOK, WHITE};
 use inkayaku_board::mask_and_shift_from_lowest_one_bit;
+use inkayaku_core::constants::{Color, Square};
 
-use crate::engine::heuristic::{Heuristic, mirror_and_flip_sign};
-
-const QUEEN_VALUE: u32 = 900;
-const ROOK_VALUE: u32 = 500;
-const BISHOP_VALUE: u32 = 330;
-const KNIGHT_VALUE: u32 = 320;
-const PAWN_VALUE: u32 = 100;
+use crate::engine::heuristic::{Heuristic, mirror_and_flip_sign, PawnEval, taper, taper_factor};
 
 // @formatter:off
 
@@ -89,28 +84,100 @@ const WHITE_PAWN_TABLE_MID: [i32; 64] = [
       0,   0,   0,   0,   0,   0,   0,   0,
 ];
 
+const WHITE_QUEEN_TABLE_LATE: [i32; 64] = [
+    -20, -10, -10,  -5,  -5, -10, -10, -20,
+    -10,   0,   0,   0,   0,   0,   0, -10,
+    -10,   0,   5,   5,   5,   5,   0, -10,
+     -5,   0,   5,  10,  10,   5,   0,  -5,
+     -5,   0,   5,  10,  10,   5,   0,  -5,
+    -10,   0,   5,   5,   5,   5,   0, -10,
+    -10,   0,   0,   0,   0,   0,   0, -10,
+    -20, -10, -10,  -5,  -5, -10, -10, -20,
+];
+
+const WHITE_ROOK_TABLE_LATE: [i32; 64] = [
+      0,   0,   0,   0,   0,   0,   0,   0,
+     10,  10,  10,  10,  10,  10,  10,  10,
+      0,   0,   0,   0,   0,   0,   0,   0,
+      0,   0,   0,   0,   0,   0,   0,   0,
+      0,   0,   0,   0,   0,   0,   0,   0,
+      0,   0,   0,   0,   0,   0,   0,   0,
+      0,   0,   0,   0,   0,   0,   0,   0,
+      0,   0,   0,   0,   0,   0,   0,   0,
+];
+
+const WHITE_BISHOP_TABLE_LATE: [i32; 64] = [
+    -20, -10, -10, -10, -10, -10, -10, -20,
+    -10,   0,   0,   0,   0,   0,   0, -10,
+    -10,   0,   5,  10,  10,   5,   0, -10,
+    -10,   0,  10,  10,  10,  10,   0, -10,
+    -10,   0,  10,  10,  10,  10,   0, -10,
+    -10,   0,   5,  10,  10,   5,   0, -10,
+    -10,   0,   0,   0,   0,   0,   0, -10,
+    -20, -10, -10, -10, -10, -10, -10, -20,
+];
+
+const WHITE_KNIGHT_TABLE_LATE: [i32; 64] = [
+    -50, -40, -30, -30, -30, -30, -40, -50,
+    -40, -20,   0,   0,   0,   0, -20, -40,
+    -30,   0,  10,  15,  15,  10,   0, -30,
+    -30,   0,  15,  20,  20,  15,   0, -30,
+    -30,   0,  15,  20,  20,  15,   0, -30,
+    -30,   0,  10,  15,  15,  10,   0, -30,
+    -40, -20,   0,   0,   0,   0, -20, -40,
+    -50, -40, -30, -30, -30, -30, -40, -50,
+];
+
+const WHITE_PAWN_TABLE_LATE: [i32; 64] = [
+      0,   0,   0,   0,   0,   0,   0,   0,
+     80,  80,  80,  80,  80,  80,  80,  80,
+     50,  50,  50,  50,  50,  50,  50,  50,
+     30,  30,  30,  30,  30,  30,  30,  30,
+     20,  20,  20,  20,  20,  20,  20,  20,
+     10,  10,  10,  10,  10,  10,  10,  10,
+      0,   0,   0,   0,   0,   0,   0,   0,
+      0,   0,   0,   0,   0,   0,   0,   0,
+];
+
 // @formatter:on
 
-const WHITE_TABLES: [[[i32; 64]; 6]; 3] = [
-    [WHITE_PAWN_TABLE_MID, WHITE_KNIGHT_TABLE_MID, WHITE_BISHOP_TABLE_MID, WHITE_ROOK_TABLE_MID, WHITE_QUEEN_TABLE_MID, WHITE_KING_TABLE_MID],
+/// Early and end game tables, interpolated by the taper factor
+const WHITE_TABLES: [[[i32; 64]; 6]; 2] = [
     [WHITE_PAWN_TABLE_MID, WHITE_KNIGHT_TABLE_MID, WHITE_BISHOP_TABLE_MID, WHITE_ROOK_TABLE_MID, WHITE_QUEEN_TABLE_MID, WHITE_KING_TABLE_MID],
-    [WHITE_PAWN_TABLE_MID, WHITE_KNIGHT_TABLE_MID, WHITE_BISHOP_TABLE_MID, WHITE_ROOK_TABLE_MID, WHITE_QUEEN_TABLE_MID, WHITE_KING_TABLE_LATE],
+    [WHITE_PAWN_TABLE_LATE, WHITE_KNIGHT_TABLE_LATE, WHITE_BISHOP_TABLE_LATE, WHITE_ROOK_TABLE_LATE, WHITE_QUEEN_TABLE_LATE, WHITE_KING_TABLE_LATE],
 ];
 
-const BLACK_TABLES: [[[i32; 64]; 6]; 3] = mirror_and_flip_sign(WHITE_TABLES);
-
-#[derive(Default)]
-pub struct SimpleHeuristic;
+const BLACK_TABLES: [[[i32; 64]; 6]; 2] = mirror_and_flip_sign(WHITE_TABLES);
+
+/// Tunable weights of the evaluation terms of [`SimpleHeuristic`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct HeuristicWeights {
+    /// Bonus per step of proximity of a minor or major piece to the enemy king, halved in the endgame.
+    pub king_tropism: i32,
+    /// Bonus per pawn on the two ranks in front of the own king, tapered off towards the endgame.
+    pub king_shield: i32,
+    /// Penalty per doubled pawn, see [`PawnEval::doubled`].
+    pub doubled_pawn: i32,
+    /// Penalty per isolated pawn, see [`PawnEval::isolated`].
+    pub isolated_pawn: i32,
+    /// Bonus per passed pawn, see [`PawnEval::passed`].
+    pub passed_pawn: i32,
+    /// Bonus for the side to move.
+    pub tempo: i32,
+}
 
-impl SimpleHeuristic {
-    const fn piece_value(state: &PlayerState) -> i32 {
-        (state.queens().count_ones() * QUEEN_VALUE +
-            state.rooks().count_ones() * ROOK_VALUE +
-            state.bishops().count_ones() * BISHOP_VALUE +
-            state.knights().count_ones() * KNIGHT_VALUE +
-            state.pawns().count_ones() * PAWN_VALUE) as i32
+impl Default for HeuristicWeights {
+    fn default() -> Self {
+        Self { king_tropism: 2, king_shield: 10, doubled_pawn: 10, isolated_pawn: 10, passed_pawn: 20, tempo: 10 }
     }
+}
 
+#[derive(Default, Clone)]
+pub struct SimpleHeuristic {
+    weights: HeuristicWeights,
+}
+
+impl SimpleHeuristic {
     const fn game_stage(board: &Bitboard) -> GameStageBits {
         let white_has_queens = board.white.queens() != 0;
         let black_has_queens = board.black.queens() != 0;
@@ -132,13 +199,11 @@ impl SimpleHeuristic {
         }
     }
 
-    const fn piece_square_value(board: &Bitboard) -> i32 {
-        let stage = Self::game_stage(board);
-
-        let white_sum = Self::piece_square_sum_for_player(&board.white, &WHITE_TABLES[stage]);
-        let black_sum = Self::piece_square_sum_for_player(&board.black, &BLACK_TABLES[stage]);
+    const fn piece_square_value(board: &Bitboard, taper_factor: u8) -> i32 {
+        let early = Self::piece_square_sum_for_player(&board.white, &WHITE_TABLES[0]) + Self::piece_square_sum_for_player(&board.black, &BLACK_TABLES[0]);
+        let late = Self::piece_square_sum_for_player(&board.white, &WHITE_TABLES[1]) + Self::piece_square_sum_for_player(&board.black, &BLACK_TABLES[1]);
 
-        white_sum + black_sum
+        taper(early, late, taper_factor)
     }
 
     const fn piece_square_sum_for_player(player: &PlayerState, tables: &[[i32; 64]; 6]) -> i32 {
@@ -150,6 +215,69 @@ impl SimpleHeuristic {
             + Self::piece_square_sum(player.kings(), &tables[KING as usize - 1])
     }
 
+    fn king_tropism_value(&self, board: &Bitboard) -> i32 {
+        let white_tropism = Self::king_tropism_for_player(&board.white, board.black.kings());
+        let black_tropism = Self::king_tropism_for_player(&board.black, board.white.kings());
+
+        let tropism = self.weights.king_tropism * (white_tropism - black_tropism);
+
+        if Self::game_stage(board) == LATE { tropism / 2 } else { tropism }
+    }
+
+    fn king_tropism_for_player(player: &PlayerState, enemy_king: OccupancyBits) -> i32 {
+        if enemy_king == 0 {
+            return 0;
+        }
+
+        let enemy_king = Square::from_index_unchecked(enemy_king.trailing_zeros() as usize);
+        let mut occupancy = player.knights() | player.bishops() | player.rooks() | player.queens();
+        let mut sum = 0;
+
+        while occupancy != 0 {
+            let (mask, shift) = mask_and_shift_from_lowest_one_bit(occupancy);
+            occupancy &= !mask;
+            sum += 7 - i32::from(Square::from_index_unchecked(shift as usize).distance(enemy_king));
+        }
+
+        sum
+    }
+
+    fn king_shield_value(&self, board: &Bitboard, taper_factor: u8) -> i32 {
+        let shield = Self::king_shield_for_player(&board.white, WHITE) - Self::king_shield_for_player(&board.black, BLACK);
+
+        taper(self.weights.king_shield * shield, 0, taper_factor)
+    }
+
+    /// Number of own pawns on the two ranks in front of the king, on its file and the adjacent files
+    fn king_shield_for_player(player: &PlayerState, color: ColorBits) -> i32 {
+        let king = player.kings();
+
+        if king == 0 {
+            return 0;
+        }
+
+        let king_shift = king.trailing_zeros();
+        let file = king_shift % 8;
+        let row = king_shift / 8;
+
+        let files = (FILE_A_OCCUPANCY << file.saturating_sub(1)) | (FILE_A_OCCUPANCY << file) | (FILE_A_OCCUPANCY << (file + 1).min(7));
+        let rows = if color == WHITE { [row.wrapping_sub(1), row.wrapping_sub(2)] } else { [row + 1, row + 2] };
+        let ranks = rows.into_iter().filter(|&row| row < 8).fold(0, |ranks, row| ranks | (RANK_8_OCCUPANCY << (8 * row)));
+
+        (player.pawns() & files & ranks).count_ones() as i32
+    }
+
+    const fn pawn_structure_value(&self, pawn_eval: &PawnEval) -> i32 {
+        self.weights.passed_pawn * pawn_eval.passed
+            - self.weights.doubled_pawn * pawn_eval.doubled
+            - self.weights.isolated_pawn * pawn_eval.isolated
+    }
+
+    /// The tempo bonus from white's perspective, so it is the side to move's bonus after the heuristic factor
+    const fn tempo_value(&self, board: &Bitboard) -> i32 {
+        if board.turn == WHITE { self.weights.tempo } else { -self.weights.tempo }
+    }
+
     const fn piece_square_sum(mut occupancy: OccupancyBits, values: &[i32; 64]) -> i32 {
         let mut sum = 0;
 
@@ -164,32 +292,165 @@ impl SimpleHeuristic {
 }
 
 impl Heuristic for SimpleHeuristic {
-    fn evaluate_ongoing(&self, bitboard: &Bitboard, _: ZobristHash) -> i32 {
-        let my_sum = Self::piece_value(&bitboard.white);
-        let their_sum = Self::piece_value(&bitboard.black);
-        let psv = Self::piece_square_value(bitboard);
+    fn set_tempo(&mut self, tempo: i32) {
+        self.weights.tempo = tempo;
+    }
 
-        my_sum - their_sum + psv
+    fn evaluate_ongoing(&self, bitboard: &Bitboard, pawn_eval: &PawnEval) -> i32 {
+        let my_sum = bitboard.material(Color::WHITE);
+        let their_sum = bitboard.material(Color::BLACK);
+        let taper_factor = taper_factor(bitboard.phase());
+        let psv = Self::piece_square_value(bitboard, taper_factor);
+        let tropism = self.king_tropism_value(bitboard);
+        let king_shield = self.king_shield_value(bitboard, taper_factor);
+        let pawn_structure = self.pawn_structure_value(pawn_eval);
+        let tempo = self.tempo_value(bitboard);
+
+        my_sum - their_sum + psv + tropism + king_shield + pawn_structure + tempo
     }
 }
 
 #[cfg(test)]
 mod test {
     use inkayaku_board::Bitboard;
+    use inkayaku_board::constants::{BLACK, WHITE};
+
+    use crate::engine::heuristic::{Heuristic, PawnEval};
+    use crate::engine::heuristic::simple::{HeuristicWeights, SimpleHeuristic};
+
+    fn pawn_eval(bitboard: &Bitboard) -> PawnEval {
+        PawnEval::from_pawns(bitboard.white.pawns(), bitboard.black.pawns())
+    }
+
+    #[test]
+    fn test_pawn_structure() {
+        let heuristic = SimpleHeuristic::default();
 
-    use crate::engine::heuristic::Heuristic;
-    use crate::engine::heuristic::simple::SimpleHeuristic;
+        let doubled = Bitboard::from_fen_string_unchecked("4k3/5ppp/8/8/8/5P2/5PP1/4K3 w - - 0 1");
+        assert!(heuristic.pawn_structure_value(&pawn_eval(&doubled)) < 0);
+
+        let passed = Bitboard::from_fen_string_unchecked("4k3/5ppp/8/2PP4/8/8/5PPP/4K3 w - - 0 1");
+        assert!(heuristic.pawn_structure_value(&pawn_eval(&passed)) > 0);
+    }
+
+    #[test]
+    fn test_insufficient_material_is_draw() {
+        let heuristic = SimpleHeuristic::default();
+
+        let bitboard = Bitboard::from_fen_string_unchecked("4k3/8/8/8/8/8/8/1N2K3 w - - 0 1");
+        assert_eq!(heuristic.evaluate(&bitboard, &pawn_eval(&bitboard), true), heuristic.draw_score());
+
+        let bitboard = Bitboard::from_fen_string_unchecked("4k3/8/8/8/8/8/8/R3K3 w - - 0 1");
+        assert!(heuristic.evaluate(&bitboard, &pawn_eval(&bitboard), true) > heuristic.draw_score());
+    }
 
     #[test]
     fn test_neutral_psv() {
         let bitboard = Bitboard::default();
-        let actual_psv = SimpleHeuristic::piece_square_value(&bitboard);
+        let actual_psv = SimpleHeuristic::piece_square_value(&bitboard, 0);
         assert_eq!(actual_psv, 0);
     }
 
+    #[test]
+    fn test_start_position_is_balanced() {
+        let bitboard = Bitboard::default();
+
+        assert_eq!(SimpleHeuristic::default().evaluate(&bitboard, &pawn_eval(&bitboard), true), HeuristicWeights::default().tempo);
+    }
+
+    #[test]
+    fn test_evaluation_is_symmetric() {
+        let heuristic = SimpleHeuristic::default();
+
+        for fen in [
+            "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3",
+            "6k1/5ppp/8/3P4/8/8/5PP1/6K1 w - - 0 40",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "4k3/5ppp/8/2PP4/8/8/5PPP/4K3 b - - 0 1",
+        ] {
+            let bitboard = Bitboard::from_fen_string_unchecked(fen);
+            let mirrored_bitboard = bitboard.mirror();
+
+            let value = heuristic.evaluate(&bitboard, &pawn_eval(&bitboard), true);
+            let mirrored_value = heuristic.evaluate(&mirrored_bitboard, &pawn_eval(&mirrored_bitboard), true);
+
+            assert_eq!(value, -mirrored_value, "{}", fen);
+        }
+    }
+
+    #[test]
+    fn test_tempo_flips_with_side_to_move() {
+        let heuristic = SimpleHeuristic { weights: HeuristicWeights { tempo: 15, ..HeuristicWeights::default() } };
+        let without = SimpleHeuristic { weights: HeuristicWeights { tempo: 0, ..HeuristicWeights::default() } };
+
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 1",
+            "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 2 3",
+        ] {
+            let bitboard = Bitboard::from_fen_string_unchecked(fen);
+            let mirrored_bitboard = bitboard.mirror();
+
+            let value = heuristic.evaluate(&bitboard, &pawn_eval(&bitboard), true);
+            let mirrored_value = heuristic.evaluate(&mirrored_bitboard, &pawn_eval(&mirrored_bitboard), true);
+            let expected_tempo = if bitboard.turn == WHITE { 15 } else { -15 };
+
+            assert_eq!(value, -mirrored_value, "{}", fen);
+            assert_eq!(value - without.evaluate(&bitboard, &pawn_eval(&bitboard), true), expected_tempo, "{}", fen);
+        }
+    }
+
+    #[test]
+    fn test_piece_square_tables_are_tapered() {
+        // A passed pawn on the seventh rank is worth more in the endgame than with all pieces on the board
+        let early = SimpleHeuristic::piece_square_value(&Bitboard::from_fen_string_unchecked("4k3/3P4/8/8/8/8/8/4K3 w - - 0 1"), 0);
+        let late = SimpleHeuristic::piece_square_value(&Bitboard::from_fen_string_unchecked("4k3/3P4/8/8/8/8/8/4K3 w - - 0 1"), 255);
+
+        assert!(late > early, "expected {} > {}", late, early);
+    }
+
+    #[test]
+    fn test_king_shield() {
+        let heuristic = SimpleHeuristic::default();
+        let sheltered = Bitboard::from_fen_string_unchecked("r4rk1/ppp2ppp/8/8/8/8/PPP2PPP/R4RK1 w - - 0 1");
+        let exposed = Bitboard::from_fen_string_unchecked("r4rk1/ppp2ppp/8/8/5PPP/8/PPP5/R4RK1 w - - 0 1");
+
+        assert_eq!(SimpleHeuristic::king_shield_for_player(&sheltered.white, WHITE), 3);
+        assert_eq!(SimpleHeuristic::king_shield_for_player(&sheltered.black, BLACK), 3);
+        assert_eq!(SimpleHeuristic::king_shield_for_player(&exposed.white, WHITE), 0);
+
+        assert_eq!(heuristic.king_shield_value(&sheltered, 0), 0);
+        assert_eq!(heuristic.king_shield_value(&exposed, 0), -30);
+        assert_eq!(heuristic.king_shield_value(&exposed, 255), 0);
+
+        let without = SimpleHeuristic { weights: HeuristicWeights { king_shield: 0, ..HeuristicWeights::default() } };
+        assert_eq!(without.king_shield_value(&exposed, 0), 0);
+    }
+
     #[test]
     fn evaluate() {
-        println!("{}", SimpleHeuristic {}.evaluate(&Bitboard::from_fen_string_unchecked("rn2k2r/ppp2ppp/8/3pPP2/3P1q2/P1KB4/P1P4P/3R2N1 b kq - 0 14"), 0, true));
-        println!("{}", SimpleHeuristic {}.evaluate(&Bitboard::from_fen_string_unchecked("rn2k2r/ppp2ppp/8/3pPP2/3P1q2/P1KB4/P1P4P/3R2N1 w kq - 0 14"), 0, true));
+        println!("{}", SimpleHeuristic::default().evaluate(&Bitboard::from_fen_string_unchecked("rn2k2r/ppp2ppp/8/3pPP2/3P1q2/P1KB4/P1P4P/3R2N1 b kq - 0 14"), &PawnEval::default(), true));
+        println!("{}", SimpleHeuristic::default().evaluate(&Bitboard::from_fen_string_unchecked("rn2k2r/ppp2ppp/8/3pPP2/3P1q2/P1KB4/P1P4P/3R2N1 w kq - 0 14"), &PawnEval::default(), true));
+    }
+
+    #[test]
+    fn test_king_tropism_rewards_queen_near_enemy_king() {
+        let heuristic = SimpleHeuristic::default();
+
+        let near = heuristic.evaluate_ongoing(&Bitboard::from_fen_string_unchecked("6k1/5ppp/8/5Q2/8/8/5PPP/6K1 w - - 0 1"), &PawnEval::default());
+        let far = heuristic.evaluate_ongoing(&Bitboard::from_fen_string_unchecked("6k1/5ppp/8/8/8/8/Q4PPP/6K1 w - - 0 1"), &PawnEval::default());
+
+        assert!(near > far, "expected {} > {}", near, far);
+    }
+
+    #[test]
+    fn test_king_tropism_weight_is_configurable() {
+        let bitboard = Bitboard::from_fen_string_unchecked("6k1/5ppp/8/5Q2/8/8/5PPP/6K1 w - - 0 1");
+
+        let without = SimpleHeuristic { weights: HeuristicWeights { king_tropism: 0, ..HeuristicWeights::default() } }.evaluate_ongoing(&bitboard, &PawnEval::default());
+        let with = SimpleHeuristic { weights: HeuristicWeights { king_tropism: 10, ..HeuristicWeights::default() } }.evaluate_ongoing(&bitboard, &PawnEval::default());
+
+        assert!(with > without);
     }
 }
@@ -10,6 +10,12 @@ pub struct Metrics {
     pub quiescence_termination_ply_sum: u64,
     pub quiescence_termination_count: u64,
     pub started_quiescence_search_count: u64,
+    /// Quiet checking moves searched in quiescence
+    pub quiescence_check_extension_count: u64,
+    /// Successful win/draw/loss tablebase probes, reported as `tbhits`
+    pub tablebase_wdl_hits: u64,
+    /// Successful distance to zero tablebase probes, reported as `sbhits`
+    pub tablebase_dtz_hits: u64,
 }
 
 impl Metrics {
@@ -48,6 +54,10 @@ impl Metrics {
     pub fn quiescence_started_rate(&self) -> f64 {
         self.started_quiescence_search_count as f64 / self.negamax_nodes as f64
     }
+
+    pub fn quiescence_check_extension_rate(&self) -> f64 {
+        self.quiescence_check_extension_count as f64 / self.quiescence_nodes as f64
+    }
 }
 
 #[derive(Default)]
@@ -82,6 +92,11 @@ impl MetricsService {
         self.total.started_quiescence_search_count += 1;
     }
 
+    pub fn increment_quiescence_check_extensions(&mut self) {
+        self.last.quiescence_check_extension_count += 1;
+        self.total.quiescence_check_extension_count += 1;
+    }
+
     pub fn increment_quiescence_transposition_hits(&mut self) {
         self.last.quiescence_transposition_hits += 1;
         self.total.quiescence_transposition_hits += 1;
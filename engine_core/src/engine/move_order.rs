@@ -2,11 +2,13 @@ use std::cmp::Reverse;
 
 use inkayaku_board::Move;
 
+use crate::engine::table::history::HistoryTable;
+
 pub trait MoveOrder {
-    fn sort(&self, moves: &mut Vec<Move>, pv_move: Option<Move>, transposition_move: Option<Move>, killer_move: Option<Move>);
+    fn sort(&self, moves: &mut Vec<Move>, pv_move: Option<Move>, transposition_move: Option<Move>, killer_move: Option<Move>, history_table: &HistoryTable);
 }
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct MvvLvaMoveOrder;
 
 impl MvvLvaMoveOrder {
@@ -19,24 +21,58 @@ impl MvvLvaMoveOrder {
     fn move_bonus(mv: &Move, high_value_move: Option<Move>, bonus: i32) -> i32 {
         high_value_move.filter(|pv_move| pv_move.bits == mv.bits).map_or(0, |_| bonus)
     }
+
+    /// History of quiet moves, always lower than the MVV-LVA value of any capture
+    #[inline(always)]
+    fn history_bonus(mv: &Move, history_table: &HistoryTable) -> i32 {
+        if mv.is_quiet() { history_table.get(*mv) } else { 0 }
+    }
 }
 
 impl MoveOrder for MvvLvaMoveOrder {
-    fn sort(&self, moves: &mut Vec<Move>, pv_move: Option<Move>, transposition_move: Option<Move>, killer_move: Option<Move>) {
+    fn sort(&self, moves: &mut Vec<Move>, pv_move: Option<Move>, transposition_move: Option<Move>, killer_move: Option<Move>, history_table: &HistoryTable) {
         moves.sort_by_key(|mv| Reverse(
             Self::eval(mv)
                 + Self::move_bonus(mv, pv_move, 900_000)
                 + Self::move_bonus(mv, transposition_move, 800_000)
                 + Self::move_bonus(mv, killer_move, 700_000)
+                + Self::history_bonus(mv, history_table)
         ));
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use inkayaku_board::Bitboard;
+    use inkayaku_board::{Bitboard, Move};
 
     use crate::engine::move_order::{MoveOrder, MvvLvaMoveOrder};
+    use crate::engine::table::history::HistoryTable;
+
+    #[test]
+    fn test_history_orders_quiet_moves_after_killers_and_captures() {
+        let mut bitboard = Bitboard::from_fen_string_unchecked("k7/8/8/8/5q2/6Pp/7Q/K7 w - - 0 1");
+        let mut moves = bitboard.generate_legal_moves();
+
+        let capture = bitboard.find_uci("g3f4").unwrap();
+        let killer = bitboard.find_uci("a1b1").unwrap();
+        let history = bitboard.find_uci("h2e2").unwrap();
+
+        let mut history_table = HistoryTable::default();
+        for _ in 0..50 {
+            history_table.put(20, history);
+        }
+
+        MvvLvaMoveOrder.sort(&mut moves, None, None, Some(killer), &history_table);
+
+        let position = |mv: Move| moves.iter().position(|other| other.bits == mv.bits).unwrap();
+
+        let captures = moves.iter().filter(|mv| mv.is_attack()).count();
+
+        assert_eq!(position(killer), 0);
+        assert!(moves[1..=captures].iter().all(Move::is_attack));
+        assert!(moves[1..=captures].iter().any(|mv| mv.bits == capture.bits));
+        assert_eq!(position(history), captures + 1);
+    }
 
     #[test]
     #[ignore]
@@ -46,7 +82,7 @@ mod tests {
 
         let order = MvvLvaMoveOrder {};
 
-        order.sort(&mut moves, None, None, None);
+        order.sort(&mut moves, None, None, None, &HistoryTable::default());
 
         for mv in moves {
             println!("{}", mv.to_pgn_string(&mut bitboard).unwrap());
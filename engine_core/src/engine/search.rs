@@ -1,24 +1,58 @@
 use std::cmp::{max, min};
+use std::collections::VecDeque;
+use std::iter;
 use std::ops::{Div, Mul};
 use std::sync::Arc;
-use std::sync::mpsc::Receiver;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Receiver};
+use std::thread;
 use std::time::{Duration, SystemTime};
 
-use inkayaku_board::{Bitboard, Move};
-use inkayaku_board::constants::{ColorBits, WHITE, ZobristHash};
+use inkayaku_board::{Bitboard, CaptureClass, Move};
+use inkayaku_board::constants::{ColorBits, PAWN, SquareShiftBits, WHITE, ZobristHash};
 use inkayaku_core::fen::Fen;
-use inkayaku_uci::{Go, Info, UciMove, UciTx};
-use SearchMessage::{UciDebug, UciGo, UciPonderHit, UciPositionFrom, UciQuit, UciStop, UciUciNewGame};
+use inkayaku_uci::{Bound, Go, Info, Score, UciMove, UciTx};
+use SearchMessage::{UciDebug, UciGo, UciPonderHit, UciPositionFrom, UciQuit, UciSetOption, UciStop, UciUciNewGame};
+use StopCondition::{Depth, Infinite, Mate, MoveTime, Nodes};
 
+use crate::engine::book::PolyglotBook;
 use crate::engine::heuristic::Heuristic;
 use crate::engine::metrics::{Metrics, MetricsService};
 use crate::engine::move_order::MoveOrder;
+use crate::engine::table::history::HistoryTable;
 use crate::engine::table::killer::KillerTable;
-use crate::engine::table::transposition::{HashMapTranspositionTable, TranspositionTable, TtEntry};
+use crate::engine::table::pawn::PawnTable;
+use crate::engine::table::transposition::{ArrayTranspositionTable, SharedTranspositionTable, TtEntry};
 use crate::engine::table::transposition::NodeType::{Exact, Lowerbound, Upperbound};
+use crate::engine::tablebase::Tablebases;
 use crate::engine::zobrist_history::ZobristHistory;
 use crate::move_into_uci_move;
 
+/// Minimum remaining draft to try a singular extension of the transposition table move
+const SINGULAR_EXTENSION_MIN_DRAFT: usize = 4;
+/// Margin below the transposition table value all other moves have to fail low against for the move to be singular
+const SINGULAR_EXTENSION_MARGIN: i32 = 50;
+/// Maximum number of singular extensions along a single line
+const SINGULAR_EXTENSION_LIMIT: usize = 4;
+/// Initial distance of the aspiration window bounds from the value of the previous iteration
+const ASPIRATION_WINDOW_DELTA: i32 = 50;
+/// Largest distance of the aspiration window bounds before falling back to the full window
+const ASPIRATION_WINDOW_MAX_DELTA: i32 = 1000;
+/// Share of the remaining time kept in reserve with `movestogo` time controls
+const MOVES_TO_GO_RESERVE: f64 = 0.05;
+/// Share of the increment added to the thinking time with `movestogo` time controls
+const MOVES_TO_GO_INCREMENT_FACTOR: f64 = 0.5;
+/// Time left on the clock for communication with the GUI, the thinking time never exceeds the remaining time minus this
+const MOVE_OVERHEAD: Duration = Duration::from_millis(50);
+/// Margin on top of the material a capture wins for positional gains before delta pruning it
+const DELTA_PRUNING_MARGIN: i32 = 200;
+/// Delta pruning is disabled with at most this much non-pawn material on the board, where few pieces cause large swings
+const DELTA_PRUNING_MIN_NON_PAWN_MATERIAL: i32 = 1300;
+/// Centipawns a score has to exceed to be more likely a win than a draw in the early game, doubling towards the endgame
+const WDL_DRAW_MARGIN: f64 = 100.0;
+/// Maximum length of a principal variation, including the moves rebuilt from the transposition table
+const MAX_PRINCIPAL_VARIATION_LENGTH: usize = 64;
+
 pub struct Search<T: UciTx, H: Heuristic, M: MoveOrder> {
     uci_tx: Arc<T>,
     search_rx: Receiver<SearchMessage>,
@@ -29,53 +63,206 @@ pub struct Search<T: UciTx, H: Heuristic, M: MoveOrder> {
     options: EngineOptions,
     flags: SearchFlags,
     params: SearchParams,
+    /// Messages received during go that are handled after it ends
+    pending_messages: VecDeque<SearchMessage>,
 }
 
-impl<T: UciTx, H: Heuristic, M: MoveOrder> Search<T, H, M> {
-    pub fn new(uci_tx: Arc<T>, rx: Receiver<SearchMessage>, heuristic: H, move_order: M, options: EngineOptions) -> Self {
-        Self { uci_tx, search_rx: rx, state: SearchState::default(), options, flags: SearchFlags::default(), params: SearchParams::default(), heuristic, move_order }
+impl<T: UciTx + Send + Sync, H: Heuristic + Clone + Send, M: MoveOrder + Clone + Send> Search<T, H, M> {
+    pub fn new(uci_tx: Arc<T>, rx: Receiver<SearchMessage>, mut heuristic: H, move_order: M, options: EngineOptions) -> Self {
+        heuristic.set_tempo(options.tempo);
+        Self { uci_tx, search_rx: rx, state: SearchState::new(&options), options, flags: SearchFlags::default(), params: SearchParams::default(), pending_messages: VecDeque::new(), heuristic, move_order }
     }
 
     pub fn idle(&mut self) {
         while !self.flags.quit_as_soon_as_possible {
-            if let Ok(message) = self.search_rx.recv() {
-                match message {
-                    UciUciNewGame => {
-                        self.flags.reset_for_next_search = true;
-                    }
-                    UciDebug(debug) => {
-                        self.options.debug = debug;
-                    }
-                    UciPositionFrom(fen, moves) => {
-                        self.set_position_from(fen, moves);
-                    }
-                    UciGo(go) => {
-                        self.params.go = go;
-                        self.go();
-                    }
-                    UciStop | UciPonderHit => {
-                        // ignore during idle
-                    }
-                    UciQuit => {
-                        self.flags.quit_as_soon_as_possible = true;
+            let Some(message) = self.pending_messages.pop_front().or_else(|| self.search_rx.recv().ok()) else {
+                // The engine hung up without sending quit
+                return;
+            };
+
+            match message {
+                UciUciNewGame => {
+                    self.flags.reset_for_next_search = true;
+                }
+                UciDebug(debug) => {
+                    self.options.debug = debug;
+                }
+                UciSetOption(name, value) => {
+                    self.set_option(&name, &value);
+                }
+                UciPositionFrom(fen, moves) => {
+                    self.set_position_from(fen, moves);
+                }
+                UciGo(go) => {
+                    self.params.go = go;
+                    self.go();
+                }
+                UciStop | UciPonderHit => {
+                    // ignore during idle
+                }
+                UciQuit => {
+                    self.flags.quit_as_soon_as_possible = true;
+                }
+            }
+        }
+    }
+
+    /// Apply a machine configuration option, buttons have an empty `value`. Options persist across games, unknown options and values are ignored.
+    fn set_option(&mut self, name: &str, value: &str) {
+        match name {
+            "Hash" => {
+                if let Ok(hash_size_mb) = value.parse() {
+                    self.options.hash_size_mb = hash_size_mb;
+
+                    if self.state.transposition_table.capacity() != ArrayTranspositionTable::capacity_for_size_mb(hash_size_mb) {
+                        self.state.transposition_table = Arc::new(SharedTranspositionTable::with_size_mb(hash_size_mb));
                     }
                 }
             }
+            "Clear Hash" => {
+                self.state.transposition_table.clear();
+                self.state.pawn_table.clear();
+            }
+            "Threads" => {
+                if let Ok(threads) = value.parse() {
+                    self.options.threads = threads;
+                }
+            }
+            "Contempt" => {
+                if let Ok(contempt_factor) = value.parse() {
+                    self.options.contempt_factor = contempt_factor;
+                }
+            }
+            "Tempo" => {
+                if let Ok(tempo) = value.parse() {
+                    self.options.tempo = tempo;
+                    self.heuristic.set_tempo(tempo);
+                }
+            }
+            "Ponder" => {
+                if let Ok(ponder) = value.parse() {
+                    self.options.ponder = ponder;
+                }
+            }
+            "MultiPV" => {
+                if let Ok(multi_pv) = value.parse() {
+                    self.options.multi_pv = multi_pv;
+                }
+            }
+            "UCI_ShowRefutations" => {
+                if let Ok(show_refutations) = value.parse() {
+                    self.options.show_refutations = show_refutations;
+                }
+            }
+            "ShowWDL" => {
+                if let Ok(show_wdl) = value.parse() {
+                    self.options.show_wdl = show_wdl;
+                }
+            }
+            "WDLScale" => {
+                if let Ok(wdl_scale) = value.parse() {
+                    self.options.wdl_scale = wdl_scale;
+                }
+            }
+            "SyzygyPath" => {
+                self.set_syzygy_path(value);
+            }
+            "OwnBook" => {
+                if let Ok(own_book) = value.parse() {
+                    self.options.own_book = own_book;
+                }
+            }
+            "BookFile" => {
+                self.set_book_file(value);
+            }
+            "SyzygyProbeLimit" => {
+                if let Ok(syzygy_probe_limit) = value.parse() {
+                    self.options.syzygy_probe_limit = syzygy_probe_limit;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Load the tablebases from `path`. If loading fails, a warning is sent and probing stays disabled.
+    fn set_syzygy_path(&mut self, path: &str) {
+        if self.options.syzygy_path.as_deref() == Some(path) {
+            return;
+        }
+
+        self.state.tablebases = Tablebases::default();
+        self.options.syzygy_path = None;
+
+        if path.is_empty() || path == "<empty>" {
+            return;
+        }
+
+        match Tablebases::load(path) {
+            Ok(tablebases) => {
+                self.uci_tx.info(&Info { string: Some(format!("found {} tablebases with up to {} pieces", tablebases.len(), tablebases.max_pieces())), ..Info::EMPTY });
+                self.state.tablebases = tablebases;
+                self.options.syzygy_path = Some(path.to_string());
+            }
+            Err(error) => {
+                self.uci_tx.info(&Info { string: Some(format!("warning could not load SyzygyPath {}: {}", path, error)), ..Info::EMPTY });
+            }
+        }
+    }
+
+    /// Load the Polyglot book from `path`. If loading fails, a warning is sent and no book is used.
+    fn set_book_file(&mut self, path: &str) {
+        if self.options.book_file.as_deref() == Some(path) {
+            return;
+        }
+
+        self.state.book = PolyglotBook::default();
+        self.options.book_file = None;
+
+        if path.is_empty() || path == "<empty>" {
+            return;
+        }
+
+        match PolyglotBook::load(path) {
+            Ok(book) => {
+                self.uci_tx.info(&Info { string: Some(format!("loaded book with {} entries", book.len())), ..Info::EMPTY });
+                self.state.book = book;
+                self.options.book_file = Some(path.to_string());
+            }
+            Err(error) => {
+                self.uci_tx.info(&Info { string: Some(format!("warning could not load BookFile {}: {}", path, error)), ..Info::EMPTY });
+            }
+        }
+    }
+
+    /// A book move for the current position if `OwnBook` is enabled. The book is not used for searches that are not
+    /// about playing a move, like `infinite`, `ponder` or `mate`.
+    fn book_move(&mut self) -> Option<Move> {
+        let go = &self.params.go;
+
+        if !self.options.own_book || self.state.book.is_empty() || go.infinite || go.ponder || go.mate.is_some() {
+            return None;
         }
+
+        self.state.book.choose_move(&mut self.state.bitboard, &mut rand::thread_rng())
+    }
+
+    /// Tablebases are only probed if any were loaded and the probe limit allows it
+    fn is_tablebase_probing_enabled(&self) -> bool {
+        !self.state.tablebases.is_empty() && self.options.syzygy_probe_limit > 0
     }
 
     fn set_position_from(&mut self, fen: Fen, moves: Vec<UciMove>) {
         let mut board = Bitboard::from(&fen);
         let mut zobrist_history = ZobristHistory::default();
-        zobrist_history.set(board.ply_clock(), board.calculate_zobrist_hash());
+        zobrist_history.set(board.ply_clock(), board.zobrist_hash());
 
         let mut bb_moves = Vec::new();
 
         for uci in moves {
-            match board.find_uci(&uci.to_string()) {
+            match board.resolve_uci_move(&uci) {
                 Ok(mv) => {
                     board.make(mv);
-                    zobrist_history.set(board.ply_clock(), board.calculate_zobrist_hash());
+                    zobrist_history.set(board.ply_clock(), board.zobrist_hash());
                     bb_moves.push(mv);
                 }
                 Err(error) => {
@@ -94,27 +281,7 @@ impl<T: UciTx, H: Heuristic, M: MoveOrder> Search<T, H, M> {
     fn check_messages(&mut self) {
         loop {
             match self.search_rx.try_recv() {
-                Ok(message) => match message {
-                    UciUciNewGame => {
-                        self.flags.reset_for_next_search = true;
-                    }
-                    UciDebug(debug) => {
-                        self.options.debug = debug;
-                    }
-                    UciPositionFrom(..) | UciGo(..) => {
-                        // Ignore during go
-                    }
-                    UciStop => {
-                        self.flags.stop_as_soon_as_possible = true;
-                    }
-                    UciPonderHit => {
-                        self.flags.ponder_hit = true;
-                    }
-                    UciQuit => {
-                        self.flags.stop_as_soon_as_possible = true;
-                        self.flags.quit_as_soon_as_possible = true;
-                    }
-                },
+                Ok(message) => self.handle_message_during_go(message),
                 Err(error) => {
                     self.uci_tx.debug(&format!("{}", error));
                     return;
@@ -123,19 +290,75 @@ impl<T: UciTx, H: Heuristic, M: MoveOrder> Search<T, H, M> {
         }
     }
 
+    fn handle_message_during_go(&mut self, message: SearchMessage) {
+        match message {
+            UciUciNewGame => {
+                self.flags.reset_for_next_search = true;
+            }
+            UciDebug(debug) => {
+                self.options.debug = debug;
+            }
+            UciSetOption(..) => {
+                // Ignore during go
+            }
+            UciPositionFrom(..) | UciGo(..) => {
+                // GUIs may send the next position after `stop` without waiting for `bestmove`
+                self.pending_messages.push_back(message);
+            }
+            UciStop => {
+                self.flags.stop_as_soon_as_possible = true;
+            }
+            UciPonderHit => {
+                if self.is_pondering() {
+                    self.ponder_hit();
+                }
+            }
+            UciQuit => {
+                self.flags.stop_as_soon_as_possible = true;
+                self.flags.quit_as_soon_as_possible = true;
+            }
+        }
+    }
+
+    /// Searching the position after the predicted opponent move, without limits until `ponderhit` or `stop`
+    const fn is_pondering(&self) -> bool {
+        self.params.go.ponder && !self.flags.ponder_hit
+    }
+
+    /// The opponent played the ponder move, continue as a regular search with time accounting starting now
+    fn ponder_hit(&mut self) {
+        self.flags.ponder_hit = true;
+        self.state.started_at = SystemTime::now();
+        self.state.stop_condition = self.stop_condition();
+        self.update_check_flags_interval();
+    }
+
+    /// The best move of a ponder search may only be sent after `ponderhit` or `stop`, even if the search finished early
+    fn wait_for_ponder_end(&mut self) {
+        while self.is_pondering() && !self.flags.stop_as_soon_as_possible {
+            match self.search_rx.recv() {
+                Ok(message) => self.handle_message_during_go(message),
+                Err(_) => return,
+            }
+        }
+    }
+
     fn create_buffer() -> Vec<Move> {
         Vec::with_capacity(200)
     }
 
-    /// Reset the search for the next go
+    /// Reset the search for the next go. After `ucinewgame`, all per game state is reset, while options are kept.
     fn reset_for_go(&mut self) {
         if self.flags.reset_for_next_search {
             self.state.metrics = MetricsService::default();
             self.state.transposition_table.clear();
             self.state.killer_table.clear();
+            self.state.history_table.clear();
+            self.state.principal_variation = None;
             self.flags.reset_for_next_search = false;
         } else {
             self.state.metrics.last = Metrics::default();
+            self.state.history_table.age();
         }
 
         self.flags = SearchFlags::default();
@@ -143,6 +366,16 @@ impl<T: UciTx, H: Heuristic, M: MoveOrder> Search<T, H, M> {
 
     // Start the search
     pub fn go(&mut self) {
+        if let Some(depth) = self.params.go.perft {
+            self.perft(depth as usize);
+            return;
+        }
+
+        if self.params.go.depth == Some(0) {
+            self.static_evaluation();
+            return;
+        }
+
         self.reset_for_go();
 
         self.state.is_running = true;
@@ -154,6 +387,31 @@ impl<T: UciTx, H: Heuristic, M: MoveOrder> Search<T, H, M> {
         self.state.is_running = false;
     }
 
+    /// Send the node count of every legal move at `depth` and their total, sorted like the divide listing of Stockfish
+    fn perft(&mut self, depth: usize) {
+        let mut divide = self.state.bitboard.perft(depth).into_iter().map(|(mv, count)| (move_into_uci_move(mv), count)).collect::<Vec<_>>();
+        divide.sort_by_key(|(mv, _)| mv.to_string());
+
+        let nodes = divide.iter().map(|(_, count)| count).sum();
+
+        self.uci_tx.perft(&divide, nodes);
+    }
+
+    /// Send the evaluation of the current position as used at the leaves of the search, from white's perspective,
+    /// without searching. There is no best move.
+    fn static_evaluation(&mut self) {
+        let legal_moves_remaining = !self.state.bitboard.generate_legal_moves().is_empty();
+        let value = self.evaluate(WHITE, self.state.bitboard.pawn_zobrist_hash(), legal_moves_remaining);
+
+        self.uci_tx.info(&Info {
+            depth: Some(0),
+            score: Some(self.heuristic.score_from_value(value, &self.state.bitboard)),
+            string: Some("static evaluation from white's perspective".to_string()),
+            ..Info::EMPTY
+        });
+        self.uci_tx.best_move(None, None);
+    }
+
     // Time remaining of the engine
     const fn get_self_time_remaining(&self) -> Option<Duration> {
         if self.state.bitboard.turn == WHITE { self.params.go.white_time } else { self.params.go.black_time }
@@ -193,13 +451,20 @@ impl<T: UciTx, H: Heuristic, M: MoveOrder> Search<T, H, M> {
         }
     }
 
+    /// Thinking time for the move from the clocks. With `movestogo`, the remaining time minus a reserve is split evenly
+    /// between the moves until the next time control, plus part of the increment.
     #[allow(clippy::option_if_let_else)]
     fn calculate_max_thinking_time(&self) -> Option<Duration> {
         let increment = self.get_self_increment();
         let time_remaining = self.get_self_time_remaining();
+        let moves_to_go = self.params.go.moves_to_go.filter(|&moves_to_go| moves_to_go > 0);
 
         if let Some(time_remaining) = time_remaining {
-            if let Some(increment) = increment {
+            if let Some(moves_to_go) = moves_to_go {
+                let budget = time_remaining.mul_f64(1.0 - MOVES_TO_GO_RESERVE).div(moves_to_go as u32) + increment.unwrap_or_default().mul_f64(MOVES_TO_GO_INCREMENT_FACTOR);
+
+                Some(min(budget, time_remaining.saturating_sub(MOVE_OVERHEAD)))
+            } else if let Some(increment) = increment {
                 let increment_factor = match time_remaining.as_secs() {
                     20.. => 1.0,
                     10.. => 0.75,
@@ -207,493 +472,1984 @@ impl<T: UciTx, H: Heuristic, M: MoveOrder> Search<T, H, M> {
                     _ => 0.25,
                 };
 
-                Some(increment.mul_f64(increment_factor))
+                Some(increment.mul_f64(increment_factor).mul(2))
             } else {
-                Some(time_remaining.div(60))
+                Some(time_remaining.div(60).mul(2))
             }
         } else {
             None
         }
     }
 
+    /// Determine what ends the search. Pondering is unlimited until `ponderhit`. `infinite`, `depth`, `nodes` and `mate`
+    /// take precedence over time management, in that order. Without any of them, an explicit `movetime` is used before
+    /// the time calculated from the clocks.
+    fn stop_condition(&self) -> StopCondition {
+        let go = &self.params.go;
+
+        if self.is_pondering() || go.infinite {
+            Infinite
+        } else if let Some(depth) = go.depth {
+            Depth(depth as usize)
+        } else if let Some(nodes) = go.nodes {
+            Nodes(nodes)
+        } else if let Some(mate) = go.mate {
+            Mate(mate)
+        } else if let Some(move_time) = go.move_time.or_else(|| self.calculate_max_thinking_time()) {
+            MoveTime(move_time)
+        } else {
+            Infinite
+        }
+    }
+
     fn best_move(&mut self) -> (Option<UciMove>, Option<UciMove>) {
+        if let Some(mv) = self.book_move() {
+            self.uci_tx.info(&Info { string: Some(format!("book move {}", mv.to_uci_string())), ..Info::EMPTY });
+            return (Some(move_into_uci_move(mv)), None);
+        }
+
         self.state.transposition_table.clear();
         self.state.killer_table.age(2);
 
         self.state.started_at = SystemTime::now();
 
-        let mut best_move = None;
-
         if self.options.try_previous_pv {
             self.try_set_pv_from_continuation().ok();
         }
 
-        let max_depth = self.params.go.depth.map_or(999_999, |d| d as usize);
+        self.state.stop_condition = self.stop_condition();
+        self.update_check_flags_interval();
+
+        let helper_stop = Arc::new(AtomicBool::new(false));
+
+        let (best_move, mate_found) = thread::scope(|scope| {
+            let helpers = (1..self.options.threads)
+                .map(|index| {
+                    let mut helper = self.create_helper(Arc::clone(&helper_stop));
+                    scope.spawn(move || helper.search_as_helper(index))
+                })
+                .collect::<Vec<_>>();
+
+            let (mut best_move, completed_depth, mate_found) = self.iterative_deepening();
+
+            helper_stop.store(true, Ordering::Relaxed);
+
+            let deepest_helper_result = helpers
+                .into_iter()
+                .filter_map(|helper| helper.join().ok().flatten())
+                .max_by_key(|&(depth, _)| depth)
+                .filter(|&(depth, _)| depth > completed_depth);
+
+            if let Some((depth, helper_best_move)) = deepest_helper_result {
+                self.report_helper_result(depth, &helper_best_move);
+                best_move = Some(helper_best_move);
+            }
+
+            (best_move, mate_found)
+        });
+
+        self.wait_for_ponder_end();
+
+        if let Mate(moves) = self.state.stop_condition {
+            if !mate_found && !self.flags.stop_as_soon_as_possible {
+                self.uci_tx.info(&Info { string: Some(format!("no mate in {} found", moves)), ..Info::EMPTY });
+            }
+        }
 
-        if self.params.go.move_time.is_none() {
-            self.params.go.move_time = self.calculate_max_thinking_time().map(|d| d.mul(2));
+        if self.options.show_wdl {
+            if let Some(value) = best_move.as_ref().and_then(|vm| vm.mv.map(|_| vm.value)) {
+                let score = self.heuristic.score_from_value(value, &self.state.bitboard);
+                let (win, draw, loss) = calculate_wdl(score, self.state.bitboard.phase(), self.options.wdl_scale);
+                self.uci_tx.info(&Info { string: Some(format!("wdl {} {} {}", win, draw, loss)), ..Info::EMPTY });
+            }
         }
 
-        let max_thinking_time = self.params.go.move_time.unwrap_or(Duration::MAX);
+        self.state.metrics.increment_duration(&self.state.elapsed());
+
+        // Checkmated or stalemated positions have neither a best move nor a ponder move
+        let Some(best_move) = best_move.and_then(|vm| vm.mv) else {
+            return (self.emergency_move().map(move_into_uci_move), None);
+        };
+
+        let ponder_move = self.state.ponder_move().or_else(|| {
+            if self.options.ponder_fallback { self.fallback_ponder_move(best_move) } else { None }
+        });
+
+        (Some(move_into_uci_move(best_move)), ponder_move.map(move_into_uci_move))
+    }
+
+    /// Search iteratively deeper until the stop condition is met, reporting every iteration. Returns the result and
+    /// depth of the last completed iteration and if the mate searched for was found.
+    fn iterative_deepening(&mut self) -> (Option<ValuedMove>, usize, bool) {
+        let max_depth = self.state.stop_condition.max_depth();
 
+        let mut best_move = None;
+        let mut completed_depth = 0;
         let mut uci_pv = None;
         let mut score = None;
+        let mut mate_found = false;
+        let mut previous_value = None;
 
         for depth in 1..=max_depth {
-            let current_best_move = self.search_negamax(
-                &mut Self::create_buffer(),
-                0,
-                depth,
-                self.heuristic.loss_score(),
-                self.heuristic.win_score(),
-                self.state.principal_variation.is_some(),
-                self.state.bitboard.calculate_zobrist_hash(),
-                self.state.bitboard.calculate_zobrist_pawn_hash(),
-            );
+            self.state.iteration_depth = depth;
+            self.state.selective_depth = 0;
+
+            let current_best_move = self.search_root(depth, previous_value);
 
             let elapsed = self.state.elapsed();
 
-            let too_little_time = elapsed > max_thinking_time.div(3);
-            let aborted = self.flags.stop_as_soon_as_possible || current_best_move.mv.is_none();
+            // The stop condition changes if `ponderhit` was received during the iteration
+            let too_little_time = self.state.stop_condition.move_time().is_some_and(|max_thinking_time| elapsed > max_thinking_time.div(3));
+            let root_move = current_best_move.mv;
+            let aborted = self.flags.stop_as_soon_as_possible || root_move.is_none();
             let stop = aborted || too_little_time;
 
-            if !stop {
-                let bb_pv = current_best_move.calculate_principal_variation();
+            // A completed iteration is used even if there is no time for the next one
+            if !aborted {
+                let bb_pv = self.principal_variation(&current_best_move);
                 self.state.principal_variation = Some(bb_pv.clone());
                 uci_pv = Some(bb_pv.into_iter().map(move_into_uci_move).collect::<Vec<_>>());
                 score = Some(self.heuristic.score_from_value(current_best_move.value, &self.state.bitboard));
+                previous_value = Some(current_best_move.value);
 
                 best_move = Some(current_best_move);
+                completed_depth = depth;
+                mate_found = self.state.stop_condition.is_satisfied_by(score);
             }
 
+            let debug_string = self.generate_debug_string_if_enabled();
+
             self.uci_tx.info(&Info {
                 principal_variation: uci_pv.clone(),
                 time: Some(elapsed),
                 score,
                 depth: Some((if aborted { depth - 1 } else { depth }) as u32),
-                string: self.generate_debug_string_if_enabled(),
+                multi_pv: (self.options.multi_pv > 1).then_some(1),
+                string: debug_string,
                 ..self.generate_info()
             });
 
-            if stop {
+            if let (false, Some(mv)) = (stop, root_move) {
+                if self.options.multi_pv > 1 {
+                    self.search_multi_pv(depth, mv);
+                }
+            }
+
+            if stop || mate_found || depth >= self.state.stop_condition.max_depth() {
                 break;
             }
         }
 
-        self.state.metrics.increment_duration(&self.state.elapsed());
-
-        (best_move.and_then(|vm| vm.mv).map(move_into_uci_move), self.state.ponder_move().map(move_into_uci_move))
-    }
-
-    fn evaluate(&self, color: ColorBits, zobrist_pawn_hash: ZobristHash, legal_moves_remaining: bool) -> i32 {
-        calculate_heuristic_factor(color) * self.heuristic.evaluate(&self.state.bitboard, zobrist_pawn_hash, legal_moves_remaining)
-    }
-
-    #[inline(always)]
-    fn should_check_flags(&mut self) -> bool {
-        self.state.metrics.last.negamax_nodes % 100_000 == 0 && self.state.metrics.last.negamax_nodes > 0
+        (best_move, completed_depth, mate_found)
     }
 
-    fn filter_search_moves(&mut self, buffer: &mut Vec<Move>) {
-        let search_moves = &self.params.go.search_moves;
+    /// A search of the current position on a separate thread, sharing the transposition table
+    fn create_helper(&self, helper_stop: Arc<AtomicBool>) -> Self {
+        let (_, search_rx) = channel();
 
-        if !search_moves.is_empty() {
-            buffer.retain(|&mv| {
-                search_moves.contains(&move_into_uci_move(mv))
-            });
+        Self {
+            uci_tx: Arc::clone(&self.uci_tx),
+            search_rx,
+            heuristic: self.heuristic.clone(),
+            move_order: self.move_order.clone(),
+            state: self.state.for_helper(helper_stop),
+            options: self.options.clone(),
+            flags: SearchFlags::default(),
+            params: self.params.clone(),
+            pending_messages: VecDeque::new(),
         }
     }
 
-    #[allow(clippy::unwrap_used)]
-    #[allow(clippy::too_many_arguments)]
-    fn search_negamax(&mut self, buffer: &mut Vec<Move>, ply_depth_from_root: usize, max_ply: usize, alpha_original: i32, beta_original: i32, is_pv: bool, zobrist_hash: ZobristHash, zobrist_pawn_hash: ZobristHash) -> ValuedMove {
-        let color = self.state.bitboard.turn;
+    /// Search iteratively deeper without reporting, until stopped by the main thread or the maximum depth is reached.
+    /// Every second helper starts a ply deeper, so the threads diverge. Returns the depth and result of the last
+    /// completed iteration.
+    fn search_as_helper(&mut self, index: usize) -> Option<(usize, ValuedMove)> {
+        let mut result = None;
 
-        let check_flags = self.should_check_flags();
-        if check_flags {
-            self.check_messages();
-            self.uci_tx.info(&Info {
-                time: Some(self.state.elapsed()),
-                ..self.generate_info()
-            });
+        for depth in (1 + index % 2)..=self.state.stop_condition.max_depth() {
+            self.state.iteration_depth = depth;
 
-            if let Some(move_time) = self.params.go.move_time {
-                if self.state.elapsed() > move_time {
-                    self.flags.stop_as_soon_as_possible = true;
-                    return ValuedMove::leaf(0);
-                }
+            let current_best_move = self.search_negamax(&mut Self::create_buffer(), 0, depth, self.heuristic.loss_score(), self.heuristic.win_score(), false);
+
+            if self.flags.stop_as_soon_as_possible || current_best_move.mv.is_none() {
+                break;
             }
+
+            result = Some((depth, current_best_move));
         }
 
-        self.state.metrics.increment_negamax_nodes();
+        result
+    }
 
-        let ply_clock = self.state.bitboard.ply_clock();
-        let halfmove_clock = self.state.bitboard.halfmove_clock;
-        self.state.zobrist_history.set(ply_clock, zobrist_hash);
+    /// The principal variation of `result`, continued with the transposition table moves of the positions after its last
+    /// move. The line ends at a missing or illegal move or a position that already occurred in it.
+    fn principal_variation(&mut self, result: &ValuedMove) -> Vec<Move> {
+        let mut pv = result.calculate_principal_variation();
+        pv.truncate(MAX_PRINCIPAL_VARIATION_LENGTH);
 
-        if self.state.zobrist_history.count_repetitions(ply_clock, halfmove_clock as u16) >= 3 {
-            let contempt_factor_factor = if ply_depth_from_root % 2 == 0 { 1 } else { -1 };
+        let mut buffer = Self::create_buffer();
+        let mut hashes = vec![self.state.bitboard.zobrist_hash()];
 
-            return ValuedMove::leaf(self.heuristic.draw_score() + contempt_factor_factor * self.options.contempt_factor);
+        for &mv in &pv {
+            self.state.bitboard.make(mv);
+            hashes.push(self.state.bitboard.zobrist_hash());
         }
 
-        let maybe_tt_entry = self.state.transposition_table.get(zobrist_hash);
-
-        let mut alpha = alpha_original;
-        let mut beta = beta_original;
-
-        let mut tt_move = None;
-
-        let remaining_draft = max_ply - ply_depth_from_root;
+        while pv.len() < MAX_PRINCIPAL_VARIATION_LENGTH {
+            let Some(mv) = self.state.transposition_table.get(self.state.bitboard.zobrist_hash()).and_then(|tt_entry| tt_entry.mv) else { break; };
 
-        if let Some(tt_entry) = maybe_tt_entry {
-            if tt_entry.depth >= remaining_draft {
-                self.state.metrics.increment_transposition_hits();
-                match tt_entry.node_type {
-                    Lowerbound => alpha = max(alpha, tt_entry.value),
-                    Upperbound => beta = min(beta, tt_entry.value),
-                    Exact => {
-                        return tt_entry.mv.clone();
-                    }
-                }
-                if alpha >= beta {
-                    return tt_entry.mv.clone();
-                }
+            // Guard against hash collisions
+            buffer.clear();
+            self.state.bitboard.generate_pseudo_legal_moves_with_buffer(&mut buffer);
+            if !buffer.contains(&mv) || !self.state.bitboard.is_move_legal(mv) {
+                break;
             }
-            tt_move = tt_entry.mv.mv;
-        };
-
-        buffer.clear();
-        self.state.bitboard.generate_pseudo_legal_moves_with_buffer(buffer);
 
-        let is_root = ply_depth_from_root == 0;
-        if is_root {
-            self.filter_search_moves(buffer);
+            self.state.bitboard.make(mv);
 
-            if buffer.is_empty() {
-                return ValuedMove::leaf(0);
+            let zobrist_hash = self.state.bitboard.zobrist_hash();
+            if hashes.contains(&zobrist_hash) {
+                self.state.bitboard.unmake(mv);
+                break;
             }
+
+            hashes.push(zobrist_hash);
+            pv.push(mv);
         }
 
-        let is_max_ply = ply_depth_from_root == max_ply;
-        if is_max_ply {
-            let legal_moves_remaining = self.state.bitboard.is_any_move_legal(buffer);
+        for &mv in pv.iter().rev() {
+            self.state.bitboard.unmake(mv);
+        }
 
-            if legal_moves_remaining && Bitboard::is_any_move_non_quiescent(buffer) {
-                self.state.metrics.increment_started_quiescence_search();
-                return self.search_quiescence(0, buffer, alpha, beta, zobrist_pawn_hash);
-            }
+        pv
+    }
 
-            let value = self.evaluate(color, zobrist_pawn_hash, legal_moves_remaining);
-            return ValuedMove::leaf(value);
-        }
+    /// Report the result of a helper that completed a deeper iteration than the main thread, which is played instead
+    fn report_helper_result(&mut self, depth: usize, helper_best_move: &ValuedMove) {
+        let pv = self.principal_variation(helper_best_move);
 
-        let pv_move = if is_pv { self.state.principal_variation.as_ref().unwrap().get(ply_depth_from_root).copied() } else { None };
-        let killer_move = self.state.killer_table.get(remaining_draft);
-        self.move_order.sort(buffer, pv_move, tt_move, killer_move);
+        self.uci_tx.info(&Info {
+            principal_variation: Some(pv.iter().copied().map(move_into_uci_move).collect()),
+            time: Some(self.state.elapsed()),
+            score: Some(self.heuristic.score_from_value(helper_best_move.value, &self.state.bitboard)),
+            depth: Some(depth as u32),
+            ..self.generate_info()
+        });
 
-        let mut best_value = self.heuristic.loss_score();
-        let mut best_child: Option<ValuedMove> = None;
-        let mut best_move: Option<Move> = None;
-        let mut legal_moves_encountered = false;
+        self.state.principal_variation = Some(pv);
+    }
 
-        let mut next_buffer = Self::create_buffer();
+    /// The first legal move if the search was stopped before completing the first iteration
+    fn emergency_move(&mut self) -> Option<Move> {
+        let mut legal_moves = self.state.bitboard.generate_legal_moves();
+        self.filter_search_moves(&mut legal_moves);
 
-        for mv in buffer {
-            self.state.bitboard.make(*mv);
-            if !self.state.bitboard.is_valid() {
-                self.state.bitboard.unmake(*mv);
-                continue;
-            }
+        legal_moves.first().copied()
+    }
 
-            let (zobrist_xor, zobrist_pawn_xor) = Bitboard::zobrist_xor(*mv);
+    /// Search the root at `depth` in a window around the value of the previous iteration. The window is widened on a fail
+    /// low or fail high, until it falls back to the full window.
+    fn search_root(&mut self, depth: usize, previous_value: Option<i32>) -> ValuedMove {
+        let loss_score = self.heuristic.loss_score();
+        let win_score = self.heuristic.win_score();
 
-            legal_moves_encountered = true;
+        let center = previous_value.filter(|&value| self.options.aspiration_windows && !self.heuristic.is_checkmate(value));
+        let mut delta = ASPIRATION_WINDOW_DELTA;
 
-            let child = self.search_negamax(
-                &mut next_buffer,
-                ply_depth_from_root + 1,
-                max_ply,
-                -beta,
-                -alpha,
-                is_pv && pv_move.map_or(false, |pv_mv| pv_mv.bits == mv.bits),
-                zobrist_hash ^ zobrist_xor,
-                zobrist_pawn_hash ^ zobrist_pawn_xor,
-            );
+        let (mut alpha, mut beta) = center.map_or((loss_score, win_score), |center| (center - delta, center + delta));
 
-            if self.flags.stop_as_soon_as_possible {
-                return ValuedMove::new(0, None, None);
-            }
+        loop {
+            let result = self.search_negamax(&mut Self::create_buffer(), 0, depth, alpha, beta, self.state.principal_variation.is_some());
 
-            let child_value = -child.value;
+            let is_fail_low = result.value <= alpha && alpha > loss_score;
+            let is_fail_high = result.value >= beta && beta < win_score;
 
-            if child_value > best_value {
-                best_value = child_value;
-                best_move = Some(*mv);
-                best_child = Some(child);
+            if self.flags.stop_as_soon_as_possible || !(is_fail_low || is_fail_high) {
+                return result;
             }
 
-            alpha = max(alpha, best_value);
+            // The value is only a bound of the actual value until the re-search completes
+            let bound = if is_fail_high { Bound::LOWER } else { Bound::UPPER };
+            let uci_pv = self.principal_variation(&result).into_iter().map(move_into_uci_move).collect();
+            self.uci_tx.info(&Info {
+                principal_variation: Some(uci_pv),
+                time: Some(self.state.elapsed()),
+                score: Some(self.bounded_score(result.value, bound)),
+                depth: Some(depth as u32),
+                multi_pv: (self.options.multi_pv > 1).then_some(1),
+                ..self.generate_info()
+            });
 
-            self.state.bitboard.unmake(*mv);
+            delta *= 4;
 
-            if alpha >= beta {
-                self.state.killer_table.put(remaining_draft, *mv);
+            match center {
+                Some(center) if delta <= ASPIRATION_WINDOW_MAX_DELTA => {
+                    if is_fail_low {
+                        alpha = center - delta;
+                    }
+                    if is_fail_high {
+                        beta = center + delta;
+                    }
+                }
+                _ => {
+                    alpha = loss_score;
+                    beta = win_score;
+                }
+            }
+        }
+    }
+
+    /// The score of `value`, marked as `bound` unless it is a mate score
+    fn bounded_score(&self, value: i32, bound: Bound) -> Score {
+        match self.heuristic.score_from_value(value, &self.state.bitboard) {
+            Score::Centipawn { score } => Score::CentipawnBounded { score, bound },
+            score => score,
+        }
+    }
+
+    /// Search and report the next best root moves at `depth` as additional principal variations, each excluding the
+    /// root moves of the previous ones
+    fn search_multi_pv(&mut self, depth: usize, best_move: Move) {
+        self.state.excluded_root_moves.push(best_move);
+
+        for multi_pv in 2..=self.options.multi_pv {
+            let current_best_move = self.search_negamax(&mut Self::create_buffer(), 0, depth, self.heuristic.loss_score(), self.heuristic.win_score(), false);
+
+            let mv = match current_best_move.mv {
+                Some(mv) if !self.flags.stop_as_soon_as_possible => mv,
+                _ => break,
+            };
+
+            let uci_pv = self.principal_variation(&current_best_move).into_iter().map(move_into_uci_move).collect();
+
+            self.uci_tx.info(&Info {
+                principal_variation: Some(uci_pv),
+                time: Some(self.state.elapsed()),
+                score: Some(self.heuristic.score_from_value(current_best_move.value, &self.state.bitboard)),
+                depth: Some(depth as u32),
+                multi_pv: Some(multi_pv as u32),
+                ..self.generate_info()
+            });
+
+            self.state.excluded_root_moves.push(mv);
+        }
+
+        self.state.excluded_root_moves.clear();
+    }
+
+    /// Find the opponents reply to `mv` if the principal variation is too short to contain a ponder move. Prefers the
+    /// transposition table move and falls back to a one ply search.
+    fn fallback_ponder_move(&mut self, mv: Move) -> Option<Move> {
+        self.state.bitboard.make(mv);
+
+        let tt_move = self.state.transposition_table.get(self.state.bitboard.zobrist_hash()).and_then(|tt_entry| tt_entry.mv);
+        let result = tt_move.or_else(|| self.search_shallow_reply());
+
+        self.state.bitboard.unmake(mv);
+
+        result
+    }
+
+    fn search_shallow_reply(&mut self) -> Option<Move> {
+        let color = self.state.bitboard.turn;
+
+        let mut buffer = Self::create_buffer();
+        let mut next_buffer = Self::create_buffer();
+        self.state.bitboard.generate_pseudo_legal_moves_with_buffer(&mut buffer);
+
+        let mut best_value = self.heuristic.loss_score();
+        let mut best_move = None;
+
+        for mv in buffer {
+            self.state.bitboard.make(mv);
+
+            if self.state.bitboard.is_valid() {
+                next_buffer.clear();
+                self.state.bitboard.generate_pseudo_legal_moves_with_buffer(&mut next_buffer);
+                let legal_moves_remaining = self.state.bitboard.is_any_move_legal(&next_buffer);
+
+                let value = self.evaluate(color, self.state.bitboard.pawn_zobrist_hash(), legal_moves_remaining);
+
+                if best_move.is_none() || value > best_value {
+                    best_value = value;
+                    best_move = Some(mv);
+                }
+            }
+
+            self.state.bitboard.unmake(mv);
+        }
+
+        best_move
+    }
+
+    fn evaluate(&mut self, color: ColorBits, zobrist_pawn_hash: ZobristHash, legal_moves_remaining: bool) -> i32 {
+        let pawn_eval = self.state.pawn_table.get_or_calculate(zobrist_pawn_hash, &self.state.bitboard);
+        calculate_heuristic_factor(color) * self.heuristic.evaluate(&self.state.bitboard, &pawn_eval, legal_moves_remaining)
+    }
+
+    #[inline(always)]
+    fn should_check_flags(&mut self) -> bool {
+        self.state.metrics.last.negamax_nodes.is_multiple_of(self.state.check_flags_interval) && self.state.metrics.last.negamax_nodes > 0
+    }
+
+    /// Update the node interval between flag checks, checking more often the less of the move time budget remains
+    fn update_check_flags_interval(&mut self) {
+        let remaining = self.state.stop_condition.move_time().map(|move_time| move_time.saturating_sub(self.state.elapsed()));
+        self.state.check_flags_interval = calculate_check_flags_interval(remaining);
+    }
+
+    fn filter_search_moves(&mut self, buffer: &mut Vec<Move>) {
+        let search_moves = &self.params.go.search_moves;
+
+        if !search_moves.is_empty() {
+            buffer.retain(|&mv| {
+                search_moves.contains(&move_into_uci_move(mv))
+            });
+        }
+
+        let excluded_root_moves = &self.state.excluded_root_moves;
+        buffer.retain(|mv| !excluded_root_moves.iter().any(|excluded| excluded.bits == mv.bits));
+    }
+
+    #[allow(clippy::unwrap_used)]
+    fn search_negamax(&mut self, buffer: &mut Vec<Move>, ply_depth_from_root: usize, max_ply: usize, alpha_original: i32, beta_original: i32, is_pv: bool) -> ValuedMove {
+        let color = self.state.bitboard.turn;
+        let zobrist_hash = self.state.bitboard.zobrist_hash();
+        let zobrist_pawn_hash = self.state.bitboard.pawn_zobrist_hash();
+
+        if self.state.helper_stop.as_ref().is_some_and(|helper_stop| helper_stop.load(Ordering::Relaxed)) {
+            self.flags.stop_as_soon_as_possible = true;
+            return ValuedMove::leaf(0);
+        }
+
+        // Only the main thread receives messages and reports
+        let check_flags = self.state.helper_stop.is_none() && self.should_check_flags();
+        if check_flags {
+            self.check_messages();
+            self.uci_tx.info(&Info {
+                time: Some(self.state.elapsed()),
+                current_move: self.state.current_root_move.map(|(mv, _)| move_into_uci_move(mv)),
+                current_move_number: self.state.current_root_move.map(|(_, move_number)| move_number),
+                ..self.generate_info()
+            });
+
+            if let Some(move_time) = self.state.stop_condition.move_time() {
+                if self.state.elapsed() > move_time {
+                    self.flags.stop_as_soon_as_possible = true;
+                    return ValuedMove::leaf(0);
+                }
+            }
+
+            self.update_check_flags_interval();
+        }
+
+        // Checked on every node instead of only when checking flags, as the interval is too coarse for small limits
+        if let Nodes(nodes) = self.state.stop_condition {
+            if self.state.metrics.last.total_nodes() >= nodes {
+                self.flags.stop_as_soon_as_possible = true;
+                return ValuedMove::leaf(0);
+            }
+        }
+
+        self.state.metrics.increment_negamax_nodes();
+        self.state.selective_depth = max(self.state.selective_depth, ply_depth_from_root);
+
+        let ply_clock = self.state.bitboard.ply_clock();
+        let halfmove_clock = self.state.bitboard.halfmove_clock;
+        self.state.zobrist_history.set(ply_clock, zobrist_hash);
+
+        if self.state.zobrist_history.is_threefold_repetition(ply_clock, halfmove_clock) {
+            // Scored from the perspective of the side to move, the root player dislikes draws by the contempt factor
+            let contempt_factor_factor = if ply_depth_from_root.is_multiple_of(2) { -1 } else { 1 };
+
+            return ValuedMove::leaf(self.heuristic.draw_score() + contempt_factor_factor * self.options.contempt_factor);
+        }
+
+        let is_root = ply_depth_from_root == 0;
+
+        // The root entry may contain an excluded move, and must not be overwritten by a worse one
+        let is_excluding_root_moves = is_root && !self.state.excluded_root_moves.is_empty();
+        let mut alpha = alpha_original;
+        let mut beta = beta_original;
+
+        let mut tt_move = None;
+        let mut singular_candidate = None;
+
+        let remaining_draft = max_ply - ply_depth_from_root;
+
+        let maybe_tt_entry = if is_excluding_root_moves { None } else { self.state.transposition_table.get(zobrist_hash) };
+
+        if let Some(tt_entry) = maybe_tt_entry {
+            if tt_entry.depth >= remaining_draft {
+                self.state.metrics.increment_transposition_hits();
+
+                let is_cutoff = match tt_entry.node_type {
+                    Lowerbound => max(alpha, tt_entry.value) >= beta,
+                    Upperbound => alpha >= min(beta, tt_entry.value),
+                    Exact => true,
+                };
+
+                // The rest of the principal variation is rebuilt from the table when it is reported
+                if is_cutoff {
+                    return ValuedMove::new(tt_entry.value, tt_entry.mv, None);
+                }
+
+                match tt_entry.node_type {
+                    Lowerbound => alpha = max(alpha, tt_entry.value),
+                    Upperbound => beta = min(beta, tt_entry.value),
+                    Exact => {}
+                }
+            }
+            tt_move = tt_entry.mv;
+
+            if tt_entry.depth + 3 >= remaining_draft && matches!(tt_entry.node_type, Lowerbound | Exact) {
+                singular_candidate = tt_move.map(|mv| (mv, tt_entry.value));
+            }
+        };
+
+        buffer.clear();
+        self.state.bitboard.generate_pseudo_legal_moves_with_buffer(buffer);
+
+        if is_root {
+            self.filter_search_moves(buffer);
+
+            if buffer.is_empty() {
+                return ValuedMove::leaf(0);
+            }
+        }
+
+        let is_max_ply = ply_depth_from_root == max_ply;
+        if is_max_ply {
+            let legal_moves_remaining = self.state.bitboard.is_any_move_legal(buffer);
+
+            let is_evading = self.options.extended_quiescence && self.state.bitboard.is_current_in_check();
+
+            let is_searching_checks = self.options.quiescence_check_depth > 0;
+
+            if legal_moves_remaining && (is_evading || is_searching_checks || Bitboard::is_any_move_non_quiescent(buffer)) {
+                self.state.metrics.increment_started_quiescence_search();
+                return self.search_quiescence(ply_depth_from_root, 0, buffer, alpha, beta, None);
+            }
+
+            let value = self.evaluate(color, zobrist_pawn_hash, legal_moves_remaining);
+            return ValuedMove::leaf(value);
+        }
+
+        let pv_move = if is_pv { self.state.principal_variation.as_ref().unwrap().get(ply_depth_from_root).copied() } else { None };
+        let killer_move = self.state.killer_table.get(remaining_draft);
+        self.move_order.sort(buffer, pv_move, tt_move, killer_move, &self.state.history_table);
+
+        let singular_move = match singular_candidate {
+            Some((mv, tt_value)) if self.should_try_singular_extension(ply_depth_from_root, max_ply, tt_value) => {
+                let singular_beta = tt_value - SINGULAR_EXTENSION_MARGIN;
+
+                if self.is_singular(buffer, mv, ply_depth_from_root, remaining_draft / 2, singular_beta) {
+                    Some(mv)
+                } else if self.flags.stop_as_soon_as_possible {
+                    return ValuedMove::new(0, None, None);
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        };
+
+        let mut best_value = self.heuristic.loss_score();
+        let mut best_child: Option<ValuedMove> = None;
+        let mut best_move: Option<Move> = None;
+        let mut legal_moves_encountered = false;
+        let mut move_number = 0;
+
+        let mut next_buffer = Self::create_buffer();
+
+        for mv in buffer {
+            self.state.bitboard.make(*mv);
+            if !self.state.bitboard.is_valid() {
+                self.state.bitboard.unmake(*mv);
+                continue;
+            }
+
+            let is_first_move = !legal_moves_encountered;
+            legal_moves_encountered = true;
+            move_number += 1;
+
+            if is_root {
+                self.state.current_root_move = Some((*mv, move_number));
+            }
+
+            let is_singular = singular_move.is_some_and(|singular_mv| singular_mv.bits == mv.bits);
+            let child_max_ply = if is_singular { max_ply + 1 } else { max_ply };
+            let is_child_pv = is_pv && pv_move.is_some_and(|pv_mv| pv_mv.bits == mv.bits);
+
+            // Principal variation search: Only search the first move with the full window, then try to prove that the
+            // other moves aren't better with a null window and only search them with the full window if that fails
+            let is_null_window_search = self.options.principal_variation_search && !is_first_move;
+
+            let mut child = if is_null_window_search {
+                self.search_negamax(&mut next_buffer, ply_depth_from_root + 1, child_max_ply, -alpha - 1, -alpha, false)
+            } else {
+                self.search_negamax(&mut next_buffer, ply_depth_from_root + 1, child_max_ply, -beta, -alpha, is_child_pv)
+            };
+
+            // The board is restored even when aborting, the emergency move is generated from it
+            if self.flags.stop_as_soon_as_possible {
+                self.state.bitboard.unmake(*mv);
+                return ValuedMove::new(0, None, None);
+            }
+
+            if is_null_window_search && -child.value > alpha && -child.value < beta {
+                child = self.search_negamax(&mut next_buffer, ply_depth_from_root + 1, child_max_ply, -beta, -alpha, is_child_pv);
+
+                if self.flags.stop_as_soon_as_possible {
+                    self.state.bitboard.unmake(*mv);
+                    return ValuedMove::new(0, None, None);
+                }
+            }
+
+            let child_value = -child.value;
+
+            if is_root && self.options.show_refutations && self.state.helper_stop.is_none() {
+                self.report_refutation(*mv, &child);
+            }
+
+            if child_value > best_value {
+                best_value = child_value;
+                best_move = Some(*mv);
+                best_child = Some(child);
+            }
+
+            alpha = max(alpha, best_value);
+
+            self.state.bitboard.unmake(*mv);
+
+            if alpha >= beta {
+                self.state.killer_table.put(remaining_draft, *mv);
+                if mv.is_quiet() {
+                    self.state.history_table.put(remaining_draft, *mv);
+                }
                 break;
             }
         }
 
-        if !legal_moves_encountered {
-            let value = self.evaluate(color, zobrist_pawn_hash, false);
-            return ValuedMove::leaf(value);
-        }
+        if !legal_moves_encountered {
+            let value = self.evaluate(color, zobrist_pawn_hash, false);
+            return ValuedMove::leaf(value);
+        }
+
+        let result = ValuedMove::new(best_value, best_move, best_child);
+
+        if !self.heuristic.is_checkmate(best_value) && !is_excluding_root_moves {
+            let node_type = if best_value <= alpha_original {
+                Upperbound
+            } else if best_value >= beta {
+                Lowerbound
+            } else {
+                Exact
+            };
+
+            self.state.transposition_table.put(zobrist_hash, TtEntry::new(best_move, zobrist_hash, remaining_draft, best_value, node_type));
+        }
+
+        // TODO transposition table
+
+        result
+    }
+
+    /// Report the line the root move `mv` was searched with, its refutation if it didn't become the best move
+    fn report_refutation(&self, mv: Move, child: &ValuedMove) {
+        let refutation = iter::once(mv).chain(child.calculate_principal_variation()).map(move_into_uci_move).collect();
+
+        self.uci_tx.info(&Info { refutation: Some(refutation), ..Info::EMPTY });
+    }
+
+    fn should_try_singular_extension(&self, ply_depth_from_root: usize, max_ply: usize, tt_value: i32) -> bool {
+        self.options.singular_extensions
+            && ply_depth_from_root > 0
+            && max_ply - ply_depth_from_root >= SINGULAR_EXTENSION_MIN_DRAFT
+            && max_ply < self.state.iteration_depth + SINGULAR_EXTENSION_LIMIT
+            && !self.heuristic.is_checkmate(tt_value)
+    }
+
+    /// Verify with a search reduced to `reduced_draft` that all moves except `singular_mv` fail low against `singular_beta`
+    fn is_singular(&mut self, buffer: &[Move], singular_mv: Move, ply_depth_from_root: usize, reduced_draft: usize, singular_beta: i32) -> bool {
+        let mut next_buffer = Self::create_buffer();
+
+        for &mv in buffer {
+            if mv.bits == singular_mv.bits {
+                continue;
+            }
+
+            self.state.bitboard.make(mv);
+            if !self.state.bitboard.is_valid() {
+                self.state.bitboard.unmake(mv);
+                continue;
+            }
+
+            let child = self.search_negamax(
+                &mut next_buffer,
+                ply_depth_from_root + 1,
+                ply_depth_from_root + reduced_draft,
+                -singular_beta,
+                -(singular_beta - 1),
+                false,
+            );
+
+            self.state.bitboard.unmake(mv);
+
+            if self.flags.stop_as_soon_as_possible || -child.value >= singular_beta {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    /// On the first call (`depth == 0`), `buffer` is expected to contain all pseudo legal moves of the current position
+    /// Search captures until the position is quiet, `depth` is the number of plies since `ply_depth_from_root`, the start of the quiescence search
+    fn search_quiescence(&mut self, ply_depth_from_root: usize, depth: u32, buffer: &mut Vec<Move>, alpha_original: i32, beta_original: i32, recapture_square: Option<SquareShiftBits>) -> ValuedMove {
+        let color = self.state.bitboard.turn;
+        let zobrist_pawn_hash = self.state.bitboard.pawn_zobrist_hash();
+        let is_evading = self.options.extended_quiescence && self.state.bitboard.is_current_in_check();
+
+        let mut alpha = alpha_original;
+        let mut delta = None;
+
+        if !is_evading {
+            let standing_pat = self.evaluate(color, zobrist_pawn_hash, true);
+
+            if standing_pat >= beta_original {
+                self.state.metrics.register_quiescence_termination(depth as usize);
+                return ValuedMove::leaf(beta_original);
+            }
+
+            alpha = max(alpha, standing_pat);
+
+            if self.options.delta_pruning && self.state.bitboard.non_pawn_material() > DELTA_PRUNING_MIN_NON_PAWN_MATERIAL {
+                delta = Some(alpha - standing_pat - DELTA_PRUNING_MARGIN);
+            }
+        }
+
+        let mut best_move = None;
+        let mut best_child = None;
+
+        let mut next_buffer = Vec::new();
+
+        let is_searching_checks = !is_evading && depth < self.options.quiescence_check_depth;
+
+        if depth > 0 {
+            buffer.clear();
+            if is_evading || is_searching_checks {
+                self.state.bitboard.generate_pseudo_legal_moves_with_buffer(buffer);
+            } else {
+                self.state.bitboard.generate_pseudo_legal_non_quiescent_moves_with_buffer(buffer);
+            }
+        }
+
+        if is_searching_checks {
+            let bitboard = &self.state.bitboard;
+            buffer.retain(|&mv| !bitboard.is_quiet(mv));
+        } else if depth == 0 && !is_evading {
+            buffer.retain(|mv| !mv.is_quiet());
+        }
+
+        if self.options.extended_quiescence && !is_evading {
+            let bitboard = &self.state.bitboard;
+            buffer.retain(|mv| recapture_square == Some(mv.get_target_square()) || mv.is_promotion() || bitboard.capture_classification(*mv) != CaptureClass::Losing);
+        }
+
+        // Captures and promotions gaining less than the delta can't raise alpha, quiet checks are kept
+        if let Some(delta) = delta {
+            buffer.retain(|&mv| mv.is_quiet() || Self::material_gain(mv) >= delta);
+        }
+
+        self.move_order.sort(buffer, None, None, None, &self.state.history_table);
+
+        let mut legal_moves_encountered = false;
+
+        for mv in buffer {
+            self.state.bitboard.make(*mv);
+
+            if !self.state.bitboard.is_valid() {
+                self.state.bitboard.unmake(*mv);
+                continue;
+            }
+
+            legal_moves_encountered = true;
+
+            self.state.metrics.increment_quiescence_nodes();
+            if is_searching_checks && mv.is_quiet() {
+                self.state.metrics.increment_quiescence_check_extensions();
+            }
+            self.state.selective_depth = max(self.state.selective_depth, ply_depth_from_root + depth as usize + 1);
+
+            let child = self.search_quiescence(ply_depth_from_root, depth + 1, &mut next_buffer, -beta_original, -alpha, Some(mv.get_target_square()));
+            let value = -child.value;
+
+            self.state.bitboard.unmake(*mv);
+
+            if value >= beta_original {
+                self.state.metrics.register_quiescence_termination(depth as usize);
+                return ValuedMove::parent(beta_original, *mv, child);
+            }
+
+            if value > alpha {
+                alpha = value;
+                best_move = Some(*mv);
+                best_child = Some(child);
+            }
+        }
+
+        self.state.metrics.register_quiescence_termination(depth as usize);
+
+        if is_evading && !legal_moves_encountered {
+            return ValuedMove::leaf(max(alpha_original, self.evaluate(color, zobrist_pawn_hash, false)));
+        }
+
+        ValuedMove::new(alpha, best_move, best_child)
+    }
+
+    /// Value of the piece captured by `mv`, plus the value gained by promoting
+    const fn material_gain(mv: Move) -> i32 {
+        let captured = Bitboard::PIECE_VALUES[mv.get_piece_attacked() as usize];
+
+        if mv.is_promotion() {
+            captured + Bitboard::PIECE_VALUES[mv.get_promotion_piece() as usize] - Bitboard::PIECE_VALUES[PAWN as usize]
+        } else {
+            captured
+        }
+    }
+}
+
+/// Non-search related functionality
+impl<T: UciTx + Send + Sync, H: Heuristic + Clone + Send, M: MoveOrder + Clone + Send> Search<T, H, M> {
+    fn generate_info(&self) -> Info {
+        Info {
+            selective_depth: Some(self.state.selective_depth as u32),
+            nodes: Some(self.state.metrics.last.total_nodes()),
+            hash_full: Some((self.state.transposition_table.load_factor() * 1000.0) as u32),
+            nps: Some(self.state.metrics.last.nps_with_duration(&self.state.elapsed())),
+            table_hits: self.is_tablebase_probing_enabled().then_some(self.state.metrics.last.tablebase_wdl_hits as u32),
+            shredder_table_hits: self.is_tablebase_probing_enabled().then_some(self.state.metrics.last.tablebase_dtz_hits as u32),
+            ..Info::EMPTY
+        }
+    }
+
+    fn generate_debug_string_if_enabled(&mut self) -> Option<String> {
+        if self.options.debug { Some(self.generate_debug_string()) } else { None }
+    }
+
+    fn generate_debug_string(&mut self) -> String {
+        format!("tphitrate {} nrate {} qrate {} avgqdepth {} qstartedrate {} qcheckrate {} qtphitrate {} tbprobing {} sanpv {}",
+                self.state.metrics.last.table_hit_rate(),
+                self.state.metrics.last.negamax_node_rate(),
+                self.state.metrics.last.quiescence_node_rate(),
+                self.state.metrics.last.average_quiescence_termination_ply(),
+                self.state.metrics.last.quiescence_started_rate(),
+                self.state.metrics.last.quiescence_check_extension_rate(),
+                self.state.metrics.last.quiescence_table_hit_rate(),
+                self.is_tablebase_probing_enabled(),
+                self.generate_san_principal_variation(),
+        )
+    }
+
+    /// Render the current principal variation in SAN by stepping through it on the search board
+    fn generate_san_principal_variation(&mut self) -> String {
+        let uci_pv = self.state.principal_variation.iter().flatten().map(|&mv| self.state.bitboard.move_to_uci_string(mv)).collect::<Vec<_>>();
+
+        self.state.bitboard.uci_line_to_pgn(&uci_pv).map_or_else(|_| "-".to_string(), |san_pv| san_pv.join(" "))
+    }
+}
+
+/// Node interval between flag checks for the `remaining` move time, all intervals divide each other so no check is skipped when switching
+const fn calculate_check_flags_interval(remaining: Option<Duration>) -> u64 {
+    match remaining {
+        Some(remaining) if remaining.as_millis() < 200 => 2_000,
+        Some(remaining) if remaining.as_millis() < 1_000 => 20_000,
+        _ => 100_000,
+    }
+}
+
+/// Win, draw and loss probabilities in permille for `score`, using a logistic model with a scale of `k` centipawns.
+/// Scores are more likely draws the further the game `phase` progressed.
+fn calculate_wdl(score: Score, phase: u8, k: i32) -> (u32, u32, u32) {
+    let score = match score {
+        Score::Mate { mate_in } => return if mate_in > 0 { (1000, 0, 0) } else { (0, 0, 1000) },
+        Score::Centipawn { score } | Score::CentipawnBounded { score, .. } => f64::from(score),
+    };
+
+    let draw_margin = WDL_DRAW_MARGIN * (1.0 + f64::from(phase) / 24.0);
+    let probability = |score: f64| 1.0 / (1.0 + ((draw_margin - score) / f64::from(k.max(1))).exp());
+
+    let win = (probability(score) * 1000.0).round() as u32;
+    let loss = (probability(-score) * 1000.0).round() as u32;
+
+    (win, 1000_u32.saturating_sub(win + loss), loss)
+}
+
+#[inline(always)]
+const fn calculate_heuristic_factor(color: ColorBits) -> i32 {
+    1 + (color as i32) * -2
+}
+
+#[derive(Clone)]
+pub struct ValuedMove {
+    value: i32,
+    mv: Option<Move>,
+    pv_child: Box<Option<ValuedMove>>,
+}
+
+impl ValuedMove {
+    pub fn new(value: i32, mv: Option<Move>, pv_child: Option<Self>) -> Self {
+        Self { value, mv, pv_child: Box::new(pv_child) }
+    }
+
+    pub fn parent(value: i32, mv: Move, pv_child: Self) -> Self {
+        Self::new(value, Some(mv), Some(pv_child))
+    }
+
+    pub fn leaf(value: i32) -> Self {
+        Self::new(value, None, None)
+    }
+
+    fn calculate_principal_variation(&self) -> Vec<Move> {
+        let mut result = Vec::new();
+
+        let option = Some(self);
+        let mut maybe_current = option;
+
+        while let Some(current) = maybe_current {
+            if let Some(mv) = current.mv {
+                result.push(mv);
+            }
+
+
+            maybe_current = (*current.pv_child).as_ref();
+        }
+
+        result
+    }
+}
+
+#[derive(Debug)]
+#[allow(clippy::enum_variant_names)]
+pub enum SearchMessage {
+    UciUciNewGame,
+    UciDebug(bool),
+    UciSetOption(String, String),
+    UciPositionFrom(Fen, Vec<UciMove>),
+    UciGo(Go),
+    UciStop,
+    UciPonderHit,
+    UciQuit,
+}
+
+/// UCI options
+#[derive(Clone)]
+pub struct EngineOptions {
+    pub debug: bool,
+    pub try_previous_pv: bool,
+    /// Centipawns a draw by repetition is worse than an equal position for the engine. Positive values avoid draws,
+    /// negative values seek them.
+    pub contempt_factor: i32,
+    /// Centipawns the side to move is awarded in the static evaluation
+    pub tempo: i32,
+    pub hash_size_mb: usize,
+    pub threads: usize,
+    pub multi_pv: usize,
+    /// The GUI may send `go ponder`, informational only
+    pub ponder: bool,
+    /// Search for a ponder move if the principal variation only contains the best move
+    pub ponder_fallback: bool,
+    /// Extend the transposition table move by a ply if it is much better than all alternatives
+    pub singular_extensions: bool,
+    /// Search check evasions in quiescence and skip captures losing material by SEE, unless they recapture
+    pub extended_quiescence: bool,
+    /// Skip captures in quiescence that can't raise alpha even if they win material, except in the late endgame
+    pub delta_pruning: bool,
+    /// Quiescence plies from the start of the quiescence search in which quiet checking moves are searched as well
+    pub quiescence_check_depth: u32,
+    /// Search all but the first move with a null window, and only re-search with the full window if it fails high
+    pub principal_variation_search: bool,
+    /// Search each iteration in a narrow window around the value of the previous one first
+    pub aspiration_windows: bool,
+    /// Report the line each root move was searched with as `info refutation`, for analysis
+    pub show_refutations: bool,
+    /// Report the win, draw and loss probabilities of the final score as `info string wdl`
+    pub show_wdl: bool,
+    /// Centipawns of the logistic model converting scores to win, draw and loss probabilities
+    pub wdl_scale: i32,
+    /// Directories of the loaded Syzygy tablebases
+    pub syzygy_path: Option<String>,
+    /// Maximum number of pieces on the board to probe the tablebases for
+    pub syzygy_probe_limit: usize,
+    /// Play moves from the loaded book instead of searching while in book
+    pub own_book: bool,
+    /// Polyglot book file of the loaded book
+    pub book_file: Option<String>,
+}
+
+impl Default for EngineOptions {
+    fn default() -> Self {
+        Self {
+            debug: false,
+            try_previous_pv: true,
+            contempt_factor: 20,
+            tempo: 10,
+            hash_size_mb: 16,
+            threads: 1,
+            multi_pv: 1,
+            ponder: false,
+            ponder_fallback: true,
+            singular_extensions: true,
+            extended_quiescence: true,
+            delta_pruning: true,
+            quiescence_check_depth: 1,
+            principal_variation_search: true,
+            aspiration_windows: true,
+            show_refutations: false,
+            show_wdl: false,
+            wdl_scale: 100,
+            syzygy_path: None,
+            syzygy_probe_limit: 7,
+            own_book: false,
+            book_file: None,
+        }
+    }
+}
+
+/// State during search
+struct SearchState {
+    bitboard: Bitboard,
+    transposition_table: Arc<SharedTranspositionTable>,
+    killer_table: KillerTable,
+    history_table: HistoryTable,
+    pawn_table: PawnTable,
+    principal_variation: Option<Vec<Move>>,
+    /// Root moves of the principal variations already found in the current iteration with `MultiPV`
+    excluded_root_moves: Vec<Move>,
+    zobrist_history: ZobristHistory,
+    started_at: SystemTime,
+    is_running: bool,
+    metrics: MetricsService,
+    /// Number of negamax nodes between flag checks
+    check_flags_interval: u64,
+    stop_condition: StopCondition,
+    tablebases: Tablebases,
+    book: PolyglotBook,
+    /// Maximum ply of the current iterative deepening iteration, before extensions
+    iteration_depth: usize,
+    /// Deepest ply reached in the current iteration, including extensions and quiescence
+    selective_depth: usize,
+    /// Root move currently searched and its 1-based number, reported as `currmove` and `currmovenumber`
+    current_root_move: Option<(Move, u32)>,
+    /// Set by the main thread to stop a helper thread, `None` on the main thread
+    helper_stop: Option<Arc<AtomicBool>>,
+}
+
+impl SearchState {
+    fn new(options: &EngineOptions) -> Self {
+        Self {
+            bitboard: Bitboard::default(),
+            transposition_table: Arc::new(SharedTranspositionTable::with_size_mb(options.hash_size_mb)),
+            killer_table: KillerTable::default(),
+            history_table: HistoryTable::default(),
+            pawn_table: PawnTable::default(),
+            principal_variation: None,
+            excluded_root_moves: Vec::new(),
+            zobrist_history: ZobristHistory::default(),
+            started_at: SystemTime::UNIX_EPOCH,
+            is_running: false,
+            metrics: MetricsService::default(),
+            check_flags_interval: calculate_check_flags_interval(None),
+            stop_condition: Infinite,
+            tablebases: Tablebases::default(),
+            book: PolyglotBook::default(),
+            iteration_depth: 0,
+            selective_depth: 0,
+            current_root_move: None,
+            helper_stop: None,
+        }
+    }
+
+    /// The state of a helper thread searching the current position, sharing only the transposition table
+    fn for_helper(&self, helper_stop: Arc<AtomicBool>) -> Self {
+        Self {
+            bitboard: self.bitboard,
+            transposition_table: Arc::clone(&self.transposition_table),
+            killer_table: KillerTable::default(),
+            history_table: HistoryTable::default(),
+            pawn_table: PawnTable::default(),
+            principal_variation: None,
+            excluded_root_moves: Vec::new(),
+            zobrist_history: self.zobrist_history.clone(),
+            started_at: self.started_at,
+            is_running: true,
+            metrics: MetricsService::default(),
+            check_flags_interval: self.check_flags_interval,
+            stop_condition: self.stop_condition,
+            tablebases: Tablebases::default(),
+            book: PolyglotBook::default(),
+            iteration_depth: 0,
+            selective_depth: 0,
+            current_root_move: None,
+            helper_stop: Some(helper_stop),
+        }
+    }
+
+    fn ponder_move(&self) -> Option<Move> {
+        self.principal_variation.as_ref().and_then(|pv| pv.get(1)).copied()
+    }
+
+    fn elapsed(&self) -> Duration {
+        self.started_at.elapsed().unwrap_or(Duration::ZERO)
+    }
+}
+
+/// The condition ending a search, besides `stop` and `quit`
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum StopCondition {
+    Infinite,
+    Depth(usize),
+    Nodes(u64),
+    /// Search for a mate in the given number of moves
+    Mate(u64),
+    MoveTime(Duration),
+}
+
+impl StopCondition {
+    const fn max_depth(&self) -> usize {
+        match self {
+            Depth(depth) => *depth,
+            Mate(moves) => (*moves as usize * 2).saturating_sub(1),
+            Infinite | Nodes(_) | MoveTime(_) => 999_999,
+        }
+    }
+
+    const fn move_time(&self) -> Option<Duration> {
+        if let MoveTime(move_time) = self { Some(*move_time) } else { None }
+    }
+
+    /// If `score` ends the search before the maximum depth, i.e. is a mate for the engine in at most the searched moves
+    const fn is_satisfied_by(&self, score: Option<Score>) -> bool {
+        match (self, score) {
+            (Mate(moves), Some(Score::Mate { mate_in })) => mate_in > 0 && mate_in as u64 <= *moves,
+            _ => false,
+        }
+    }
+}
+
+/// Control the search "from the outside"
+#[derive(Default)]
+#[allow(clippy::struct_excessive_bools)]
+struct SearchFlags {
+    reset_for_next_search: bool,
+    stop_as_soon_as_possible: bool,
+    quit_as_soon_as_possible: bool,
+    ponder_hit: bool,
+}
+
+/// Input for the search
+#[derive(Default, Clone)]
+struct SearchParams {
+    go: Go,
+    fen: Fen,
+    moves: Vec<Move>,
+}
+
+enum PvContinuationError {
+    PvTooShort(usize),
+    NoPreviousPv { last_ponder_move: Move, last_played_move: Move },
+    DivergedFromPv { last_ponder_move: Move, last_played_move: Move },
+    NoLastPlayedMove { last_ponder_move: Move },
+    NoPonderMove,
+}
+
+#[cfg(test)]
+mod test {
+    use std::env::temp_dir;
+    use std::fs::{create_dir_all, File, write};
+    use std::ops::{Div, Mul};
+    use std::str::FromStr;
+    use std::sync::Arc;
+    use std::sync::mpsc::channel;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    use inkayaku_board::Bitboard;
+    use inkayaku_board::constants::{BLACK, WHITE};
+    use inkayaku_core::fen::Fen;
+    use inkayaku_uci::{Bound, Go, Info, Score, UciMove, UciTxCommand};
+    use inkayaku_uci::command::CommandUciTx;
+
+    use crate::engine::book::polyglot_hash;
+    use crate::engine::heuristic::Heuristic;
+    use crate::engine::heuristic::simple::SimpleHeuristic;
+    use crate::engine::move_order::MvvLvaMoveOrder;
+    use crate::engine::search::{calculate_check_flags_interval, calculate_heuristic_factor, calculate_wdl, EngineOptions, Search, ValuedMove};
+    use crate::engine::search::StopCondition::{Depth, Infinite, Mate, MoveTime, Nodes};
+    use crate::engine::search::SearchMessage::{UciGo, UciPositionFrom, UciQuit, UciSetOption, UciUciNewGame};
+    use crate::engine::table::transposition::ArrayTranspositionTable;
+
+    #[test]
+    fn test_heuristic_factor() {
+        assert_eq!(calculate_heuristic_factor(BLACK), -1);
+        assert_eq!(calculate_heuristic_factor(WHITE), 1);
+    }
+
+    #[test]
+    fn test_check_flags_interval_shrinks_near_time_pressure() {
+        assert_eq!(calculate_check_flags_interval(None), 100_000);
+        assert_eq!(calculate_check_flags_interval(Some(Duration::from_secs(5))), 100_000);
+        assert_eq!(calculate_check_flags_interval(Some(Duration::from_millis(500))), 20_000);
+        assert_eq!(calculate_check_flags_interval(Some(Duration::from_millis(150))), 2_000);
+        assert_eq!(calculate_check_flags_interval(Some(Duration::ZERO)), 2_000);
+    }
+
+    #[test]
+    fn test_go_perft() {
+        let commands = run_go(Go { perft: Some(3), ..Go::default() });
+
+        let [UciTxCommand::Perft { divide, nodes }] = commands.as_slice() else { panic!("{:?}", commands) };
+
+        assert_eq!(*nodes, 8902);
+        assert_eq!(divide.len(), 20);
+        assert_eq!(divide.first(), Some(&(UciMove::from_str("a2a3").unwrap(), 380)));
+        assert_eq!(divide.last(), Some(&(UciMove::from_str("h2h4").unwrap(), 420)));
+    }
+
+    #[test]
+    fn test_go_depth_0_evaluates_statically() {
+        let commands = run_go(Go { depth: Some(0), ..Go::default() });
+
+        assert_eq!(last_score(&commands), Some(Score::Centipawn { score: EngineOptions::default().tempo }));
+        assert!(commands.iter().any(|command| matches!(command, UciTxCommand::BestMove { best_move: None, ponder_move: None })));
+
+        // White is up a queen with black to move
+        let (commands, nodes) = search_go("rnb1kbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 1", EngineOptions::default(), Go { depth: Some(0), ..Go::default() });
+
+        assert!(matches!(last_score(&commands), Some(Score::Centipawn { score }) if score > 800));
+        assert_eq!(nodes, 0);
+    }
+
+    #[test]
+    fn test_move_time_1_plays_legal_move() {
+        let (commands, _) = search_go("r1bq1rk1/pp2bppp/2n1pn2/3p4/2PP4/2N1PN2/PP2BPPP/R2QKB1R w KQ - 0 9", EngineOptions::default(), Go { move_time: Some(Duration::from_millis(1)), ..Go::default() });
+
+        let best_move = best_move(&commands).unwrap();
+        let mut bitboard = Bitboard::from_fen_string_unchecked("r1bq1rk1/pp2bppp/2n1pn2/3p4/2PP4/2N1PN2/PP2BPPP/R2QKB1R w KQ - 0 9");
+        assert!(bitboard.find_uci(&best_move.to_string()).is_ok(), "{}", best_move);
+    }
+
+    #[test]
+    fn test_emergency_move_if_first_iteration_is_aborted() {
+        let (uci_tx, _uci_rx) = channel();
+        let (_search_tx, search_rx) = channel();
+        let mut search = Search::new(Arc::new(CommandUciTx::new(uci_tx)), search_rx, SimpleHeuristic::default(), MvvLvaMoveOrder, EngineOptions::default());
+
+        search.set_position_from(Fen::default(), vec![]);
+        search.params.go = Go { depth: Some(5), search_moves: vec![UciMove::from_str("g1f3").unwrap()], ..Go::default() };
+        search.reset_for_go();
+        search.flags.stop_as_soon_as_possible = true;
+
+        assert_eq!(search.best_move(), (Some(UciMove::from_str("g1f3").unwrap()), None));
+
+        // Mated positions still have no best move
+        search.set_position_from(Fen::from_str("r1bqkb1r/pppp1Qpp/2n2n2/4p3/2B1P3/8/PPPP1PPP/RNB1K1NR b KQkq - 0 4").unwrap(), vec![]);
+        search.params.go = Go { depth: Some(5), ..Go::default() };
+        search.reset_for_go();
+        search.flags.stop_as_soon_as_possible = true;
+
+        assert_eq!(search.best_move(), (None, None));
+    }
+
+    #[test]
+    fn test_stops_within_move_time() {
+        let move_time = Duration::from_millis(150);
+        let tolerance = Duration::from_millis(100);
+
+        let (uci_tx, uci_rx) = channel();
+        let (search_tx, search_rx) = channel();
+        let mut search = Search::new(Arc::new(CommandUciTx::new(uci_tx)), search_rx, SimpleHeuristic::default(), MvvLvaMoveOrder, EngineOptions::default());
+
+        let fen = Fen::from_str("r1bq1rk1/pp2bppp/2n1pn2/3p4/2PP4/2N1PN2/PP2BPPP/R2QKB1R w KQ - 0 9").unwrap();
+        search_tx.send(UciPositionFrom(fen, vec![])).unwrap();
+        search_tx.send(UciGo(Go { move_time: Some(move_time), ..Go::default() })).unwrap();
+        search_tx.send(UciQuit).unwrap();
+
+        search.idle();
+        // Measured from the start of the search, creating the search and clearing its tables don't count against the budget
+        let elapsed = search.state.elapsed();
+
+        assert!(uci_rx.try_iter().any(|command| matches!(command, UciTxCommand::BestMove { best_move: Some(_), .. })));
+        assert!(elapsed < move_time + tolerance, "search took {:?} with a move time of {:?}", elapsed, move_time);
+    }
+
+    #[test]
+    fn test_stop_condition_precedence() {
+        let (uci_tx, _uci_rx) = channel();
+        let (_search_tx, search_rx) = channel();
+        let mut search = Search::new(Arc::new(CommandUciTx::new(uci_tx)), search_rx, SimpleHeuristic::default(), MvvLvaMoveOrder, EngineOptions::default());
+
+        let clocks = || Go { white_time: Some(Duration::from_millis(1000)), black_time: Some(Duration::from_millis(1000)), ..Go::default() };
+
+        for (go, expected) in [
+            (Go { depth: Some(5), ..Go::default() }, Depth(5)),
+            (Go { nodes: Some(1000), ..Go::default() }, Nodes(1000)),
+            (Go { mate: Some(2), ..Go::default() }, Mate(2)),
+            (Go { move_time: Some(Duration::from_millis(500)), ..Go::default() }, MoveTime(Duration::from_millis(500))),
+            (clocks(), MoveTime(Duration::from_millis(1000).div(60).mul(2))),
+            (Go::default(), Infinite),
+            (Go { depth: Some(5), ..clocks() }, Depth(5)),
+            (Go { nodes: Some(1000), move_time: Some(Duration::from_millis(500)), ..clocks() }, Nodes(1000)),
+            (Go { mate: Some(2), ..clocks() }, Mate(2)),
+            (Go { infinite: true, depth: Some(5), ..clocks() }, Infinite),
+        ] {
+            search.params.go = go;
+            assert_eq!(search.stop_condition(), expected, "{:?}", search.params.go);
+        }
+    }
+
+    #[test]
+    fn test_moves_to_go_time_budget() {
+        let (uci_tx, _uci_rx) = channel();
+        let (_search_tx, search_rx) = channel();
+        let mut search = Search::new(Arc::new(CommandUciTx::new(uci_tx)), search_rx, SimpleHeuristic::default(), MvvLvaMoveOrder, EngineOptions::default());
+
+        let clocks = |time: u64, increment: u64, moves_to_go: u64| Go {
+            white_time: Some(Duration::from_millis(time)),
+            black_time: Some(Duration::from_millis(time)),
+            white_increment: Some(Duration::from_millis(increment)),
+            black_increment: Some(Duration::from_millis(increment)),
+            moves_to_go: Some(moves_to_go),
+            ..Go::default()
+        };
+
+        search.params.go = Go { white_increment: None, black_increment: None, ..clocks(60_000, 0, 20) };
+        let budget = search.stop_condition().move_time().unwrap();
+        assert!((Duration::from_millis(2500)..=Duration::from_millis(3000)).contains(&budget), "{:?}", budget);
+
+        search.params.go = clocks(60_000, 1000, 20);
+        let budget_with_increment = search.stop_condition().move_time().unwrap();
+        assert!(budget_with_increment > budget && budget_with_increment < budget + Duration::from_millis(1000), "{:?}", budget_with_increment);
+
+        // Never more than the time remaining on the last move before the time control
+        search.params.go = clocks(1000, 5000, 1);
+        assert_eq!(search.stop_condition(), MoveTime(Duration::from_millis(950)));
+
+        search.params.go = Go { move_time: Some(Duration::from_millis(500)), ..clocks(60_000, 0, 20) };
+        assert_eq!(search.stop_condition(), MoveTime(Duration::from_millis(500)));
+
+        // Falls back to the time remaining without a valid number of moves to go
+        search.params.go = Go { white_increment: None, black_increment: None, ..clocks(1000, 0, 0) };
+        assert_eq!(search.stop_condition(), MoveTime(Duration::from_millis(1000).div(60).mul(2)));
+    }
+
+    #[test]
+    fn test_go_depth_searches_to_depth() {
+        let commands = run_go(Go { depth: Some(5), ..Go::default() });
+
+        let last_depth = commands.iter().rev().find_map(|command| if let UciTxCommand::Info { info } = command { info.depth } else { None });
+
+        assert_eq!(last_depth, Some(5));
+        assert!(commands.iter().any(|command| matches!(command, UciTxCommand::BestMove { best_move: Some(_), .. })));
+    }
+
+    #[test]
+    fn test_go_nodes_stops_at_node_limit() {
+        let (uci_tx, uci_rx) = channel();
+        let (search_tx, search_rx) = channel();
+        let mut search = Search::new(Arc::new(CommandUciTx::new(uci_tx)), search_rx, SimpleHeuristic::default(), MvvLvaMoveOrder, EngineOptions::default());
+
+        search_tx.send(UciPositionFrom(Fen::default(), vec![])).unwrap();
+        search_tx.send(UciGo(Go { nodes: Some(1000), ..Go::default() })).unwrap();
+        search_tx.send(UciQuit).unwrap();
+        search.idle();
+
+        assert!(search.state.metrics.last.total_nodes() >= 1000);
+        assert!(uci_rx.try_iter().any(|command| matches!(command, UciTxCommand::BestMove { best_move: Some(_), .. })));
+    }
+
+    #[test]
+    fn test_go_nodes_counts_quiescence_nodes() {
+        let (commands, nodes) = search_go("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", EngineOptions::default(), Go { nodes: Some(50_000), ..Go::default() });
+
+        // Only the quiescence search started by the last negamax node may exceed the limit
+        assert!((50_000..51_000).contains(&nodes), "searched {} nodes", nodes);
+        assert!(best_move(&commands).is_some());
+    }
+
+    #[test]
+    fn test_go_clocks_stop_within_thinking_time() {
+        let started_at = Instant::now();
+        let commands = run_go(Go { white_time: Some(Duration::from_millis(1000)), black_time: Some(Duration::from_millis(1000)), ..Go::default() });
+        let elapsed = started_at.elapsed();
+
+        assert!(commands.iter().any(|command| matches!(command, UciTxCommand::BestMove { best_move: Some(_), .. })));
+        assert!(elapsed < Duration::from_millis(200), "search took {:?}", elapsed);
+    }
+
+    fn run_go(go: Go) -> Vec<UciTxCommand> {
+        let (uci_tx, uci_rx) = channel();
+        let (search_tx, search_rx) = channel();
+        let mut search = Search::new(Arc::new(CommandUciTx::new(uci_tx)), search_rx, SimpleHeuristic::default(), MvvLvaMoveOrder, EngineOptions::default());
+
+        search_tx.send(UciPositionFrom(Fen::default(), vec![])).unwrap();
+        search_tx.send(UciGo(go)).unwrap();
+        search_tx.send(UciQuit).unwrap();
+        search.idle();
+
+        uci_rx.try_iter().collect()
+    }
+
+    #[test]
+    fn test_syzygy_path() {
+        let directory = temp_dir().join("inkayaku_search_test_syzygy_path");
+        create_dir_all(&directory).unwrap();
+        File::create(directory.join("KQvK.rtbw")).unwrap();
+
+        let (uci_tx, uci_rx) = channel();
+        let (search_tx, search_rx) = channel();
+        let mut search = Search::new(Arc::new(CommandUciTx::new(uci_tx)), search_rx, SimpleHeuristic::default(), MvvLvaMoveOrder, EngineOptions::default());
+
+        search_tx.send(UciSetOption("SyzygyPath".to_string(), directory.to_str().unwrap().to_string())).unwrap();
+        search_tx.send(UciQuit).unwrap();
+        search.idle();
+
+        assert!(search.is_tablebase_probing_enabled());
+        assert_eq!(search.state.tablebases.len(), 1);
+        assert_eq!(search.options.syzygy_path.as_deref(), directory.to_str());
+
+        search.set_option("SyzygyProbeLimit", "0");
+        assert!(!search.is_tablebase_probing_enabled());
+
+        assert!(uci_rx.try_iter().all(|command| !matches!(command, UciTxCommand::Info { info } if info.string.as_deref().is_some_and(|string| string.starts_with("warning")))));
+    }
+
+    #[test]
+    fn test_tablebase_hits_reported_when_probing() {
+        let directory = temp_dir().join("inkayaku_search_test_tablebase_hits");
+        create_dir_all(&directory).unwrap();
+        File::create(directory.join("KQvK.rtbw")).unwrap();
+
+        let (uci_tx, _uci_rx) = channel();
+        let (_search_tx, search_rx) = channel();
+        let mut search = Search::new(Arc::new(CommandUciTx::new(uci_tx)), search_rx, SimpleHeuristic::default(), MvvLvaMoveOrder, EngineOptions::default());
 
-        let result = ValuedMove::new(best_value, best_move, best_child);
+        assert_eq!(search.generate_info().table_hits, None);
+        assert_eq!(search.generate_info().shredder_table_hits, None);
 
-        if !self.heuristic.is_checkmate(best_value) {
-            let node_type = if best_value <= alpha_original {
-                Upperbound
-            } else if best_value >= beta {
-                Lowerbound
-            } else {
-                Exact
-            };
+        search.set_option("SyzygyPath", directory.to_str().unwrap());
+        search.state.metrics.last.tablebase_wdl_hits = 3;
+        search.state.metrics.last.tablebase_dtz_hits = 1;
 
-            self.state.transposition_table.put(zobrist_hash, TtEntry::new(result.clone(), zobrist_hash, remaining_draft, best_value, node_type));
-        }
+        assert_eq!(search.generate_info().table_hits, Some(3));
+        assert_eq!(search.generate_info().shredder_table_hits, Some(1));
+    }
 
-        // TODO transposition table
+    #[test]
+    fn test_syzygy_path_invalid() {
+        let (uci_tx, uci_rx) = channel();
+        let (search_tx, search_rx) = channel();
+        let mut search = Search::new(Arc::new(CommandUciTx::new(uci_tx)), search_rx, SimpleHeuristic::default(), MvvLvaMoveOrder, EngineOptions::default());
+
+        let directory = temp_dir().join("inkayaku_search_test_syzygy_path_does_not_exist");
+        search_tx.send(UciSetOption("SyzygyPath".to_string(), directory.to_str().unwrap().to_string())).unwrap();
+        search_tx.send(UciQuit).unwrap();
+        search.idle();
+
+        assert!(!search.is_tablebase_probing_enabled());
+        assert_eq!(search.options.syzygy_path, None);
+        assert!(uci_rx.try_iter().any(|command| matches!(command, UciTxCommand::Info { info } if info.string.as_deref().is_some_and(|string| string.starts_with("warning")))));
+    }
 
-        result
+    #[test]
+    fn test_book_move() {
+        let path = temp_dir().join("inkayaku_search_test_book.bin");
+        let key = polyglot_hash(&Bitboard::default());
+        // d2d4 in Polyglot encoding, to square d4 and from square d2
+        let d2d4: u16 = 27 | 11 << 6;
+        write(&path, [key.to_be_bytes().as_slice(), &d2d4.to_be_bytes(), &1_u16.to_be_bytes(), &0_u32.to_be_bytes()].concat()).unwrap();
+
+        let (uci_tx, uci_rx) = channel();
+        let (_search_tx, search_rx) = channel();
+        let mut search = Search::new(Arc::new(CommandUciTx::new(uci_tx)), search_rx, SimpleHeuristic::default(), MvvLvaMoveOrder, EngineOptions::default());
+
+        search.set_option("BookFile", path.to_str().unwrap());
+        search.set_option("OwnBook", "true");
+        search.set_position_from(Fen::default(), vec![]);
+        search.params.go = Go { depth: Some(1), ..Go::default() };
+        search.go();
+
+        let commands = uci_rx.try_iter().collect::<Vec<_>>();
+        assert_eq!(best_move(&commands), Some(UciMove::from_str("d2d4").unwrap()));
+        assert_eq!(search.state.metrics.last.total_nodes(), 0);
+
+        // Out of book
+        search.set_position_from(Fen::default(), vec![UciMove::from_str("e2e4").unwrap()]);
+        search.go();
+        assert!(search.state.metrics.last.total_nodes() > 0);
+
+        search.set_option("OwnBook", "false");
+        search.set_position_from(Fen::default(), vec![]);
+        search.go();
+        assert!(search.state.metrics.last.total_nodes() > 0);
     }
 
-    fn search_quiescence(&mut self, depth: u32, buffer: &mut Vec<Move>, alpha_original: i32, beta_original: i32, zobrist_pawn_hash: ZobristHash) -> ValuedMove {
-        let color = self.state.bitboard.turn;
+    #[test]
+    fn test_singular_extension_finds_forcing_move_earlier() {
+        let fen = "r1k4r/ppp1bq1p/2n1N3/6B1/3p2Q1/8/PPP2PPP/R5K1 w - - 0 1";
+
+        assert_eq!(first_depth_with_best_move(fen, "e6f8", true, 6), Some(5));
+        assert_eq!(first_depth_with_best_move(fen, "e6f8", false, 6), Some(6));
+    }
 
-        // TODO take attack moves from buffer on first call
+    /// Search `fen` to `depth` and return the first iteration with `expected` as the best move
+    fn first_depth_with_best_move(fen: &str, expected: &str, singular_extensions: bool, depth: u64) -> Option<u32> {
+        let (uci_tx, uci_rx) = channel();
+        let (search_tx, search_rx) = channel();
 
-        let standing_pat = self.evaluate(color, zobrist_pawn_hash, true);
+        // Search on a separate thread, as sending quit before the search finished would abort it
+        // Evasions in quiescence, null window searches and aspiration windows change the depth the forcing move is found at, so disable them to isolate the extension
+        let handle = thread::spawn(move || {
+            Search::new(Arc::new(CommandUciTx::new(uci_tx)), search_rx, SimpleHeuristic::default(), MvvLvaMoveOrder, EngineOptions { singular_extensions, extended_quiescence: false, principal_variation_search: false, aspiration_windows: false, ..EngineOptions::default() }).idle();
+        });
 
-        if standing_pat >= beta_original {
-            self.state.metrics.register_quiescence_termination(depth as usize);
-            return ValuedMove::leaf(beta_original);
+        search_tx.send(UciPositionFrom(Fen::from_str(fen).unwrap(), vec![])).unwrap();
+        search_tx.send(UciGo(Go { depth: Some(depth), ..Go::default() })).unwrap();
+
+        let result = uci_rx.iter()
+            .take_while(|command| !matches!(command, UciTxCommand::BestMove { .. }))
+            .filter_map(|command| if let UciTxCommand::Info { info } = command { info.depth.zip(info.principal_variation) } else { None })
+            .find(|(_, pv)| pv.first().is_some_and(|mv| mv.to_string() == expected))
+            .map(|(depth, _)| depth);
+
+        search_tx.send(UciQuit).unwrap();
+        handle.join().unwrap();
+
+        result
+    }
+
+    #[test]
+    fn test_extended_quiescence_is_equal_or_better() {
+        for fen in [
+            "rnb1k2r/ppp2ppp/3q1n2/2bpp3/4P3/2NP1N2/PPP1BPPP/R1BQK2R w KQkq - 0 6",
+            "2r3k1/pp3ppp/2n5/3q4/3P4/2P2N2/P2Q1PPP/2R3K1 b - - 0 22",
+        ] {
+            let (value, nodes) = quiescence(fen, false);
+            let (extended_value, extended_nodes) = quiescence(fen, true);
+
+            assert_eq!(extended_value, value, "{}", fen);
+            assert!(extended_nodes <= nodes, "{} searched {} instead of {} nodes", fen, extended_nodes, nodes);
         }
 
-        let mut alpha = max(alpha_original, standing_pat);
+        // Searching check evasions may cost more nodes than pruning losing captures saves, but doesn't change the value
+        let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        assert_eq!(quiescence(fen, true).0, quiescence(fen, false).0, "{}", fen);
+    }
 
-        let mut best_move = None;
-        let mut best_child = None;
+    #[test]
+    fn test_extended_quiescence_evades_checks() {
+        let search = Search::new(Arc::new(CommandUciTx::new(channel().0)), channel().1, SimpleHeuristic::default(), MvvLvaMoveOrder, EngineOptions::default());
 
-        let mut next_buffer = Vec::new();
+        // Qxf7# is only recognized as mate if black may not stand pat in check
+        let fen = "r1bqkb1r/pppp1ppp/2n2n2/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 4 4";
+        let (value, nodes) = quiescence(fen, false);
+        let (extended_value, extended_nodes) = quiescence(fen, true);
 
-        buffer.clear();
-        self.state.bitboard.generate_pseudo_legal_non_quiescent_moves_with_buffer(buffer);
-        self.move_order.sort(buffer, None, None, None);
+        assert!(!search.heuristic.is_checkmate(value));
+        assert!(search.heuristic.is_checkmate(extended_value) && extended_value > 0);
+        assert!(extended_nodes <= nodes);
 
-        for mv in buffer {
-            self.state.bitboard.make(*mv);
+        let fen = "r1bqkb1r/pppp1Qpp/2n2n2/4p3/2B1P3/8/PPPP1PPP/RNB1K1NR b KQkq - 0 4";
+        assert!(!search.heuristic.is_checkmate(quiescence(fen, false).0));
+        assert!(search.heuristic.is_checkmate(quiescence(fen, true).0));
+    }
 
-            if !self.state.bitboard.is_valid() {
-                self.state.bitboard.unmake(*mv);
-                continue;
-            }
+    fn quiescence(fen: &str, extended_quiescence: bool) -> (i32, u64) {
+        let (value, nodes, _) = quiescence_with_options(fen, EngineOptions { extended_quiescence, quiescence_check_depth: 0, ..EngineOptions::default() });
 
-            self.state.metrics.increment_quiescence_nodes();
+        (value, nodes)
+    }
 
-            let child = self.search_quiescence(depth + 1, &mut next_buffer, -beta_original, -alpha, zobrist_pawn_hash ^ Bitboard::zobrist_xor(*mv).1);
-            let value = -child.value;
+    fn quiescence_with_options(fen: &str, options: EngineOptions) -> (i32, u64, u64) {
+        let (uci_tx, _uci_rx) = channel();
+        let (_search_tx, search_rx) = channel();
+        let mut search = Search::new(Arc::new(CommandUciTx::new(uci_tx)), search_rx, SimpleHeuristic::default(), MvvLvaMoveOrder, options);
 
-            self.state.bitboard.unmake(*mv);
+        search.state.bitboard = Bitboard::from(&Fen::from_str(fen).unwrap());
+        let mut buffer = Vec::new();
+        search.state.bitboard.generate_pseudo_legal_moves_with_buffer(&mut buffer);
 
-            if value >= beta_original {
-                self.state.metrics.register_quiescence_termination(depth as usize);
-                return ValuedMove::parent(beta_original, *mv, child);
-            }
+        let value = search.search_quiescence(0, 0, &mut buffer, search.heuristic.loss_score(), search.heuristic.win_score(), None).value;
 
-            if value > alpha {
-                alpha = value;
-                best_move = Some(*mv);
-                best_child = Some(child);
-            }
-        }
+        (value, search.state.metrics.last.quiescence_nodes, search.state.metrics.last.quiescence_check_extension_count)
+    }
 
-        self.state.metrics.register_quiescence_termination(depth as usize);
-        ValuedMove::new(alpha, best_move, best_child)
+    #[test]
+    fn test_quiescence_checks_find_mate() {
+        let heuristic = SimpleHeuristic::default();
+
+        // Ra8# is a quiet move, only found if quiescence searches checks
+        let fen = "6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1";
+        let (value, _, check_extensions) = quiescence_with_options(fen, EngineOptions { quiescence_check_depth: 0, ..EngineOptions::default() });
+        assert!(!heuristic.is_checkmate(value));
+        assert_eq!(check_extensions, 0);
+
+        let (value, _, check_extensions) = quiescence_with_options(fen, EngineOptions::default());
+        assert!(heuristic.is_checkmate(value) && value > 0);
+        assert!(check_extensions > 0);
     }
-}
 
-/// Non-search related functionality
-impl<T: UciTx, H: Heuristic, M: MoveOrder> Search<T, H, M> {
-    fn generate_info(&self) -> Info {
-        Info {
-            nodes: Some(self.state.metrics.last.total_nodes()),
-            hash_full: Some((self.state.transposition_table.load_factor() * 1000.0) as u32),
-            nps: Some(self.state.metrics.last.nps_with_duration(&self.state.elapsed())),
-            ..Info::EMPTY
+    #[test]
+    fn test_principal_variation_search_searches_fewer_nodes() {
+        for fen in [
+            "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        ] {
+            let (score, best_move, nodes) = search_to_depth(fen, false, 5);
+            let (pvs_score, pvs_best_move, pvs_nodes) = search_to_depth(fen, true, 5);
+
+            assert_eq!(pvs_score, score, "{}", fen);
+            assert_eq!(pvs_best_move, best_move, "{}", fen);
+            assert!(pvs_nodes < nodes, "{} searched {} instead of {} nodes", fen, pvs_nodes, nodes);
         }
     }
 
-    fn generate_debug_string_if_enabled(&self) -> Option<String> {
-        if self.options.debug { Some(self.generate_debug_string()) } else { None }
+    /// Score, best move and node count of a search of `fen` to `depth`, with a full window at the root to isolate the null window searches
+    fn search_to_depth(fen: &str, principal_variation_search: bool, depth: u64) -> (Option<Score>, Option<UciMove>, u64) {
+        let (commands, nodes) = search_go(fen, EngineOptions { principal_variation_search, aspiration_windows: false, ..EngineOptions::default() }, Go { depth: Some(depth), ..Go::default() });
+
+        (last_score(&commands), best_move(&commands), nodes)
     }
 
-    fn generate_debug_string(&self) -> String {
-        format!("tphitrate {} nrate {} qrate {} avgqdepth {} qstartedrate {} qtphitrate {}",
-                self.state.metrics.last.table_hit_rate(),
-                self.state.metrics.last.negamax_node_rate(),
-                self.state.metrics.last.quiescence_node_rate(),
-                self.state.metrics.last.average_quiescence_termination_ply(),
-                self.state.metrics.last.quiescence_started_rate(),
-                self.state.metrics.last.quiescence_table_hit_rate(),
-        )
+    /// Commands sent and node count of a search of `fen` with `go` on the current thread
+    fn search_go(fen: &str, options: EngineOptions, go: Go) -> (Vec<UciTxCommand>, u64) {
+        let (uci_tx, uci_rx) = channel();
+        let (_search_tx, search_rx) = channel();
+        let mut search = Search::new(Arc::new(CommandUciTx::new(uci_tx)), search_rx, SimpleHeuristic::default(), MvvLvaMoveOrder, options);
+
+        search.set_position_from(Fen::from_str(fen).unwrap(), vec![]);
+        search.params.go = go;
+        search.go();
+
+        (uci_rx.try_iter().collect(), search.state.metrics.last.total_nodes())
     }
-}
 
-#[inline(always)]
-const fn calculate_heuristic_factor(color: ColorBits) -> i32 {
-    1 + (color as i32) * -2
-}
+    fn last_score(commands: &[UciTxCommand]) -> Option<Score> {
+        commands.iter().rev().find_map(|command| if let UciTxCommand::Info { info } = command { info.score } else { None })
+    }
 
-#[derive(Clone)]
-pub struct ValuedMove {
-    value: i32,
-    mv: Option<Move>,
-    pv_child: Box<Option<ValuedMove>>,
-}
+    fn best_move(commands: &[UciTxCommand]) -> Option<UciMove> {
+        commands.iter().find_map(|command| if let UciTxCommand::BestMove { best_move, .. } = command { best_move.clone() } else { None })
+    }
 
-impl ValuedMove {
-    pub fn new(value: i32, mv: Option<Move>, pv_child: Option<Self>) -> Self {
-        Self { value, mv, pv_child: Box::new(pv_child) }
+    #[test]
+    fn test_threads_play_legal_move() {
+        let fen = "r1bq1rk1/pp2bppp/2n1pn2/3p4/2PP4/2N1PN2/PP2BPPP/R2QKB1R w KQ - 0 9";
+        let (commands, nodes) = search_go(fen, EngineOptions { threads: 2, ..EngineOptions::default() }, Go { depth: Some(5), ..Go::default() });
+
+        let best_move = best_move(&commands).unwrap();
+        assert!(Bitboard::from_fen_string_unchecked(fen).resolve_uci_move(&best_move).is_ok(), "{}", best_move);
+        assert!(nodes > 0);
     }
 
-    pub fn parent(value: i32, mv: Move, pv_child: Self) -> Self {
-        Self::new(value, Some(mv), Some(pv_child))
+    #[test]
+    fn test_delta_pruning_searches_fewer_nodes() {
+        for fen in [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r1b1k2r/ppppnppp/2n2q2/2b5/3NP3/2P1B3/PP3PPP/RN1QKB1R w KQkq - 0 1",
+        ] {
+            let (commands, nodes) = search_go(fen, EngineOptions { delta_pruning: false, ..EngineOptions::default() }, Go { depth: Some(5), ..Go::default() });
+            let (delta_pruning_commands, delta_pruning_nodes) = search_go(fen, EngineOptions::default(), Go { depth: Some(5), ..Go::default() });
+
+            assert_eq!(best_move(&delta_pruning_commands), best_move(&commands), "{}", fen);
+            assert!(delta_pruning_nodes < nodes, "{} searched {} instead of {} nodes", fen, delta_pruning_nodes, nodes);
+        }
     }
 
-    pub fn leaf(value: i32) -> Self {
-        Self::new(value, None, None)
+    #[test]
+    fn test_contempt_declines_repetition() {
+        // White is slightly worse and repeats the position for the third time with d5b6
+        let fen = "5rk1/5r2/pN6/2p1p1q1/2P1P2p/1P3P1P/P4RPP/5RK1 b - - 1 28";
+        let moves = ["g5e3", "b6d5", "e3g5", "d5b6", "g5e3", "b6d5", "e3g5"];
+        let repetition = UciMove::from_str("d5b6").unwrap();
+
+        let search_with_contempt = |contempt_factor| {
+            let (uci_tx, uci_rx) = channel();
+            let (_search_tx, search_rx) = channel();
+            let mut search = Search::new(Arc::new(CommandUciTx::new(uci_tx)), search_rx, SimpleHeuristic::default(), MvvLvaMoveOrder, EngineOptions { contempt_factor, ..EngineOptions::default() });
+
+            search.set_position_from(Fen::from_str(fen).unwrap(), moves.iter().map(|uci| UciMove::from_str(uci).unwrap()).collect());
+            search.params.go = Go { depth: Some(6), ..Go::default() };
+            search.go();
+
+            uci_rx.try_iter().collect::<Vec<_>>()
+        };
+
+        let commands = search_with_contempt(0);
+        assert_eq!(best_move(&commands), Some(repetition.clone()));
+        assert_eq!(last_score(&commands), Some(Score::Centipawn { score: 0 }));
+
+        let commands = search_with_contempt(100);
+        assert_ne!(best_move(&commands), Some(repetition));
     }
 
-    fn calculate_principal_variation(&self) -> Vec<Move> {
-        let mut result = Vec::new();
+    #[test]
+    fn test_aspiration_windows_match_full_window() {
+        let fen = "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3";
 
-        let option = Some(self);
-        let mut maybe_current = option;
+        let (commands, _) = search_go(fen, EngineOptions { aspiration_windows: false, ..EngineOptions::default() }, Go { depth: Some(6), ..Go::default() });
+        let (aspiration_commands, _) = search_go(fen, EngineOptions::default(), Go { depth: Some(6), ..Go::default() });
 
-        while let Some(current) = maybe_current {
-            if let Some(mv) = current.mv {
-                result.push(mv);
-            }
+        assert_eq!(best_move(&aspiration_commands), best_move(&commands));
+        assert_eq!(last_principal_variation(&aspiration_commands), last_principal_variation(&commands));
+        assert_eq!(last_score(&aspiration_commands), last_score(&commands));
+    }
 
+    #[test]
+    fn test_aspiration_window_fail_reports_bound() {
+        let bounds = |previous_value: i32| {
+            let (uci_tx, uci_rx) = channel();
+            let (_search_tx, search_rx) = channel();
+            let mut search = Search::new(Arc::new(CommandUciTx::new(uci_tx)), search_rx, SimpleHeuristic::default(), MvvLvaMoveOrder, EngineOptions::default());
+
+            search.set_position_from(Fen::default(), vec![]);
+            search.params.go = Go { depth: Some(4), ..Go::default() };
+            search.reset_for_go();
+            search.state.stop_condition = search.stop_condition();
+            search.search_root(4, Some(previous_value));
+
+            uci_rx.try_iter()
+                .filter_map(|command| if let UciTxCommand::Info { info: Info { score: Some(Score::CentipawnBounded { bound, .. }), .. } } = command { Some(bound) } else { None })
+                .collect::<Vec<_>>()
+        };
 
-            maybe_current = (*current.pv_child).as_ref();
+        // The start position is about even, so a window around a large previous value fails
+        assert_eq!(bounds(-500).first(), Some(&Bound::LOWER));
+        assert_eq!(bounds(500).first(), Some(&Bound::UPPER));
+        assert!(bounds(0).is_empty());
+    }
+
+    #[test]
+    fn test_principal_variation_continues_from_transposition_table() {
+        let (uci_tx, _uci_rx) = channel();
+        let (_search_tx, search_rx) = channel();
+        let mut search = Search::new(Arc::new(CommandUciTx::new(uci_tx)), search_rx, SimpleHeuristic::default(), MvvLvaMoveOrder, EngineOptions::default());
+
+        search.set_position_from(Fen::default(), vec![]);
+        search.params.go = Go { depth: Some(4), ..Go::default() };
+        search.go();
+
+        let mut bitboard = search.state.bitboard;
+        let root_move = search.state.principal_variation.clone().unwrap()[0];
+        let pv = search.principal_variation(&ValuedMove::new(0, Some(root_move), None));
+
+        assert!(pv.len() > 1);
+        assert_eq!(pv[0], root_move);
+        assert_eq!(search.state.bitboard, bitboard);
+
+        for mv in pv {
+            assert!(bitboard.generate_legal_moves().contains(&mv));
+            bitboard.make(mv);
         }
+    }
 
-        result
+    fn last_principal_variation(commands: &[UciTxCommand]) -> Option<Vec<UciMove>> {
+        commands.iter().rev().find_map(|command| if let UciTxCommand::Info { info } = command { info.principal_variation.clone() } else { None })
     }
-}
 
-#[derive(Debug)]
-#[allow(clippy::enum_variant_names)]
-pub enum SearchMessage {
-    UciUciNewGame,
-    UciDebug(bool),
-    UciPositionFrom(Fen, Vec<UciMove>),
-    UciGo(Go),
-    UciStop,
-    UciPonderHit,
-    UciQuit,
-}
+    #[test]
+    fn test_info_selective_depth() {
+        let (commands, _) = search_go("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", EngineOptions::default(), Go { depth: Some(3), ..Go::default() });
 
-/// UCI options
-pub struct EngineOptions {
-    pub debug: bool,
-    pub try_previous_pv: bool,
-    pub contempt_factor: i32,
-}
+        let info = commands.iter().rev().find_map(|command| if let UciTxCommand::Info { info } = command { info.depth.is_some().then_some(info) } else { None }).unwrap();
 
-impl Default for EngineOptions {
-    fn default() -> Self {
-        Self {
-            debug: false,
-            try_previous_pv: true,
-            contempt_factor: 50,
-        }
+        // Quiescence continues past the iteration depth in a position with this many captures
+        assert!(info.selective_depth.unwrap() > 3);
     }
-}
 
-/// State during search
-struct SearchState {
-    bitboard: Bitboard,
-    transposition_table: HashMapTranspositionTable,
-    killer_table: KillerTable,
-    principal_variation: Option<Vec<Move>>,
-    zobrist_history: ZobristHistory,
-    started_at: SystemTime,
-    is_running: bool,
-    metrics: MetricsService,
-}
+    #[test]
+    fn test_periodic_info_current_move() {
+        let (commands, _) = search_go("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", EngineOptions::default(), Go { depth: Some(6), ..Go::default() });
 
-impl SearchState {
-    fn ponder_move(&self) -> Option<Move> {
-        self.principal_variation.as_ref().and_then(|pv| pv.get(1)).copied()
+        let periodic_infos = commands.iter().filter_map(|command| if let UciTxCommand::Info { info } = command { info.depth.is_none().then_some(info) } else { None }).collect::<Vec<_>>();
+
+        assert!(!periodic_infos.is_empty());
+        for info in periodic_infos {
+            assert!(info.current_move.is_some());
+            assert!((1..=48).contains(&info.current_move_number.unwrap()));
+        }
     }
 
-    fn elapsed(&self) -> Duration {
-        self.started_at.elapsed().unwrap_or(Duration::ZERO)
+    #[test]
+    fn test_multi_pv() {
+        let (commands, _) = search_go("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3", EngineOptions { multi_pv: 2, ..EngineOptions::default() }, Go { depth: Some(4), ..Go::default() });
+
+        let last_depth = commands.iter()
+            .filter_map(|command| if let UciTxCommand::Info { info } = command { Some(info) } else { None })
+            .filter(|info| info.depth == Some(4) && info.multi_pv.is_some())
+            .collect::<Vec<_>>();
+
+        assert_eq!(last_depth.iter().map(|info| info.multi_pv).collect::<Vec<_>>(), vec![Some(1), Some(2)]);
+
+        let first_moves = last_depth.iter().map(|info| info.principal_variation.as_ref().unwrap()[0].clone()).collect::<Vec<_>>();
+        assert_ne!(first_moves[0], first_moves[1]);
+        assert_eq!(best_move(&commands), Some(first_moves[0].clone()));
+        assert!(last_depth[0].score.is_some() && last_depth[1].score.is_some());
     }
-}
 
-impl Default for SearchState {
-    fn default() -> Self {
-        Self {
-            bitboard: Bitboard::default(),
-            transposition_table: HashMapTranspositionTable::new(10_000_000),
-            killer_table: KillerTable::default(),
-            principal_variation: None,
-            zobrist_history: ZobristHistory::default(),
-            started_at: SystemTime::UNIX_EPOCH,
-            is_running: false,
-            metrics: MetricsService::default(),
+    #[test]
+    fn test_show_refutations() {
+        let fen = "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3";
+        let refutations = |show_refutations| {
+            let (commands, _) = search_go(fen, EngineOptions { show_refutations, ..EngineOptions::default() }, Go { depth: Some(3), ..Go::default() });
+            commands.into_iter().filter_map(|command| if let UciTxCommand::Info { info } = command { info.refutation } else { None }).collect::<Vec<_>>()
+        };
+
+        assert!(refutations(false).is_empty());
+
+        let refutations = refutations(true);
+        let mut board = Bitboard::from_fen_string_unchecked(fen);
+        let legal_moves = board.generate_legal_moves();
+
+        assert!(!refutations.is_empty());
+        assert!(refutations.iter().any(|refutation| refutation.len() >= 2));
+        for refutation in &refutations {
+            assert!(legal_moves.contains(&board.resolve_uci_move(&refutation[0]).unwrap()), "{:?}", refutation);
         }
+        // Every root move is reported in the last iteration
+        assert!(refutations.len() >= legal_moves.len());
     }
-}
 
-/// Control the search "from the outside"
-#[derive(Default)]
-#[allow(clippy::struct_excessive_bools)]
-struct SearchFlags {
-    reset_for_next_search: bool,
-    stop_as_soon_as_possible: bool,
-    quit_as_soon_as_possible: bool,
-    ponder_hit: bool,
-}
+    #[test]
+    fn test_wdl() {
+        let (win, draw, loss) = calculate_wdl(Score::Centipawn { score: 300 }, 0, 100);
+        assert!(win > 800 && loss < 50, "{} {} {}", win, draw, loss);
 
-/// Input for the search
-#[derive(Default)]
-struct SearchParams {
-    go: Go,
-    fen: Fen,
-    moves: Vec<Move>,
-}
+        let (win, draw, loss) = calculate_wdl(Score::Centipawn { score: 0 }, 0, 100);
+        assert_eq!(win, loss);
+        assert!(draw > win, "{} {} {}", win, draw, loss);
 
-enum PvContinuationError {
-    PvTooShort(usize),
-    NoPreviousPv { last_ponder_move: Move, last_played_move: Move },
-    DivergedFromPv { last_ponder_move: Move, last_played_move: Move },
-    NoLastPlayedMove { last_ponder_move: Move },
-    NoPonderMove,
-}
+        // Draws become more likely towards the endgame
+        assert!(calculate_wdl(Score::Centipawn { score: 0 }, 24, 100).1 > draw);
+        assert_eq!(calculate_wdl(Score::Mate { mate_in: -2 }, 0, 100), (0, 0, 1000));
+    }
 
-#[cfg(test)]
-mod test {
-    use inkayaku_board::constants::{BLACK, WHITE};
+    #[test]
+    fn test_show_wdl() {
+        let wdl_strings = |show_wdl| {
+            let (commands, _) = search_go("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3", EngineOptions { show_wdl, ..EngineOptions::default() }, Go { depth: Some(3), ..Go::default() });
+            commands.into_iter().filter_map(|command| if let UciTxCommand::Info { info } = command { info.string.filter(|string| string.starts_with("wdl ")) } else { None }).collect::<Vec<_>>()
+        };
+
+        assert!(wdl_strings(false).is_empty());
+
+        let wdl_strings = wdl_strings(true);
+        assert_eq!(wdl_strings.len(), 1);
+        assert_eq!(wdl_strings[0].split_whitespace().skip(1).map(|probability| probability.parse::<u32>().unwrap()).sum::<u32>(), 1000);
+    }
+
+    #[test]
+    fn test_multi_pv_defaults_to_single_pv() {
+        let (commands, _) = search_go("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3", EngineOptions::default(), Go { depth: Some(3), ..Go::default() });
 
-    use crate::engine::search::calculate_heuristic_factor;
+        assert!(commands.iter().all(|command| !matches!(command, UciTxCommand::Info { info } if info.multi_pv.is_some())));
+    }
 
     #[test]
-    fn test_heuristic_factor() {
-        assert_eq!(calculate_heuristic_factor(BLACK), -1);
-        assert_eq!(calculate_heuristic_factor(WHITE), 1);
+    fn test_go_mate_finds_mate_in_two() {
+        let (commands, _) = search_go("r2qkb1r/pp2nppp/3p4/2pNN1B1/2BnP3/3P4/PPP2PPP/R2bK2R w KQkq - 1 1", EngineOptions::default(), Go { mate: Some(2), ..Go::default() });
+
+        assert_eq!(last_score(&commands), Some(Score::Mate { mate_in: 2 }));
+        assert_eq!(best_move(&commands).map(|mv| mv.to_string()), Some("d5f6".to_string()));
+    }
+
+    #[test]
+    fn test_go_mate_stops_at_mate() {
+        // Mate in one, so the search stops after the first iteration even though a deeper mate search was requested
+        let (commands, _) = search_go("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", EngineOptions::default(), Go { mate: Some(5), ..Go::default() });
+
+        let depths = commands.iter().filter_map(|command| if let UciTxCommand::Info { info } = command { info.depth } else { None }).collect::<Vec<_>>();
+
+        assert_eq!(depths, vec![1]);
+        assert_eq!(last_score(&commands), Some(Score::Mate { mate_in: 1 }));
+    }
+
+    #[test]
+    fn test_go_mate_reports_no_mate_found() {
+        let (commands, _) = search_go("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", EngineOptions::default(), Go { mate: Some(2), ..Go::default() });
+
+        assert!(commands.iter().any(|command| matches!(command, UciTxCommand::Info { info } if info.string.as_deref() == Some("no mate in 2 found"))));
+        assert!(best_move(&commands).is_some());
+    }
+
+    #[test]
+    fn test_clear_hash() {
+        let (uci_tx, _uci_rx) = channel();
+        let (search_tx, search_rx) = channel();
+        let mut search = Search::new(Arc::new(CommandUciTx::new(uci_tx)), search_rx, SimpleHeuristic::default(), MvvLvaMoveOrder, EngineOptions { hash_size_mb: 1, ..EngineOptions::default() });
+
+        search_tx.send(UciPositionFrom(Fen::default(), vec![])).unwrap();
+        search_tx.send(UciGo(Go { depth: Some(3), ..Go::default() })).unwrap();
+        search_tx.send(UciQuit).unwrap();
+        search.idle();
+
+        let zobrist_hash = search.state.bitboard.zobrist_hash();
+        assert!(search.state.transposition_table.get(zobrist_hash).is_some());
+
+        search.set_option("Clear Hash", "");
+
+        assert!(search.state.transposition_table.get(zobrist_hash).is_none());
+    }
+
+    #[test]
+    fn test_set_option_ponder_and_multi_pv() {
+        let (uci_tx, _uci_rx) = channel();
+        let (_search_tx, search_rx) = channel();
+        let mut search = Search::new(Arc::new(CommandUciTx::new(uci_tx)), search_rx, SimpleHeuristic::default(), MvvLvaMoveOrder, EngineOptions::default());
+
+        search.set_option("Ponder", "true");
+        search.set_option("MultiPV", "3");
+        search.set_option("MultiPV", "many");
+
+        assert!(search.options.ponder);
+        assert_eq!(search.options.multi_pv, 3);
+    }
+
+    #[test]
+    fn test_options_persist_across_new_game() {
+        let (uci_tx, _uci_rx) = channel();
+        let (search_tx, search_rx) = channel();
+        let mut search = Search::new(Arc::new(CommandUciTx::new(uci_tx)), search_rx, SimpleHeuristic::default(), MvvLvaMoveOrder, EngineOptions::default());
+
+        search_tx.send(UciSetOption("Hash".to_string(), "256".to_string())).unwrap();
+        search_tx.send(UciPositionFrom(Fen::default(), vec![])).unwrap();
+        search_tx.send(UciGo(Go { depth: Some(5), ..Go::default() })).unwrap();
+        search_tx.send(UciUciNewGame).unwrap();
+        search_tx.send(UciQuit).unwrap();
+        search.idle();
+
+        let zobrist_hash = search.state.bitboard.zobrist_hash();
+        let moves = search.state.bitboard.generate_legal_moves();
+        let has_killer_moves = |search: &Search<_, _, _>| (0..=5).any(|depth| search.state.killer_table.get(depth).is_some());
+        let has_history = |search: &Search<_, _, _>| moves.iter().any(|&mv| search.state.history_table.get(mv) != 0);
+
+        assert!(search.state.transposition_table.get(zobrist_hash).is_some());
+        assert!(has_killer_moves(&search));
+        assert!(has_history(&search));
+        assert!(search.state.metrics.total.negamax_nodes > 0);
+
+        // The tables are reset when the next search starts
+        search.reset_for_go();
+
+        assert!(search.state.transposition_table.get(zobrist_hash).is_none());
+        assert!(!has_killer_moves(&search));
+        assert!(!has_history(&search));
+        assert_eq!(search.state.metrics.total.negamax_nodes, 0);
+        assert_eq!(search.state.metrics.last.negamax_nodes, 0);
+        assert!(search.state.principal_variation.is_none());
+
+        assert_eq!(search.options.hash_size_mb, 256);
+        assert_eq!(search.state.transposition_table.capacity(), ArrayTranspositionTable::capacity_for_size_mb(256));
     }
 }
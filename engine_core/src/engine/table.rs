@@ -3,7 +3,9 @@ use std::hash::Hash;
 
 use inkayaku_board::constants::ZobristHash;
 
+pub mod history;
 pub mod killer;
+pub mod pawn;
 pub mod transposition;
 
 pub struct HashTable<K: Eq + Hash + Copy, V> {
@@ -37,14 +39,6 @@ impl<V> HashTable<ZobristHash, V> {
     fn get(&self, key: ZobristHash) -> Option<&V> {
         self.entry_map.get(&key)
     }
-
-    fn len(&self) -> usize {
-        self.entry_map.len()
-    }
-
-    fn load_factor(&self) -> f32 {
-        self.len() as f32 / self.capacity as f32
-    }
 }
 
 // #[cfg(test)]
@@ -0,0 +1,103 @@
+use inkayaku_board::Move;
+
+/// Largest value of an entry, kept below the lowest MVV-LVA value of a capture so captures are always ordered first
+const MAX_VALUE: i32 = 20_000;
+
+/// Butterfly history of quiet moves causing beta cutoffs, indexed by side to move, source square and target square
+pub struct HistoryTable {
+    table: Vec<i32>,
+}
+
+impl HistoryTable {
+    pub fn clear(&mut self) {
+        self.table.fill(0);
+    }
+
+    /// Halve all entries, so cutoffs of previous searches weigh less than the current search
+    pub fn age(&mut self) {
+        for value in &mut self.table {
+            *value /= 2;
+        }
+    }
+
+    /// Reward a cutoff of `mv` with a remaining draft of `depth`. Ages the table if the entry would exceed the maximum value.
+    pub fn put(&mut self, depth: usize, mv: Move) {
+        let index = Self::index(mv);
+        let bonus = (depth * depth).min(MAX_VALUE as usize) as i32;
+
+        self.table[index] += bonus;
+
+        if self.table[index] > MAX_VALUE {
+            self.age();
+        }
+    }
+
+    pub fn get(&self, mv: Move) -> i32 {
+        self.table[Self::index(mv)]
+    }
+
+    #[inline(always)]
+    fn index(mv: Move) -> usize {
+        ((mv.get_side_to_move() as usize) << 12) | ((mv.get_source_square() as usize) << 6) | mv.get_target_square() as usize
+    }
+}
+
+impl Default for HistoryTable {
+    fn default() -> Self {
+        Self { table: vec![0; 2 * 64 * 64] }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use inkayaku_board::Bitboard;
+
+    use crate::engine::table::history::{HistoryTable, MAX_VALUE};
+
+    #[test]
+    fn test_put_and_age() {
+        let mut bitboard = Bitboard::default();
+        let e2e4 = bitboard.find_uci("e2e4").unwrap();
+        let d2d4 = bitboard.find_uci("d2d4").unwrap();
+
+        let mut sut = HistoryTable::default();
+
+        sut.put(3, e2e4);
+        sut.put(2, e2e4);
+        assert_eq!(sut.get(e2e4), 13);
+        assert_eq!(sut.get(d2d4), 0);
+
+        sut.age();
+        assert_eq!(sut.get(e2e4), 6);
+
+        sut.clear();
+        assert_eq!(sut.get(e2e4), 0);
+    }
+
+    #[test]
+    fn test_sides_are_separate() {
+        let mut bitboard = Bitboard::default();
+        let e2e4 = bitboard.find_uci("e2e4").unwrap();
+        bitboard.make(e2e4);
+        let e7e5 = bitboard.find_uci("e7e5").unwrap();
+
+        let mut sut = HistoryTable::default();
+        sut.put(4, e2e4);
+
+        assert_eq!(sut.get(e2e4), 16);
+        assert_eq!(sut.get(e7e5), 0);
+    }
+
+    #[test]
+    fn test_put_never_exceeds_max_value() {
+        let mut bitboard = Bitboard::default();
+        let e2e4 = bitboard.find_uci("e2e4").unwrap();
+
+        let mut sut = HistoryTable::default();
+
+        for _ in 0..1000 {
+            sut.put(40, e2e4);
+            assert!(sut.get(e2e4) <= MAX_VALUE);
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use inkayaku_board::Bitboard;
+use inkayaku_board::constants::ZobristHash;
+
+use crate::engine::heuristic::PawnEval;
+use crate::engine::table::HashTable;
+
+/// Cache of the pawn structure evaluation, keyed by the pawn zobrist hash
+pub struct PawnTable {
+    hash_table: HashTable<ZobristHash, PawnEval>,
+}
+
+impl PawnTable {
+    pub fn new(capacity: usize) -> Self {
+        Self { hash_table: HashTable::new(capacity) }
+    }
+
+    pub fn clear(&mut self) {
+        self.hash_table.clear();
+    }
+
+    /// The pawn structure evaluation of `bitboard`, only calculated if `zobrist_pawn_hash` is not cached yet
+    pub fn get_or_calculate(&mut self, zobrist_pawn_hash: ZobristHash, bitboard: &Bitboard) -> PawnEval {
+        if let Some(&pawn_eval) = self.hash_table.get(zobrist_pawn_hash) {
+            return pawn_eval;
+        }
+
+        let pawn_eval = PawnEval::from_pawns(bitboard.white.pawns(), bitboard.black.pawns());
+        self.hash_table.put(zobrist_pawn_hash, pawn_eval);
+        pawn_eval
+    }
+}
+
+impl Default for PawnTable {
+    fn default() -> Self {
+        Self::new(1 << 16)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use inkayaku_board::Bitboard;
+
+    use crate::engine::heuristic::PawnEval;
+    use crate::engine::table::pawn::PawnTable;
+
+    #[test]
+    fn test_get_or_calculate() {
+        let mut table = PawnTable::default();
+        let doubled = Bitboard::from_fen_string_unchecked("4k3/5ppp/8/8/8/5P2/5PP1/4K3 w - - 0 1");
+        let expected = PawnEval::from_pawns(doubled.white.pawns(), doubled.black.pawns());
+
+        assert_eq!(table.get_or_calculate(doubled.pawn_zobrist_hash(), &doubled), expected);
+        assert_eq!(table.hash_table.entry_map.len(), 1);
+
+        // Cached by the hash only, so the pawns of the board are not looked at again
+        assert_eq!(table.get_or_calculate(doubled.pawn_zobrist_hash(), &Bitboard::default()), expected);
+        assert_eq!(table.hash_table.entry_map.len(), 1);
+
+        table.clear();
+        assert_eq!(table.hash_table.entry_map.len(), 0);
+    }
+}
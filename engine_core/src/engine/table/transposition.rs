@@ -1,16 +1,20 @@
-use inkayaku_board::constants::ZobristHash;
+use std::mem::size_of;
+use std::sync::{Mutex, MutexGuard, PoisonError};
 
-use crate::engine::search::ValuedMove;
-use crate::engine::table::HashTable;
+use inkayaku_board::constants::ZobristHash;
+use inkayaku_board::Move;
 
+#[derive(Copy, Clone)]
 pub enum NodeType {
     Exact,
     Lowerbound,
     Upperbound,
 }
 
+/// Only the best move is stored, the principal variation is rebuilt from the entries of the following positions
+#[derive(Copy, Clone)]
 pub struct TtEntry {
-    pub mv: ValuedMove,
+    pub mv: Option<Move>,
     pub zobrist_hash: ZobristHash,
     pub depth: usize,
     pub value: i32,
@@ -18,96 +22,223 @@ pub struct TtEntry {
 }
 
 impl TtEntry {
-    pub const fn new(mv: ValuedMove, zobrist_hash: ZobristHash, depth: usize, value: i32, node_type: NodeType) -> Self {
+    pub const fn new(mv: Option<Move>, zobrist_hash: ZobristHash, depth: usize, value: i32, node_type: NodeType) -> Self {
         Self { mv, zobrist_hash, depth, value, node_type }
     }
 }
 
-pub trait TranspositionTable {
-    fn clear(&mut self);
-    fn put(&mut self, zobrist_hash: ZobristHash, entry: TtEntry);
-    fn get(&self, zobrist_hash: ZobristHash) -> Option<&TtEntry>;
-    fn len(&self) -> usize;
-    fn load_factor(&self) -> f32;
+/// Number of slots [`ArrayTranspositionTable::load_factor`] samples
+const LOAD_FACTOR_SAMPLE_SIZE: usize = 1000;
+/// Number of separately locked parts of a [`SharedTranspositionTable`]
+const SHARDS: usize = 64;
+
+struct Slot {
+    generation: u16,
+    entry: TtEntry,
 }
 
-pub struct ArrayTranspositionTable<const N: usize> {
-    entries: Vec<Option<TtEntry>>,
-    load: usize,
+/// Transposition table backed by a power of two sized array indexed by the low bits of the Zobrist hash, storing the
+/// full hash for verification. The table is cleared by advancing a generation, which is cheap for tables that are
+/// cleared before every search.
+pub struct ArrayTranspositionTable {
+    slots: Vec<Option<Slot>>,
+    capacity: usize,
+    generation: u16,
 }
 
-impl<const N: usize> ArrayTranspositionTable<N> {
-    fn new_vec() -> Vec<Option<TtEntry>> {
-        (0..N).map(|_| None).collect()
+impl ArrayTranspositionTable {
+    /// Create a table with `capacity` rounded down to a power of two slots
+    pub fn new(capacity: usize) -> Self {
+        let capacity = if capacity == 0 { 1 } else { 1 << capacity.ilog2() };
+
+        Self { slots: (0..capacity).map(|_| None).collect(), capacity, generation: 0 }
     }
 
-    const fn array_hash(hash: u64) -> usize {
-        (hash % N as u64) as usize
+    pub const fn capacity_for_size_mb(size_mb: usize) -> usize {
+        let capacity = size_mb * 1024 * 1024 / size_of::<Option<Slot>>();
+
+        if capacity == 0 { 1 } else { 1 << capacity.ilog2() }
     }
-}
 
-impl<const N: usize> Default for ArrayTranspositionTable<N> {
-    fn default() -> Self {
-        Self { entries: Self::new_vec(), load: 0 }
+    const fn index(&self, zobrist_hash: ZobristHash) -> usize {
+        zobrist_hash as usize & (self.capacity - 1)
     }
-}
 
-impl<const N: usize> TranspositionTable for ArrayTranspositionTable<N> {
-    fn clear(&mut self) {
-        self.entries = Self::new_vec();
+    fn is_current(&self, slot: &Option<Slot>) -> bool {
+        slot.as_ref().is_some_and(|slot| slot.generation == self.generation)
     }
 
-    fn put(&mut self, zobrist_hash: ZobristHash, entry: TtEntry) {
-        let hash = Self::array_hash(zobrist_hash);
-        let option = &mut self.entries[hash];
-        if option.is_none() {
-            self.load += 1;
+    pub fn clear(&mut self) {
+        self.generation = self.generation.wrapping_add(1);
+
+        // Entries of a wrapped around generation would be current again
+        if self.generation == 0 {
+            self.slots.iter_mut().for_each(|slot| *slot = None);
         }
-        *option = Some(entry);
     }
 
-    fn get(&self, zobrist_hash: ZobristHash) -> Option<&TtEntry> {
-        let array_hash = Self::array_hash(zobrist_hash);
-        self.entries[array_hash].as_ref().filter(|entry| entry.zobrist_hash == zobrist_hash)
+    /// Depth-preferred replacement, an entry only replaces an entry of the current generation of at least its depth
+    pub fn put(&mut self, zobrist_hash: ZobristHash, entry: TtEntry) {
+        let index = self.index(zobrist_hash);
+        let is_current = self.is_current(&self.slots[index]);
+        let slot = &mut self.slots[index];
+
+        match slot {
+            Some(existing) if is_current && existing.entry.depth > entry.depth => {}
+            _ => *slot = Some(Slot { generation: self.generation, entry }),
+        }
     }
 
-    fn len(&self) -> usize {
-        self.load
+    pub fn get(&self, zobrist_hash: ZobristHash) -> Option<&TtEntry> {
+        let slot = &self.slots[self.index(zobrist_hash)];
+
+        if !self.is_current(slot) {
+            return None;
+        }
+
+        slot.as_ref().map(|slot| &slot.entry).filter(|entry| entry.zobrist_hash == zobrist_hash)
     }
 
-    fn load_factor(&self) -> f32 {
-        self.len() as f32 / N as f32
+    /// Estimated from the first slots of the table
+    pub fn load_factor(&self) -> f32 {
+        let sample_size = LOAD_FACTOR_SAMPLE_SIZE.min(self.capacity);
+        let current = self.slots[..sample_size].iter().filter(|&slot| self.is_current(slot)).count();
+
+        current as f32 / sample_size as f32
     }
 }
 
-pub struct HashMapTranspositionTable {
-    hash_table: HashTable<ZobristHash, TtEntry>,
+/// [`ArrayTranspositionTable`] shared between search threads. The slots are split into shards of consecutive indices,
+/// each behind its own lock, so threads rarely wait for each other. Hashes map to the same slots as in a single
+/// [`ArrayTranspositionTable`] of the same capacity.
+pub struct SharedTranspositionTable {
+    shards: Vec<Mutex<ArrayTranspositionTable>>,
+    capacity: usize,
+    /// Shift of a slot index to the index of its shard
+    shard_shift: u32,
 }
 
-impl HashMapTranspositionTable {
+impl SharedTranspositionTable {
+    /// Create a table with `capacity` rounded down to a power of two slots
     pub fn new(capacity: usize) -> Self {
-        Self { hash_table: HashTable::new(capacity) }
+        let capacity = if capacity == 0 { 1 } else { 1 << capacity.ilog2() };
+        let shard_capacity = capacity / SHARDS.min(capacity);
+        let shards = (0..capacity / shard_capacity).map(|_| Mutex::new(ArrayTranspositionTable::new(shard_capacity))).collect();
+
+        Self { shards, capacity, shard_shift: shard_capacity.ilog2() }
+    }
+
+    /// Create a table holding as many entries as fit into `size_mb` megabytes
+    pub fn with_size_mb(size_mb: usize) -> Self {
+        Self::new(ArrayTranspositionTable::capacity_for_size_mb(size_mb))
+    }
+
+    pub const fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    fn shard(&self, zobrist_hash: ZobristHash) -> MutexGuard<'_, ArrayTranspositionTable> {
+        let index = (zobrist_hash as usize & (self.capacity - 1)) >> self.shard_shift;
+
+        // The tables stay consistent if a thread panics while holding the lock
+        self.shards[index].lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn clear(&self) {
+        for shard in &self.shards {
+            shard.lock().unwrap_or_else(PoisonError::into_inner).clear();
+        }
     }
-}
 
-impl TranspositionTable for HashMapTranspositionTable {
-    fn clear(&mut self) {
-        self.hash_table.clear();
+    pub fn put(&self, zobrist_hash: ZobristHash, entry: TtEntry) {
+        self.shard(zobrist_hash).put(zobrist_hash, entry);
     }
 
-    fn put(&mut self, zobrist_hash: ZobristHash, entry: TtEntry) {
-        self.hash_table.put(zobrist_hash, entry);
+    pub fn get(&self, zobrist_hash: ZobristHash) -> Option<TtEntry> {
+        self.shard(zobrist_hash).get(zobrist_hash).copied()
     }
 
-    fn get(&self, zobrist_hash: ZobristHash) -> Option<&TtEntry> {
-        self.hash_table.get(zobrist_hash)
+    /// Estimated from the first slots of the first shard, hashes are spread evenly over the shards
+    pub fn load_factor(&self) -> f32 {
+        self.shards[0].lock().unwrap_or_else(PoisonError::into_inner).load_factor()
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::table::transposition::{ArrayTranspositionTable, NodeType, SharedTranspositionTable, TtEntry};
 
-    fn len(&self) -> usize {
-        self.hash_table.len()
+    fn entry(zobrist_hash: u64, depth: usize) -> TtEntry {
+        TtEntry::new(None, zobrist_hash, depth, 0, NodeType::Exact)
     }
 
-    fn load_factor(&self) -> f32 {
-        self.hash_table.load_factor()
+    #[test]
+    fn test_array_table() {
+        let mut sut = ArrayTranspositionTable::new(100);
+
+        assert_eq!(sut.slots.len(), 64);
+        assert!(sut.get(1).is_none());
+
+        sut.put(1, entry(1, 5));
+        sut.put(2, entry(2, 5));
+        assert_eq!(sut.get(1).map(|entry| entry.depth), Some(5));
+        assert_eq!(sut.load_factor(), 2.0 / 64.0);
+
+        // Same slot as 1, shallower entries don't replace deeper ones
+        sut.put(65, entry(65, 2));
+        assert!(sut.get(65).is_none());
+        assert_eq!(sut.get(1).map(|entry| entry.depth), Some(5));
+
+        sut.put(65, entry(65, 6));
+        assert!(sut.get(1).is_none());
+        assert_eq!(sut.get(65).map(|entry| entry.depth), Some(6));
+        assert_eq!(sut.load_factor(), 2.0 / 64.0);
+
+        sut.clear();
+        assert_eq!(sut.load_factor(), 0.0);
+        assert!(sut.get(65).is_none());
+
+        // Entries of previous generations are replaced regardless of depth
+        sut.put(1, entry(1, 1));
+        assert_eq!(sut.get(1).map(|entry| entry.depth), Some(1));
+        assert_eq!(sut.load_factor(), 1.0 / 64.0);
+    }
+
+    #[test]
+    fn test_array_table_capacity_for_size_mb() {
+        let capacity = ArrayTranspositionTable::capacity_for_size_mb(16);
+
+        assert!(capacity.is_power_of_two());
+        assert_eq!(SharedTranspositionTable::with_size_mb(16).capacity(), capacity);
+        assert_eq!(ArrayTranspositionTable::capacity_for_size_mb(0), 1);
+    }
+
+    #[test]
+    fn test_shared_table() {
+        let sut = SharedTranspositionTable::new(1000);
+
+        assert_eq!(sut.capacity(), 512);
+        assert_eq!(sut.load_factor(), 0.0);
+
+        sut.put(1, entry(1, 5));
+        sut.put(300, entry(300, 5));
+        assert_eq!(sut.get(1).map(|entry| entry.depth), Some(5));
+        assert_eq!(sut.get(300).map(|entry| entry.depth), Some(5));
+
+        // Same slot as 1, shallower entries don't replace deeper ones
+        sut.put(513, entry(513, 2));
+        assert!(sut.get(513).map(|entry| entry.depth).is_none());
+
+        sut.put(513, entry(513, 6));
+        assert!(sut.get(1).map(|entry| entry.depth).is_none());
+        assert_eq!(sut.get(513).map(|entry| entry.depth), Some(6));
+
+        // Sampled from the first shard, which holds slot 1 of its 8 slots
+        assert_eq!(sut.load_factor(), 1.0 / 8.0);
+
+        sut.clear();
+        assert_eq!(sut.load_factor(), 0.0);
+        assert!(sut.get(300).map(|entry| entry.depth).is_none());
+        assert_eq!(SharedTranspositionTable::new(4).capacity(), 4);
     }
 }
@@ -0,0 +1,112 @@
+use std::env::split_paths;
+use std::fmt::{Display, Formatter};
+use std::fs::read_dir;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Syzygy tablebase files found in the directories of the `SyzygyPath` option
+#[derive(Default, Debug)]
+pub struct Tablebases {
+    /// Material keys of the found tables, e.g. `KQvK`
+    tables: Vec<String>,
+}
+
+impl Tablebases {
+    /// Load the tables from `path`, which may contain multiple directories separated like the `PATH` variable
+    pub fn load(path: &str) -> Result<Self, TablebaseError> {
+        let mut tables = Vec::new();
+
+        for directory in split_paths(path) {
+            Self::load_directory(&directory, &mut tables)?;
+        }
+
+        if tables.is_empty() {
+            return Err(TablebaseError::NoTablesFound(path.to_string()));
+        }
+
+        tables.sort();
+        tables.dedup();
+
+        Ok(Self { tables })
+    }
+
+    fn load_directory(directory: &Path, tables: &mut Vec<String>) -> Result<(), TablebaseError> {
+        if !directory.is_dir() {
+            return Err(TablebaseError::NotADirectory(directory.to_path_buf()));
+        }
+
+        let entries = read_dir(directory).map_err(|error| TablebaseError::Io(directory.to_path_buf(), error))?;
+
+        for entry in entries.flatten() {
+            let path = entry.path();
+
+            let is_table = path.extension().is_some_and(|extension| extension == "rtbw" || extension == "rtbz");
+
+            if let (true, Some(key)) = (is_table, path.file_stem().and_then(|stem| stem.to_str())) {
+                tables.push(key.to_string());
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.tables.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tables.is_empty()
+    }
+
+    /// The largest number of pieces, including kings, covered by any table
+    pub fn max_pieces(&self) -> usize {
+        self.tables.iter().map(|key| key.chars().filter(char::is_ascii_alphabetic).count() - 1).max().unwrap_or(0)
+    }
+}
+
+#[derive(Debug)]
+pub enum TablebaseError {
+    NotADirectory(PathBuf),
+    NoTablesFound(String),
+    Io(PathBuf, io::Error),
+}
+
+impl Display for TablebaseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotADirectory(path) => write!(f, "{} is not a directory", path.display()),
+            Self::NoTablesFound(path) => write!(f, "no tables found in {}", path),
+            Self::Io(path, error) => write!(f, "failed to read {}: {}", path.display(), error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::env::temp_dir;
+    use std::fs::{create_dir_all, File};
+
+    use crate::engine::tablebase::{TablebaseError, Tablebases};
+
+    #[test]
+    fn test_load() {
+        let directory = temp_dir().join("inkayaku_tablebase_test_load");
+        create_dir_all(&directory).unwrap();
+
+        for file in ["KQvK.rtbw", "KQvK.rtbz", "KRPvKR.rtbw", "readme.txt"] {
+            File::create(directory.join(file)).unwrap();
+        }
+
+        let tablebases = Tablebases::load(directory.to_str().unwrap()).unwrap();
+
+        assert_eq!(tablebases.len(), 2);
+        assert_eq!(tablebases.max_pieces(), 5);
+    }
+
+    #[test]
+    fn test_load_invalid_path() {
+        let directory = temp_dir().join("inkayaku_tablebase_test_does_not_exist");
+
+        assert!(matches!(Tablebases::load(directory.to_str().unwrap()), Err(TablebaseError::NotADirectory(_))));
+    }
+}
@@ -1,34 +1,43 @@
 use std::cmp::max;
 use inkayaku_board::constants::ZobristHash;
 
+/// Zobrist hashes of the positions of the current game, indexed by ply clock. Grows with the game, so there is no
+/// limit on its length.
+#[derive(Clone)]
 pub struct ZobristHistory {
-    history: [ZobristHash; 5000],
+    history: Vec<ZobristHash>,
 }
 
 impl ZobristHistory {
+    /// Plies reserved up front, enough for most games and searches without growing
+    const INITIAL_CAPACITY: usize = 1024;
+
     pub fn set(&mut self, index: u16, zobrist_hash: ZobristHash) {
-        self.history[index as usize] = zobrist_hash;
-    }
+        let index = index as usize;
 
-    pub fn count_repetitions(&self, start_index: u16, halfmove_clock: u16) -> usize {
-        if start_index < 4 {
-            return 0;
+        if index >= self.history.len() {
+            self.history.resize(index + 1, 0);
         }
 
-        let mut current_index = start_index as i32 - 4;
-        let mut repetitions = 1_usize;
-        let zobrist = self.history[start_index as usize];
+        self.history[index] = zobrist_hash;
+    }
+
+    fn get(&self, index: usize) -> ZobristHash {
+        self.history.get(index).copied().unwrap_or_default()
+    }
 
-        let min_index = max(0, start_index as i32 - halfmove_clock as i32);
+    /// Count the occurrences of the position at `index`, including the occurrence at `index` itself. Only positions
+    /// since the last capture or pawn move, `halfmove_clock` plies back, can repeat. Counting stops at three.
+    pub fn count_repetitions(&self, index: u16, halfmove_clock: u32) -> usize {
+        let zobrist = self.get(index as usize);
+        let min_index = max(0, i64::from(index) - i64::from(halfmove_clock));
 
-        while current_index >= min_index {
-            let current_zobrist = self.history[current_index as usize];
-            if current_zobrist == zobrist {
-                repetitions += 1;
+        let mut repetitions = 1;
+        let mut current_index = i64::from(index) - 4;
 
-                if repetitions >= 3 {
-                    return 3;
-                }
+        while current_index >= min_index && repetitions < 3 {
+            if self.get(current_index as usize) == zobrist {
+                repetitions += 1;
             }
 
             current_index -= 2;
@@ -36,11 +45,16 @@ impl ZobristHistory {
 
         repetitions
     }
+
+    /// The position at `index` occurs for the third time
+    pub fn is_threefold_repetition(&self, index: u16, halfmove_clock: u32) -> bool {
+        self.count_repetitions(index, halfmove_clock) >= 3
+    }
 }
 
 impl Default for ZobristHistory {
     fn default() -> Self {
-        Self { history: [0; 5000] }
+        Self { history: Vec::with_capacity(Self::INITIAL_CAPACITY) }
     }
 }
 
@@ -67,4 +81,58 @@ mod test {
         assert_ne!(history.count_repetitions(10, 7), 3);
         assert_ne!(history.count_repetitions(10, 6), 3);
     }
+
+    #[test]
+    fn test_count_repetitions_includes_current_position() {
+        let mut history = ZobristHistory::default();
+        history.set(0, 1);
+        history.set(1, 2);
+        history.set(2, 3);
+
+        assert_eq!(history.count_repetitions(0, 0), 1);
+        assert_eq!(history.count_repetitions(2, 2), 1);
+    }
+
+    #[test]
+    fn test_twofold_is_not_threefold() {
+        let mut history = ZobristHistory::default();
+        history.set(0, 1);
+        history.set(1, 2);
+        history.set(2, 3);
+        history.set(3, 4);
+        history.set(4, 1);
+
+        assert_eq!(history.count_repetitions(4, 4), 2);
+        assert!(!history.is_threefold_repetition(4, 4));
+
+        history.set(5, 2);
+        history.set(6, 3);
+        history.set(7, 4);
+        history.set(8, 1);
+
+        assert_eq!(history.count_repetitions(8, 8), 3);
+        assert!(history.is_threefold_repetition(8, 8));
+    }
+
+    #[test]
+    fn test_long_game() {
+        let mut history = ZobristHistory::default();
+
+        // Distinct positions
+        for index in 0..600 {
+            history.set(index, u64::from(index) + 1);
+        }
+
+        // A knight moving back and forth since ply 600
+        for index in 600..=608 {
+            history.set(index, if index % 4 == 0 { 1000 } else { 1000 + u64::from(index % 4) });
+        }
+
+        assert_eq!(history.count_repetitions(608, 8), 3);
+        assert_eq!(history.count_repetitions(604, 4), 2);
+        assert_eq!(history.count_repetitions(599, 9), 1);
+
+        history.set(10_000, 1);
+        assert_eq!(history.count_repetitions(10_000, 100), 1);
+    }
 }
@@ -9,7 +9,7 @@ use serde_json::Value;
 use surf::{Client, Request, RequestBuilder, Response, StatusCode};
 use surf::http::Method;
 
-use crate::api::bot_event_response::BotEvent;
+use crate::api::bot_event_response::{BotEvent, ChallengeEventDeclineReason};
 use crate::api::bot_game_state_response::BotGameState;
 
 pub mod response;
@@ -208,8 +208,16 @@ impl BotApi {
 
     /// Resign a game
     /// https://lichess.org/api#tag/Bot/operation/botGameResign
-    pub async fn post_resign_game(&self) {
-        todo!();
+    pub async fn post_resign_game(&self, game_id: &str) -> Result<(), RequestError> {
+        let url = format!("/api/bot/game/{}/resign", game_id);
+        self.client.post(&url, None).await
+    }
+
+    /// Create, accept or decline draw offers
+    /// https://lichess.org/api#tag/Bot/operation/botGameDraw
+    pub async fn post_handle_draw_offer(&self, game_id: &str, accept: bool) -> Result<(), RequestError> {
+        let url = format!("/api/bot/game/{}/draw/{}", game_id, if accept { "yes" } else { "no" });
+        self.client.post(&url, None).await
     }
 }
 
@@ -236,9 +244,10 @@ impl BotApi {
 
     /// Decline a challenge
     /// https://lichess.org/api#tag/Challenges/operation/challengeDecline
-    pub async fn post_decline_challenge(&self, challenge_id: &str) -> Result<(), RequestError> {
+    pub async fn post_decline_challenge(&self, challenge_id: &str, reason: &ChallengeEventDeclineReason) -> Result<(), RequestError> {
         let url = format!("/api/challenge/{}/decline", challenge_id);
-        self.client.post(&url, None).await
+        let body = serde_json::json!({ "reason": reason });
+        self.client.post(&url, Some(&body)).await
     }
 
     /// Cancel a challenge
@@ -175,7 +175,7 @@ pub enum ChallengeEventDirection {
     Out,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Eq, PartialEq)]
 #[serde(rename_all = "lowercase")]
 pub enum ChallengeEventDeclineReason {
     Generic,
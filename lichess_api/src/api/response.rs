@@ -8,7 +8,7 @@ pub struct VariantFull {
     pub short: String,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, Eq, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub enum VariantKey {
     Standard,
@@ -76,7 +76,8 @@ pub enum GameStatusKey {
     VariantEnd,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+/// Ordered from fastest to slowest
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd)]
 #[serde(rename_all = "camelCase")]
 pub enum SpeedKey {
     UltraBullet,
@@ -1,7 +1,7 @@
 use std::cell::{RefCell, RefMut};
 use std::str::FromStr;
 
-use std::sync::{Arc};
+use std::sync::{Arc, Mutex};
 use std::sync::mpsc::{channel, Receiver, Sender};
 use std::thread;
 use std::time::Duration;
@@ -14,13 +14,17 @@ use inkayaku_board::Bitboard;
 use inkayaku_core::constants::Color;
 use inkayaku_core::fen::Fen;
 use inkayaku_engine_core::Engine;
-use inkayaku_lichess_api::api::bot_event_response::ChallengeEventDeclineReason;
-use inkayaku_lichess_api::api::bot_game_state_response::{BotGameState, Clock, GameStateHolder};
+use inkayaku_lichess_api::api::bot_game_state_response::{BotGameState, GameStateHolder};
 use inkayaku_lichess_api::api::BotApi;
-use inkayaku_lichess_api::api::response::{GameStatusKey, SpeedKey, VariantFull, VariantKey};
-use inkayaku_uci::{UciEngine, Go, UciCommand, UciMove, UciTxCommand};
+use inkayaku_lichess_api::api::response::GameStatusKey;
+use inkayaku_uci::{UciEngine, Go, Score, UciCommand, UciMove, UciTxCommand};
 use inkayaku_uci::command::CommandUciTx;
 
+/// Resign if the score of the bot's search was at most this for [`RESIGN_PLIES`] consecutive moves
+const RESIGN_THRESHOLD_CENTIPAWNS: i32 = -1000;
+const RESIGN_PLIES: usize = 6;
+/// Accept draw offers if the score of the last search is at most this far from equal
+const DRAW_ACCEPT_THRESHOLD_CENTIPAWNS: i32 = 25;
 
 pub struct GameThread {
     bot_id: String,
@@ -28,12 +32,18 @@ pub struct GameThread {
     api: Arc<BotApi>,
     engine: RefCell<Engine<CommandUciTx>>,
     game_state: RefCell<GameState>,
+    /// Scores of the bot's searches, from its own perspective
+    scores: Arc<Mutex<Vec<Score>>>,
 }
 
 #[derive(Default)]
 struct GameState {
     initial_fen: Option<Fen>,
     self_color: Option<Color>,
+    /// The current position, `None` until the first state of the game has been applied
+    board: Option<Bitboard>,
+    /// Number of moves of the game already made on `board`
+    applied_moves: usize,
 }
 
 impl GameState {
@@ -44,14 +54,56 @@ impl GameState {
     fn self_color(&self) -> &Color {
         self.self_color.as_ref().unwrap()
     }
+
+    /// Reset to a new game starting from `initial_fen`
+    fn reset(&mut self, initial_fen: Fen, self_color: Color) {
+        self.initial_fen = Some(initial_fen);
+        self.self_color = Some(self_color);
+        self.board = None;
+        self.applied_moves = 0;
+    }
+
+    /// Bring the board up to date with `moves`, the complete move list of the game, by making only the moves not yet
+    /// applied. The board is rebuilt from the initial position if the move list shrunk due to a takeback. Returns
+    /// `false` if `moves` contains no new moves, e.g. for a duplicate state.
+    fn update_board(&mut self, moves: &[String]) -> bool {
+        match &mut self.board {
+            Some(_) if moves.len() == self.applied_moves => {
+                return false;
+            }
+            Some(board) if moves.len() > self.applied_moves => {
+                Self::make_moves(board, &moves[self.applied_moves..]);
+            }
+            _ => {
+                let mut board = Bitboard::from(self.initial_fen());
+                Self::make_moves(&mut board, moves);
+                self.board = Some(board);
+            }
+        }
+
+        self.applied_moves = moves.len();
+        true
+    }
+
+    fn make_moves(board: &mut Bitboard, moves: &[String]) {
+        for uci in moves {
+            let mv = board.find_uci(uci).unwrap();
+            board.make(mv);
+        }
+    }
+
+    fn is_my_turn(&self) -> bool {
+        self.board.as_ref().is_some_and(|board| self.self_color().index == board.turn)
+    }
 }
 
 impl GameThread {
     pub fn new(bot_id: &str, game_id: &str, api: BotApi) -> Self {
         let api = Arc::new(api);
-        let engine = Self::spawn_engine(api.clone(), game_id);
+        let scores = Arc::new(Mutex::new(Vec::new()));
+        let engine = Self::spawn_engine(api.clone(), game_id, scores.clone());
 
-        Self { bot_id: bot_id.to_string(), game_id: game_id.to_string(), api, engine: RefCell::new(engine), game_state: RefCell::new(GameState::default()) }
+        Self { bot_id: bot_id.to_string(), game_id: game_id.to_string(), api, engine: RefCell::new(engine), game_state: RefCell::new(GameState::default()), scores }
     }
 
     pub async fn start(self) {
@@ -60,48 +112,46 @@ impl GameThread {
         pin_mut!(stream);
 
         while let Some(state) = stream.next().await {
-            match state {
+            let state = match state {
                 BotGameState::GameFull { state, id, variant, speed, perf, rated, created_at, white, black, initial_fen, clock, days_per_turn, tournament_id } => {
-                    let fen = Fen::from_str(&initial_fen).unwrap();
+                    // Lichess sends "startpos" instead of a FEN for games from the standard starting position
+                    let fen = if initial_fen == "startpos" { Fen::default() } else { Fen::from_str(&initial_fen).unwrap() };
 
-                    self.game_state.borrow_mut().self_color = Some(if white.id == self.bot_id {
+                    let self_color = if white.id == self.bot_id {
                         Color::WHITE
                     } else if black.id == self.bot_id {
                         Color::BLACK
                     } else {
-                        panic!();
-                    });
+                        println!("Bot {} is not playing in game {}", self.bot_id, self.game_id);
+                        break;
+                    };
 
-                    self.game_state.borrow_mut().initial_fen = Some(fen);
+                    self.game_state.borrow_mut().reset(fen, self_color);
                     self.initialize_engine();
-                    if !self.accept_state(state) {
-                        return;
-                    };
+                    state
                 }
-                BotGameState::GameState { state, .. } => {
-                    if !self.accept_state(state) {
-                        return;
-                    };
-                }
-                BotGameState::ChatLine { room, username, text } => {}
-                BotGameState::OpponentGone { gone, claim_win_in_seconds } => {}
+                BotGameState::GameState { state, .. } => state,
+                BotGameState::ChatLine { .. } | BotGameState::OpponentGone { .. } => continue,
+            };
+
+            let is_draw_offered = self.is_draw_offered(&state);
+
+            if !self.accept_state(state) {
+                break;
             }
-        }
-    }
 
-    fn decide_accept(&self, variant: VariantFull, speed: SpeedKey, clock: Option<Clock>, initial_fen: &Fen) -> Option<ChallengeEventDeclineReason> {
-        if initial_fen.ne(&Fen::default()) || !matches!(variant.key, VariantKey::Standard) {
-            Some(ChallengeEventDeclineReason::Standard)
-        } else {
-            match speed {
-                SpeedKey::Bullet => {
-                    None
-                }
-                SpeedKey::UltraBullet | SpeedKey::Blitz | SpeedKey::Rapid | SpeedKey::Classical | SpeedKey::Correspondence => {
-                    Some(ChallengeEventDeclineReason::Standard)
+            if is_draw_offered {
+                let accept = self.should_accept_draw();
+                println!("{} draw offer in game {}", if accept { "Accepting" } else { "Declining" }, self.game_id);
+
+                if let Err(error) = self.api.post_handle_draw_offer(&self.game_id, accept).await {
+                    println!("Failed to answer draw offer in game {}: {:?}", self.game_id, error);
                 }
             }
         }
+
+        self.stop_engine();
+        println!("END GAME THREAD");
     }
 
     fn initialize_engine(&self) {
@@ -109,13 +159,37 @@ impl GameThread {
         engine.accept(UciCommand::UciNewGame);
     }
 
+    /// Stop any running search and shut down the engine's search thread, which also ends the thread posting its moves
+    fn stop_engine(&self) {
+        let mut engine = self.engine();
+        engine.accept(UciCommand::Stop);
+        engine.accept(UciCommand::Quit);
+    }
+
+    fn is_draw_offered(&self, state: &GameStateHolder) -> bool {
+        let opponent_offers_draw = if *self.game_state.borrow().self_color() == Color::WHITE { state.bdraw } else { state.wdraw };
+        opponent_offers_draw.unwrap_or(false)
+    }
+
+    fn should_accept_draw(&self) -> bool {
+        let game_state = self.game_state.borrow();
+        let scores = self.scores.lock().unwrap();
+        game_state.board.as_ref().is_some_and(|board| should_accept_draw(board, scores.last()))
+    }
+
+    /// Start a search if `state` contains new moves and it is our turn. Returns `false` if the game is over.
     fn accept_state(&self, state: GameStateHolder) -> bool {
         let mut engine = self.engine();
-        let moves = state.moves.iter().map(|m| UciMove::from_str(m).unwrap()).collect();
 
         match state.status {
             GameStatusKey::Created | GameStatusKey::Started => {
-                if self.is_my_turn(&moves) {
+                let is_my_turn = {
+                    let mut game_state = self.game_state.borrow_mut();
+                    game_state.update_board(&state.moves) && game_state.is_my_turn()
+                };
+
+                if is_my_turn {
+                    let moves = state.moves.iter().map(|m| UciMove::from_str(m).unwrap()).collect();
                     let fen = self.game_state.borrow().initial_fen().clone();
                     engine.accept(UciCommand::PositionFrom { fen, moves });
                     engine.accept(UciCommand::Go {
@@ -134,42 +208,55 @@ impl GameThread {
         }
     }
 
-    fn is_my_turn(&self, moves: &Vec<UciMove>) -> bool {
-        let mut bitboard: Bitboard = self.game_state.borrow().initial_fen.clone().unwrap().into();
-
-        for mv in moves {
-            bitboard.make_uci(&mv.to_string()).unwrap();
-        }
-
-        self.game_state.borrow().self_color().index == bitboard.turn
-    }
-
     fn engine(&self) -> RefMut<Engine<CommandUciTx>> {
         self.engine.borrow_mut()
     }
 
-    fn spawn_engine(api: Arc<BotApi>, game_id: &str) -> Engine<CommandUciTx> {
+    fn spawn_engine(api: Arc<BotApi>, game_id: &str, scores: Arc<Mutex<Vec<Score>>>) -> Engine<CommandUciTx> {
         let (tx, rx): (Sender<UciTxCommand>, _) = channel();
-        Self::spawn_engine_rx_thread(rx, api, game_id);
+        Self::spawn_engine_rx_thread(rx, api, game_id, scores);
 
         Engine::new(Arc::new(CommandUciTx::new(tx)), false)
     }
 
-    fn spawn_engine_rx_thread(rx: Receiver<UciTxCommand>, api: Arc<BotApi>, game_id: &str) {
+    fn spawn_engine_rx_thread(rx: Receiver<UciTxCommand>, api: Arc<BotApi>, game_id: &str, scores: Arc<Mutex<Vec<Score>>>) {
         let game_id = game_id.to_string();
 
         thread::spawn(move || {
             let send_uci_move = |uci_move: UciMove| {
-                block_on(api.post_bot_move(&game_id, &uci_move.to_string(), false)).unwrap();
+                if let Err(error) = block_on(api.post_bot_move(&game_id, &uci_move.to_string(), false)) {
+                    println!("Failed to post move {} in game {}: {:?}", uci_move, game_id, error);
+                }
+            };
+
+            let resign = || {
+                println!("Resigning game {}", game_id);
+
+                if let Err(error) = block_on(api.post_resign_game(&game_id)) {
+                    println!("Failed to resign game {}: {:?}", game_id, error);
+                }
             };
 
+            let mut last_score = None;
+
             while let Ok(command) = rx.recv() {
                 match command {
                     UciTxCommand::BestMove { best_move: Some(uci_move), .. } => {
-                        send_uci_move(uci_move);
+                        let is_hopeless = {
+                            let mut scores = scores.lock().unwrap();
+                            scores.extend(last_score.take());
+                            should_resign(&scores)
+                        };
+
+                        if is_hopeless {
+                            resign();
+                        } else {
+                            send_uci_move(uci_move);
+                        }
                     }
                     UciTxCommand::Info { info } => {
                         println!("{:?}", info);
+                        last_score = info.score.or(last_score);
                     }
                     _ => {}
                 };
@@ -177,3 +264,90 @@ impl GameThread {
         });
     }
 }
+
+/// Whether the last [`RESIGN_PLIES`] scores are all hopeless
+fn should_resign(scores: &[Score]) -> bool {
+    scores.len() >= RESIGN_PLIES && scores[scores.len() - RESIGN_PLIES..].iter().all(|score| match *score {
+        Score::Centipawn { score } | Score::CentipawnBounded { score, .. } => score <= RESIGN_THRESHOLD_CENTIPAWNS,
+        Score::Mate { mate_in } => mate_in < 0,
+    })
+}
+
+/// Whether the position on `board` is dead equal according to `last_score` or can't be won by either side
+fn should_accept_draw(board: &Bitboard, last_score: Option<&Score>) -> bool {
+    board.is_insufficient_material() || matches!(last_score, Some(Score::Centipawn { score }) if score.abs() <= DRAW_ACCEPT_THRESHOLD_CENTIPAWNS)
+}
+
+#[cfg(test)]
+mod tests {
+    use inkayaku_board::Bitboard;
+    use inkayaku_core::constants::Color;
+    use inkayaku_core::fen::Fen;
+
+    use inkayaku_uci::Score;
+
+    use crate::bot::{GameState, RESIGN_PLIES, should_accept_draw, should_resign};
+
+    fn moves(uci: &str) -> Vec<String> {
+        uci.split_whitespace().map(str::to_string).collect()
+    }
+
+    fn fen(game_state: &GameState) -> Fen {
+        Fen::from(game_state.board.as_ref().unwrap())
+    }
+
+    fn expected_fen(uci: &str) -> Fen {
+        let mut board = Bitboard::default();
+        for mv in moves(uci) {
+            board.make_uci(&mv).unwrap();
+        }
+        Fen::from(&board)
+    }
+
+    #[test]
+    fn test_update_board() {
+        let mut sut = GameState::default();
+        sut.reset(Fen::default(), Color::BLACK);
+
+        assert!(sut.update_board(&[]));
+        assert!(!sut.is_my_turn());
+
+        assert!(sut.update_board(&moves("e2e4")));
+        assert!(sut.is_my_turn());
+        assert!(!sut.update_board(&moves("e2e4")));
+
+        assert!(sut.update_board(&moves("e2e4 e7e5 g1f3")));
+        assert_eq!(sut.applied_moves, 3);
+        assert_eq!(fen(&sut), expected_fen("e2e4 e7e5 g1f3"));
+
+        // Takeback
+        assert!(sut.update_board(&moves("e2e4 e7e5")));
+        assert_eq!(sut.applied_moves, 2);
+        assert_eq!(fen(&sut), expected_fen("e2e4 e7e5"));
+        assert!(!sut.is_my_turn());
+    }
+
+    #[test]
+    fn test_should_resign() {
+        let hopeless = Score::Centipawn { score: -1500 };
+
+        assert!(!should_resign(&[hopeless; RESIGN_PLIES - 1]));
+        assert!(should_resign(&[hopeless; RESIGN_PLIES]));
+        assert!(should_resign(&[Score::Mate { mate_in: -3 }; RESIGN_PLIES]));
+
+        let mut recovered = vec![hopeless; RESIGN_PLIES];
+        recovered.push(Score::Centipawn { score: -200 });
+        assert!(!should_resign(&recovered));
+    }
+
+    #[test]
+    fn test_should_accept_draw() {
+        let board = Bitboard::default();
+
+        assert!(should_accept_draw(&board, Some(&Score::Centipawn { score: 10 })));
+        assert!(!should_accept_draw(&board, Some(&Score::Centipawn { score: 150 })));
+        assert!(!should_accept_draw(&board, Some(&Score::Mate { mate_in: -5 })));
+        assert!(!should_accept_draw(&board, None));
+        assert!(should_accept_draw(&Bitboard::from_fen_string_unchecked("8/8/4k3/8/8/3NK3/8/8 w - - 0 1"), None));
+    }
+}
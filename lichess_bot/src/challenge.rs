@@ -0,0 +1,91 @@
+use inkayaku_lichess_api::api::bot_event_response::{ChallengeEventDeclineReason, ChallengeEventInfo};
+use inkayaku_lichess_api::api::response::{SpeedKey, VariantKey};
+
+/// Determines which challenges the bot accepts
+pub struct ChallengePolicy {
+    pub variants: Vec<VariantKey>,
+    pub speeds: Vec<SpeedKey>,
+    pub accept_rated: bool,
+    pub accept_casual: bool,
+}
+
+impl Default for ChallengePolicy {
+    /// Standard chess from bullet to classical, rated or casual
+    fn default() -> Self {
+        Self {
+            variants: vec![VariantKey::Standard],
+            speeds: vec![SpeedKey::Bullet, SpeedKey::Blitz, SpeedKey::Rapid, SpeedKey::Classical],
+            accept_rated: true,
+            accept_casual: true,
+        }
+    }
+}
+
+impl ChallengePolicy {
+    /// Returns the reason to decline `challenge` with, or `None` if it should be accepted
+    pub fn decline_reason(&self, challenge: &ChallengeEventInfo) -> Option<ChallengeEventDeclineReason> {
+        self.decline_reason_for(challenge.variant.key, challenge.speed, challenge.rated)
+    }
+
+    fn decline_reason_for(&self, variant: VariantKey, speed: SpeedKey, rated: bool) -> Option<ChallengeEventDeclineReason> {
+        if !self.variants.contains(&variant) {
+            return Some(if self.variants == [VariantKey::Standard] {
+                ChallengeEventDeclineReason::Standard
+            } else {
+                ChallengeEventDeclineReason::Variant
+            });
+        }
+
+        if !self.speeds.contains(&speed) {
+            return Some(match (self.speeds.iter().min(), self.speeds.iter().max()) {
+                (Some(&fastest), _) if speed < fastest => ChallengeEventDeclineReason::TooFast,
+                (_, Some(&slowest)) if speed > slowest => ChallengeEventDeclineReason::TooSlow,
+                _ => ChallengeEventDeclineReason::TimeControl,
+            });
+        }
+
+        if rated && !self.accept_rated {
+            return Some(ChallengeEventDeclineReason::Casual);
+        }
+
+        if !rated && !self.accept_casual {
+            return Some(ChallengeEventDeclineReason::Rated);
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use inkayaku_lichess_api::api::bot_event_response::ChallengeEventDeclineReason;
+    use inkayaku_lichess_api::api::response::{SpeedKey, VariantKey};
+
+    use crate::challenge::ChallengePolicy;
+
+    #[test]
+    fn test_default() {
+        let sut = ChallengePolicy::default();
+
+        assert_eq!(sut.decline_reason_for(VariantKey::Standard, SpeedKey::Blitz, true), None);
+        assert_eq!(sut.decline_reason_for(VariantKey::Standard, SpeedKey::Bullet, false), None);
+        assert_eq!(sut.decline_reason_for(VariantKey::Chess960, SpeedKey::Blitz, true), Some(ChallengeEventDeclineReason::Standard));
+        assert_eq!(sut.decline_reason_for(VariantKey::Standard, SpeedKey::UltraBullet, true), Some(ChallengeEventDeclineReason::TooFast));
+        assert_eq!(sut.decline_reason_for(VariantKey::Standard, SpeedKey::Correspondence, true), Some(ChallengeEventDeclineReason::TooSlow));
+    }
+
+    #[test]
+    fn test_custom() {
+        let sut = ChallengePolicy {
+            variants: vec![VariantKey::Standard, VariantKey::Chess960],
+            speeds: vec![SpeedKey::Bullet, SpeedKey::Rapid],
+            accept_rated: false,
+            accept_casual: true,
+        };
+
+        assert_eq!(sut.decline_reason_for(VariantKey::Chess960, SpeedKey::Rapid, false), None);
+        assert_eq!(sut.decline_reason_for(VariantKey::Atomic, SpeedKey::Rapid, false), Some(ChallengeEventDeclineReason::Variant));
+        assert_eq!(sut.decline_reason_for(VariantKey::Standard, SpeedKey::Blitz, false), Some(ChallengeEventDeclineReason::TimeControl));
+        assert_eq!(sut.decline_reason_for(VariantKey::Standard, SpeedKey::Bullet, true), Some(ChallengeEventDeclineReason::Casual));
+    }
+}
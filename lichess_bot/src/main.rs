@@ -8,8 +8,10 @@ use inkayaku_lichess_api::api::{BotApi, SurfWebClient};
 use inkayaku_lichess_api::api::bot_event_response::BotEvent;
 
 use crate::bot::GameThread;
+use crate::challenge::ChallengePolicy;
 
 mod bot;
+mod challenge;
 
 #[tokio::main]
 async fn main() {
@@ -20,6 +22,8 @@ async fn main() {
     let swc = SurfWebClient::new(&token, client);
     let api = BotApi::new(swc);
 
+    let challenge_policy = ChallengePolicy::default();
+
     let event_stream = api.stream_incoming_events().await.unwrap();
 
     pin_mut!(event_stream);
@@ -29,8 +33,10 @@ async fn main() {
 
         match value {
             BotEvent::Challenge { challenge, compat: _compat } => {
-                let id = challenge.id;
-                api.post_accept_challenge(&id).await.unwrap_or_default();
+                match challenge_policy.decline_reason(&challenge) {
+                    None => api.post_accept_challenge(&challenge.id).await.unwrap_or_default(),
+                    Some(reason) => api.post_decline_challenge(&challenge.id, &reason).await.unwrap_or_default(),
+                }
             }
             BotEvent::GameStart { game } => {
                 let thread = GameThread::new("kingsgambot", &game.game_id, BotApi::new(SurfWebClient::new(&token, create_client())));
@@ -1,3 +1,3 @@
 pub mod pgn;
 pub mod reader;
-
+pub mod writer;
@@ -1,5 +1,10 @@
 use std::collections::HashMap;
+use std::fs::File;
+use std::io;
 use std::io::Read;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
 
 use crate::reader::PgnRawParserError::ReadingFromClosedRead;
 
@@ -7,11 +12,57 @@ use crate::reader::PgnRawParserError::ReadingFromClosedRead;
 pub struct PgnRawAnnotatedMove {
     pub mv: String,
     pub annotation: Option<String>,
+    /// Remaining time of the moving side after the move, from a `[%clk 0:03:00]` annotation
+    pub clock: Option<Duration>,
+    /// Engine evaluation after the move, from a `[%eval 0.17]` or `[%eval #-3]` annotation
+    pub eval: Option<PgnEval>,
+}
+
+/// Evaluation from the perspective of white
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum PgnEval {
+    Centipawns(i32),
+    /// Mate in the given number of moves, negative if black mates
+    Mate(i32),
 }
 
 impl PgnRawAnnotatedMove {
-    pub const fn new(mv: String, annotation: Option<String>) -> Self {
-        Self { mv, annotation }
+    pub fn new(mv: String, annotation: Option<String>) -> Self {
+        let clock = annotation.as_deref().and_then(|annotation| command(annotation, "clk")).and_then(parse_clock);
+        let eval = annotation.as_deref().and_then(|annotation| command(annotation, "eval")).and_then(parse_eval);
+
+        Self { mv, annotation, clock, eval }
+    }
+}
+
+/// The parameter of the embedded command `[%name parameter]` in `annotation`
+fn command<'a>(annotation: &'a str, name: &str) -> Option<&'a str> {
+    let start = annotation.find(&format!("[%{} ", name))? + name.len() + 3;
+    let length = annotation[start..].find(']')?;
+
+    Some(annotation[start..start + length].trim())
+}
+
+/// Parse a clock of the form `h:mm:ss` with optional fractional seconds
+fn parse_clock(clock: &str) -> Option<Duration> {
+    let mut seconds = 0.0;
+
+    for part in clock.split(':') {
+        seconds = seconds * 60.0 + f64::from_str(part).ok()?;
+    }
+
+    Duration::try_from_secs_f64(seconds).ok()
+}
+
+/// Parse an evaluation in pawns like `-1.23`, or a mate like `#-3`
+fn parse_eval(eval: &str) -> Option<PgnEval> {
+    // Lichess appends the search depth to some evaluations, e.g. `0.17,20`
+    let eval = eval.split(',').next()?;
+
+    if let Some(mate) = eval.strip_prefix('#') {
+        i32::from_str(mate).ok().map(PgnEval::Mate)
+    } else {
+        f64::from_str(eval).ok().filter(|pawns| pawns.is_finite()).map(|pawns| PgnEval::Centipawns((pawns * 100.0).round() as i32))
     }
 }
 
@@ -27,6 +78,7 @@ impl PgnRaw {
     }
 }
 
+/// Parses games from any [`Read`], e.g. a plain [`File`] or a decompressing decoder wrapping one
 pub struct PgnRawParser<R: Read> {
     reader: R,
     chunk_size: usize,
@@ -43,6 +95,32 @@ pub enum PgnRawParserError {
     IllegalSymbol { position: u64, actual: u8 },
 }
 
+/// Determines how [`PgnRawGames`] handles games that failed to parse
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ErrorPolicy {
+    /// Skip the erroring game and continue with the next one
+    Skip,
+    /// Stop iterating at the first erroring game
+    Stop,
+}
+
+impl PgnRawParser<Box<dyn Read>> {
+    /// Open the file at `path`, decompressing it according to its extension: `.zst` for zstd, `.gz` for gzip and
+    /// plain text otherwise
+    pub fn from_path<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref();
+        let file = File::open(path)?;
+
+        let reader: Box<dyn Read> = match path.extension().and_then(|extension| extension.to_str()) {
+            Some("zst") => Box::new(zstd::Decoder::new(file)?),
+            Some("gz") => Box::new(flate2::read::GzDecoder::new(file)),
+            _ => Box::new(file),
+        };
+
+        Ok(Self::new(reader))
+    }
+}
+
 impl<R: Read> PgnRawParser<R> {
     pub fn new(reader: R) -> Self {
         Self::with_chunk_size(reader, 8192)
@@ -52,6 +130,11 @@ impl<R: Read> PgnRawParser<R> {
         Self { reader, chunk_size, eof_reached: false, current_buffer: vec![0; chunk_size], current_byte: chunk_size, position: 0 }
     }
 
+    /// Iterate over the successfully parsed games, handling errors according to `policy`.
+    pub const fn games(self, policy: ErrorPolicy) -> PgnRawGames<R> {
+        PgnRawGames { parser: self, policy, stopped: false }
+    }
+
     fn ensure_buffer(&mut self) -> bool {
         if self.current_byte >= self.current_buffer.len() {
             self.current_byte = 0;
@@ -128,14 +211,6 @@ impl<R: Read> PgnRawParser<R> {
         Ok(())
     }
 
-    fn skip_spaces(&mut self) -> Result<(), PgnRawParserError> {
-        while self.peek_byte()? == b' ' {
-            self.skip_byte()?;
-        }
-
-        Ok(())
-    }
-
     fn skip_to_next_line(&mut self) -> Result<(), PgnRawParserError> {
         while self.pop_byte()? != b'\n' {};
 
@@ -155,6 +230,20 @@ impl<R: Read> PgnRawParser<R> {
         Ok(result)
     }
 
+    /// Read a movetext token, which ends at a space or at the end of a wrapped line
+    fn read_token(&mut self) -> Result<String, PgnRawParserError> {
+        let mut result = String::new();
+        let mut cur_byte = self.peek_byte()?;
+
+        while cur_byte != b' ' && cur_byte != b'\n' {
+            result.push(cur_byte as char);
+            self.skip_byte()?;
+            cur_byte = self.peek_byte()?;
+        }
+
+        Ok(result)
+    }
+
     fn read_tag_pairs(&mut self) -> Result<HashMap<String, String>, PgnRawParserError> {
         let mut result = HashMap::new();
 
@@ -206,26 +295,27 @@ impl<R: Read> PgnRawParser<R> {
     fn read_move(&mut self) -> Result<Option<PgnRawAnnotatedMove>, PgnRawParserError> {
         self.skip_blank_lines_and_spaces()?;
 
-        let token = self.read_until(b' ')?;
+        let token = self.read_token()?;
 
         let mut chars = token.chars();
-        if chars.next() == Some('*') {
+        let first = chars.next();
+        if first == Some('*') {
             return Ok(None);
         }
 
-        if let Some('-' | '/') = chars.next() {
-            self.skip_to_next_line()?;
+        // Game termination markers, castling like `O-O` has the same second character
+        if first.is_some_and(|c| c.is_ascii_digit()) && matches!(chars.next(), Some('-' | '/')) {
             return Ok(None);
         }
 
         let mv = if token.contains('.') {
-            self.skip_spaces()?;
-            self.read_until(b' ')?
+            self.skip_blank_lines_and_spaces()?;
+            self.read_token()?
         } else {
             token
         };
 
-        self.skip_spaces()?;
+        self.skip_blank_lines_and_spaces()?;
 
         let byte = self.peek_byte()?;
 
@@ -276,3 +366,139 @@ impl<R: Read> Iterator for PgnRawParser<R> {
         }
     }
 }
+
+pub struct PgnRawGames<R: Read> {
+    parser: PgnRawParser<R>,
+    policy: ErrorPolicy,
+    stopped: bool,
+}
+
+impl<R: Read> Iterator for PgnRawGames<R> {
+    type Item = PgnRaw;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.stopped {
+            match self.parser.next() {
+                Some(Ok(pgn)) => { return Some(pgn); }
+                Some(Err(_)) if self.policy == ErrorPolicy::Skip => {
+                    // Make progress so a malformed line can't produce the same error indefinitely
+                    self.stopped = self.parser.skip_to_next_line().is_err();
+                }
+                _ => { self.stopped = true; }
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::{File, remove_file, write};
+    use std::io::Write;
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    use flate2::Compression;
+    use flate2::write::GzEncoder;
+
+    use crate::reader::{ErrorPolicy, PgnEval, PgnRawParser};
+
+    const PGNS: &str = concat!(
+        "[White \"a\"]\n[Result \"1-0\"]\n\n1. e4 e5 2. Qh5 Nc6 3. Bc4 Nf6 4. Qxf7# 1-0 \n\n",
+        "[Event Broken]\n\n1. d4 d5 0-1 \n\n",
+        "[White \"b\"]\n[Result \"0-1\"]\n\n1. f3 e5 2. g4 Qh4# 0-1 \n\n",
+        "[White \"c\"]\n[Result \"1-0\"]\n\n1. e4 e5 1-0 \n\n",
+    );
+
+    #[test]
+    fn test_games_skip() {
+        let actual = PgnRawParser::new(PGNS.as_bytes())
+            .games(ErrorPolicy::Skip)
+            .filter(|pgn| pgn.tag_pairs.get("Result").map(String::as_str) == Some("1-0"))
+            .map(|pgn| (pgn.tag_pairs["White"].clone(), pgn.moves.len()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(actual, vec![("a".to_string(), 7), ("c".to_string(), 2)]);
+    }
+
+    #[test]
+    fn test_games_stop() {
+        let actual = PgnRawParser::new(PGNS.as_bytes())
+            .games(ErrorPolicy::Stop)
+            .map(|pgn| pgn.tag_pairs["White"].clone())
+            .collect::<Vec<_>>();
+
+        assert_eq!(actual, vec!["a".to_string()]);
+    }
+
+    #[test]
+    fn test_games_take() {
+        let actual = PgnRawParser::new(PGNS.as_bytes())
+            .games(ErrorPolicy::Skip)
+            .take(2)
+            .count();
+
+        assert_eq!(actual, 2);
+    }
+
+    #[test]
+    fn test_clock_and_eval_annotations() {
+        let pgn = concat!(
+            "[White \"a\"]\n\n",
+            "1. e4 { [%eval 0.17] [%clk 0:03:00] } 1... e5 { [%eval #-3] [%clk 0:02:58.5] } 2. Nf3 { a comment } 2... Nc6 1-0 \n\n",
+        );
+
+        let actual = PgnRawParser::new(pgn.as_bytes()).next().unwrap().unwrap();
+        let actual = actual.moves.iter().map(|mv| (mv.mv.as_str(), mv.clock, mv.eval)).collect::<Vec<_>>();
+
+        assert_eq!(actual, vec![
+            ("e4", Some(Duration::from_secs(180)), Some(PgnEval::Centipawns(17))),
+            ("e5", Some(Duration::from_millis(178_500)), Some(PgnEval::Mate(-3))),
+            ("Nf3", None, None),
+            ("Nc6", None, None),
+        ]);
+    }
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("inkayaku_pgn_{}_{}", std::process::id(), name))
+    }
+
+    fn white_players(parser: PgnRawParser<impl std::io::Read>) -> Vec<String> {
+        parser.games(ErrorPolicy::Skip).map(|pgn| pgn.tag_pairs["White"].clone()).collect()
+    }
+
+    #[test]
+    fn test_plain_file() {
+        let path = temp_path("plain.pgn");
+        write(&path, PGNS).unwrap();
+
+        let actual = white_players(PgnRawParser::new(File::open(&path).unwrap()));
+        remove_file(&path).unwrap();
+
+        assert_eq!(actual, vec!["a".to_string(), "b".to_string(), "c".to_string()]);
+    }
+
+    #[test]
+    fn test_from_path() {
+        let plain = temp_path("from_path.pgn");
+        write(&plain, PGNS).unwrap();
+
+        let zstd = temp_path("from_path.pgn.zst");
+        write(&zstd, zstd::encode_all(PGNS.as_bytes(), 0).unwrap()).unwrap();
+
+        let gzip = temp_path("from_path.pgn.gz");
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(PGNS.as_bytes()).unwrap();
+        write(&gzip, encoder.finish().unwrap()).unwrap();
+
+        for path in [plain, zstd, gzip] {
+            let actual = white_players(PgnRawParser::from_path(&path).unwrap());
+            remove_file(&path).unwrap();
+
+            assert_eq!(actual, vec!["a".to_string(), "b".to_string(), "c".to_string()], "{}", path.display());
+        }
+
+        assert!(PgnRawParser::from_path(temp_path("missing.pgn")).is_err());
+    }
+}
@@ -0,0 +1,222 @@
+use std::collections::HashMap;
+
+use inkayaku_board::{Bitboard, Move, MoveFromUciError};
+use inkayaku_board::constants::WHITE;
+use inkayaku_core::fen::Fen;
+
+/// Tags every PGN game has to contain, in the order they have to be exported in
+const SEVEN_TAG_ROSTER: [(&str, &str); 7] = [
+    ("Event", "?"),
+    ("Site", "?"),
+    ("Date", "????.??.??"),
+    ("Round", "?"),
+    ("White", "?"),
+    ("Black", "?"),
+    ("Result", "*"),
+];
+
+const MAX_LINE_LENGTH: usize = 80;
+
+/// Writes a game played from `fen` as PGN. Missing tags of the seven tag roster are filled with their unknown value and
+/// the `Result` tag determines the game termination marker.
+pub struct PgnWriter {
+    fen: Fen,
+    moves: Vec<Move>,
+    tag_pairs: HashMap<String, String>,
+}
+
+impl PgnWriter {
+    pub const fn new(fen: Fen, moves: Vec<Move>, tag_pairs: HashMap<String, String>) -> Self {
+        Self { fen, moves, tag_pairs }
+    }
+
+    pub fn write(&self) -> Result<String, MoveFromUciError> {
+        let mut result = self.tag_pairs_section();
+        result.push('\n');
+
+        let mut tokens = self.movetext_tokens()?;
+        tokens.push(self.result().to_string());
+
+        result.push_str(&Self::wrap(&tokens));
+        result.push('\n');
+
+        Ok(result)
+    }
+
+    fn result(&self) -> &str {
+        self.tag_pairs.get("Result").map_or("*", String::as_str)
+    }
+
+    fn tag_pairs_section(&self) -> String {
+        let mut tag_pairs = SEVEN_TAG_ROSTER
+            .iter()
+            .map(|&(name, default)| (name.to_string(), self.tag_pairs.get(name).map_or(default, String::as_str).to_string()))
+            .collect::<Vec<_>>();
+
+        if !self.is_standard_start_position() {
+            for (name, value) in [("SetUp", "1"), ("FEN", self.fen.fen.as_str())] {
+                if !self.tag_pairs.contains_key(name) {
+                    tag_pairs.push((name.to_string(), value.to_string()));
+                }
+            }
+        }
+
+        let mut remaining = self.tag_pairs
+            .iter()
+            .filter(|(name, _)| !SEVEN_TAG_ROSTER.iter().any(|(roster_name, _)| roster_name == name))
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect::<Vec<_>>();
+        remaining.sort();
+        tag_pairs.extend(remaining);
+
+        tag_pairs
+            .iter()
+            .map(|(name, value)| format!("[{} \"{}\"]\n", name, value.replace('\\', "\\\\").replace('"', "\\\"")))
+            .collect()
+    }
+
+    fn is_standard_start_position(&self) -> bool {
+        let start = Fen::default();
+
+        self.fen.get_piece_placement() == start.get_piece_placement()
+            && self.fen.get_active_color() == start.get_active_color()
+            && self.fen.get_castling_availability() == start.get_castling_availability()
+            && self.fen.get_en_passant_target_square() == start.get_en_passant_target_square()
+    }
+
+    /// Move numbers are part of the white move's token, so they are never separated from their move by line wrapping
+    fn movetext_tokens(&self) -> Result<Vec<String>, MoveFromUciError> {
+        let mut board = Bitboard::from(&self.fen);
+        let mut result = Vec::with_capacity(self.moves.len());
+
+        for (index, &mv) in self.moves.iter().enumerate() {
+            let san = mv.to_pgn_string(&mut board)?;
+
+            let token = if board.turn == WHITE {
+                format!("{}. {}", board.fullmove_clock, san)
+            } else if index == 0 {
+                format!("{}... {}", board.fullmove_clock, san)
+            } else {
+                san
+            };
+
+            result.push(token);
+            board.make(mv);
+        }
+
+        Ok(result)
+    }
+
+    fn wrap(tokens: &[String]) -> String {
+        let mut result = String::new();
+        let mut line_length = 0;
+
+        for token in tokens {
+            if line_length == 0 {
+                line_length = token.len();
+            } else if line_length + 1 + token.len() > MAX_LINE_LENGTH {
+                result.push('\n');
+                line_length = token.len();
+            } else {
+                result.push(' ');
+                line_length += 1 + token.len();
+            }
+
+            result.push_str(token);
+        }
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::str::FromStr;
+
+    use inkayaku_board::{Bitboard, Move};
+    use inkayaku_core::fen::Fen;
+
+    use crate::reader::PgnRawParser;
+    use crate::writer::PgnWriter;
+
+    fn moves(fen: &Fen, uci: &str) -> Vec<Move> {
+        let mut board = Bitboard::from(fen);
+
+        uci.split_whitespace()
+            .map(|uci| {
+                let mv = board.find_uci(uci).unwrap();
+                board.make(mv);
+                mv
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_write() {
+        let fen = Fen::default();
+        let tag_pairs = HashMap::from([
+            ("White".to_string(), "a".to_string()),
+            ("Result".to_string(), "1-0".to_string()),
+            ("Annotator".to_string(), "b".to_string()),
+        ]);
+
+        let actual = PgnWriter::new(fen.clone(), moves(&fen, "e2e4 e7e5 d1h5 b8c6 f1c4 g8f6 h5f7"), tag_pairs).write().unwrap();
+
+        let expected = concat!(
+            "[Event \"?\"]\n[Site \"?\"]\n[Date \"????.??.??\"]\n[Round \"?\"]\n[White \"a\"]\n[Black \"?\"]\n[Result \"1-0\"]\n[Annotator \"b\"]\n",
+            "\n",
+            "1. e4 e5 2. Qh5 Nc6 3. Bc4 Nf6 4. Qxf7# 1-0\n",
+        );
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_write_black_to_move() {
+        let fen = Fen::from_str("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1").unwrap();
+
+        let actual = PgnWriter::new(fen.clone(), moves(&fen, "e7e5 g1f3"), HashMap::new()).write().unwrap();
+
+        assert!(actual.contains("[SetUp \"1\"]\n[FEN \"rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1\"]\n"));
+        assert!(actual.ends_with("\n1... e5 2. Nf3 *\n"));
+    }
+
+    #[test]
+    fn test_write_wraps_lines() {
+        let fen = Fen::default();
+        let uci = "g1f3 g8f6 f3g1 f6g8 ".repeat(10);
+
+        let actual = PgnWriter::new(fen.clone(), moves(&fen, &uci), HashMap::new()).write().unwrap();
+        let movetext = actual.split("\n\n").nth(1).unwrap();
+
+        assert!(movetext.lines().count() > 1);
+        assert!(movetext.lines().all(|line| line.len() <= 80));
+        assert!(movetext.lines().all(|line| !line.ends_with('.')));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let fen = Fen::default();
+        let uci = "e2e4 c7c5 g1f3 d7d6 d2d4 c5d4 f3d4 g8f6 b1c3 a7a6 c1e3 e7e5 d4b3 c8e6 f2f3 f8e7 d1d2 e8g8 e1c1 b8d7 g2g4 b7b5 g4g5 b5b4 c3e2 f6e8 f3f4 a6a5 f4f5 a5a4 b3d4 e5d4 e2d4 b4b3 c1b1 b3c2 d4c2 e6b3 a2b3 a4b3 c2a3 d7e5";
+        let moves = moves(&fen, uci);
+        let tag_pairs = HashMap::from([("Result".to_string(), "0-1".to_string())]);
+
+        let written = PgnWriter::new(fen.clone(), moves.clone(), tag_pairs).write().unwrap();
+        let parsed = PgnRawParser::new(written.as_bytes()).next().unwrap().unwrap();
+
+        assert_eq!(parsed.tag_pairs["Result"], "0-1");
+
+        let mut board = Bitboard::from(&fen);
+        let actual = parsed.moves
+            .iter()
+            .map(|raw| {
+                let mv = board.pgn_to_bb(&raw.mv).unwrap();
+                board.make(mv);
+                mv
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(actual, moves);
+    }
+}
@@ -1,5 +1,4 @@
-use std::cmp::{max, min};
-use std::fs::{File, write};
+use std::fs::write;
 use std::ops::Deref;
 use std::str::FromStr;
 use std::thread;
@@ -9,7 +8,7 @@ use inkayaku_board::{Bitboard, PlayerState};
 use inkayaku_board::constants::{BLACK, ColorBits, DRAW, KING, PAWN, PieceBits, WHITE};
 use inkayaku_board::mask_and_shift_from_lowest_one_bit;
 use inkayaku_core::constants::Color;
-use inkayaku_pgn::reader::{PgnRaw, PgnRawParser};
+use inkayaku_pgn::reader::{ErrorPolicy, PgnRaw, PgnRawParser};
 
 use crate::PgnExclusion::{BlackEloNotAvailable, BlackEloNotParsable, BlackEloTooLow, TimeControlNotAvailable, TimeControlNotParsable, TimeNotParsable, TimeTooLow, WhiteEloNotAvailable, WhiteEloNotParsable, WhiteEloTooLow};
 
@@ -19,40 +18,22 @@ fn main() {
 
 
 fn test() {
-    let file = File::open("Y:\\Data\\lichess_db_standard_rated_2023-07.pgn.zst").unwrap();
-
     let start = Instant::now();
 
-    let mut parser = PgnRawParser::new(zstd::Decoder::new(file).unwrap());
-
-    let mut t = 0;
-    let mut i = 0;
+    let parser = PgnRawParser::from_path("Y:\\Data\\lichess_db_standard_rated_2023-07.pgn.zst").unwrap();
 
     let mut buckets = TaperPieceCountBucket::default();
 
-    loop {
-        match parser.next() {
-            Some(Ok(pgn)) => {
-                t += 1;
-                if filter_pgn(&pgn).is_err() {
-                    continue;
-                }
-
-                calc(pgn, &mut buckets);
+    let games = parser
+        .games(ErrorPolicy::Stop)
+        .enumerate()
+        .filter(|(_, pgn)| filter_pgn(pgn).is_ok())
+        .take(2_700_000);
 
-                i += 1;
+    for (i, (t, pgn)) in games.enumerate() {
+        calc(pgn, &mut buckets);
 
-                if i >= 2_700_000 {
-                    break;
-                }
-                println!("{}/{}", i, t);
-            }
-            Some(Err(err)) => {
-                println!("{:?}", err);
-                break;
-            }
-            _ => { break; }
-        }
+        println!("{}/{}", i + 1, t + 1);
     }
 
 
@@ -151,15 +132,19 @@ fn calc(pgn: PgnRaw, buckets: &mut TaperPieceCountBucket) {
     };
 
     for x in &pgn.moves {
-        if let Ok(mv) = board.pgn_to_bb(&x.mv) {
-            board.make(mv);
-            let taper_factor = taper_factor(&board);
+        match board.pgn_to_bb(&x.mv) {
+            Ok(mv) => {
+                board.make(mv);
+                let taper_factor = board.phase();
 
 
-            buckets.add(WHITE, &board.white, taper_factor, result);
-            buckets.add(BLACK, &board.black, taper_factor, result);
-        } else {
-            panic!("{:?}\n{:?}", x, pgn);
+                buckets.add(WHITE, &board.white, taper_factor, result);
+                buckets.add(BLACK, &board.black, taper_factor, result);
+            }
+            Err(error) => {
+                println!("Failed to parse move {:?} of game {:?}: {:?}", x, pgn.tag_pairs.get("Site"), error);
+                return;
+            }
         }
     }
 }
@@ -195,22 +180,3 @@ impl TaperPieceCountBucket {
     }
 }
 
-/// Returns the taper factor in `0..=24`, 0 being early game and 24 being end game
-fn taper_factor(board: &Bitboard) -> u8 {
-    const PAWN_PHASE: i32 = 0;
-    const KNIGHT_PHASE: i32 = 1;
-    const BISHOP_PHASE: i32 = 1;
-    const ROOK_PHASE: i32 = 2;
-    const QUEEN_PHASE: i32 = 4;
-    const TOTAL_PHASE: i32 = PAWN_PHASE * 16 + KNIGHT_PHASE * 4 + BISHOP_PHASE * 4 + ROOK_PHASE * 4 + QUEEN_PHASE * 2;
-
-    let phase = TOTAL_PHASE
-        - (board.white.pawns().count_ones() + board.black.pawns().count_ones()) as i32 * PAWN_PHASE
-        - (board.white.knights().count_ones() + board.black.knights().count_ones()) as i32 * KNIGHT_PHASE
-        - (board.white.bishops().count_ones() + board.black.bishops().count_ones()) as i32 * BISHOP_PHASE
-        - (board.white.rooks().count_ones() + board.black.rooks().count_ones()) as i32 * ROOK_PHASE
-        - (board.white.queens().count_ones() + board.black.queens().count_ones()) as i32 * QUEEN_PHASE
-        ;
-
-    min(max(phase, 0), TOTAL_PHASE) as u8
-}
@@ -12,6 +12,8 @@ pub mod console;
 pub mod parser;
 pub mod command;
 
+pub use command::CommandUciTx;
+
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub enum ParseUciMoveError {
     InvalidFormat(String)
@@ -79,7 +81,7 @@ impl Display for UciMove {
     }
 }
 
-#[derive(Debug, Eq, PartialEq, Default)]
+#[derive(Debug, Eq, PartialEq, Default, Clone)]
 pub struct Go {
     pub search_moves: Vec<UciMove>,
     pub ponder: bool,
@@ -93,10 +95,12 @@ pub struct Go {
     pub mate: Option<u64>,
     pub move_time: Option<Duration>,
     pub infinite: bool,
+    /// Count the leaf nodes of every legal move at this depth instead of searching
+    pub perft: Option<u64>,
 }
 
 impl Go {
-    pub const EMPTY: Self = Self::new(Vec::new(), false, None, None, None, None, None, None, None, None, None, false);
+    pub const EMPTY: Self = Self::new(Vec::new(), false, None, None, None, None, None, None, None, None, None, false, None);
 
     #[allow(clippy::too_many_arguments)]
     pub const fn new(
@@ -112,8 +116,9 @@ impl Go {
         mate: Option<u64>,
         move_time: Option<Duration>,
         infinite: bool,
+        perft: Option<u64>,
     ) -> Self {
-        Self { search_moves, ponder, white_time, black_time, white_increment, black_increment, moves_to_go, depth, nodes, mate, move_time, infinite }
+        Self { search_moves, ponder, white_time, black_time, white_increment, black_increment, moves_to_go, depth, nodes, mate, move_time, infinite, perft }
     }
 }
 
@@ -275,6 +280,7 @@ pub enum UciCommand {
     Quit,
 }
 
+/// A call of a [`UciTx`] method with its arguments, as recorded by [`CommandUciTx`]
 #[derive(Debug, Eq, PartialEq)]
 pub enum UciTxCommand {
     IdName { name: String },
@@ -291,6 +297,7 @@ pub enum UciTxCommand {
     OptionButton { name: String },
     OptionString { name: String, default: String },
     Debug { message: String },
+    Perft { divide: Vec<(UciMove, u64)>, nodes: u64 },
 }
 
 pub trait UciEngine {
@@ -312,6 +319,7 @@ pub trait UciTx {
     fn option_button(&self, name: &str);
     fn option_string(&self, name: &str, default: &str);
     fn debug(&self, message: &str);
+    fn perft(&self, divide: &[(UciMove, u64)], nodes: u64);
 }
 
 #[cfg(test)]
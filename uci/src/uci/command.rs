@@ -1,8 +1,19 @@
 use std::sync::{Mutex};
-use std::sync::mpsc::Sender;
+use std::sync::mpsc::{channel, Receiver, Sender};
 
 use crate::uci::{Info, ProtectionMessage, UciMove, UciTx, UciTxCommand};
 
+/// A [`UciTx`] recording every call as a [`UciTxCommand`] instead of printing it, to assert on the output of an engine.
+///
+/// ```
+/// use inkayaku_uci::{CommandUciTx, UciTx, UciTxCommand};
+///
+/// let (uci_tx, rx) = CommandUciTx::channel();
+/// uci_tx.id_name("Engine");
+/// uci_tx.uci_ok();
+///
+/// assert_eq!(rx.try_iter().collect::<Vec<_>>(), vec![UciTxCommand::IdName { name: "Engine".to_string() }, UciTxCommand::Ok]);
+/// ```
 pub struct CommandUciTx {
     command_consumer: Mutex<Sender<UciTxCommand>>,
 }
@@ -12,11 +23,17 @@ impl CommandUciTx {
         #[allow(clippy::unwrap_used)]
         self.command_consumer.lock().unwrap().send(command).unwrap();
     }
-    pub fn new(command_consumer: Sender<UciTxCommand>) -> Self {
-        // TODO Spawn channel inside (?)
 
+    /// Send the recorded commands to `command_consumer`, whose receiver has to outlive this
+    pub fn new(command_consumer: Sender<UciTxCommand>) -> Self {
         Self { command_consumer: Mutex::new(command_consumer) }
     }
+
+    /// A new instance and the receiver of its recorded commands
+    pub fn channel() -> (Self, Receiver<UciTxCommand>) {
+        let (tx, rx) = channel();
+        (Self::new(tx), rx)
+    }
 }
 
 impl UciTx for CommandUciTx {
@@ -75,4 +92,8 @@ impl UciTx for CommandUciTx {
     fn debug(&self, message: &str) {
         self.send(UciTxCommand::Debug { message: message.to_string() });
     }
+
+    fn perft(&self, divide: &[(UciMove, u64)], nodes: u64) {
+        self.send(UciTxCommand::Perft { divide: divide.to_vec(), nodes });
+    }
 }
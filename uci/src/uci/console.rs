@@ -3,13 +3,15 @@ use std::io::Error as IoError;
 use std::sync::Mutex;
 
 use crate::uci::{CurrentLine, Info, ProtectionMessage, Score, UciCommand, UciMove, UciTx};
-use crate::uci::console::ConsoleUciRxError::{CommandParseError, SystemError};
+use crate::uci::console::ConsoleUciRxError::{CommandParseError, CommandParseWarning, SystemError};
 use crate::uci::parser::{CommandParser, ParserError};
 
 #[derive(Debug)]
 pub enum ConsoleUciRxError {
     SystemError(IoError),
     CommandParseError(ParserError),
+    /// A token the parser skipped, the command itself follows
+    CommandParseWarning(ParserError),
 }
 
 pub struct ConsoleUciTx<FConsumer: Fn(&str), FDebugConsumer: Fn(&str)> {
@@ -156,6 +158,16 @@ impl<FConsumer: Fn(&str), FDebugConsumer: Fn(&str)> UciTx for ConsoleUciTx<FCons
     fn debug(&self, message: &str) {
         self.tx_debug(message);
     }
+
+    /// Formatted like Stockfish, one `move: nodes` line per move followed by the total
+    fn perft(&self, divide: &[(UciMove, u64)], nodes: u64) {
+        for (mv, count) in divide {
+            self.tx(&format!("{}: {}", mv, count));
+        }
+
+        self.tx("");
+        self.tx(&format!("Nodes searched: {}", nodes));
+    }
 }
 
 pub struct ConsoleUciRx<FRead: Fn() -> Result<String, IoError>, FOnCommand: Fn(Result<UciCommand, ConsoleUciRxError>)> {
@@ -170,8 +182,12 @@ impl<FRead: Fn() -> Result<String, IoError>, FOnCommand: Fn(Result<UciCommand, C
 
     pub fn start(&self) {
         loop {
-            let command = self.read_next_command();
+            let (command, warnings) = self.read_next_command();
             let is_quit = matches!(command, Ok(UciCommand::Quit));
+
+            for warning in warnings {
+                (self.on_command)(Err(CommandParseWarning(warning)));
+            }
             (self.on_command)(command);
 
             if is_quit {
@@ -180,13 +196,50 @@ impl<FRead: Fn() -> Result<String, IoError>, FOnCommand: Fn(Result<UciCommand, C
         }
     }
 
-    fn read_next_command(&self) -> Result<UciCommand, ConsoleUciRxError> {
-        (self.read)().map_err(SystemError).and_then(|raw| {
-            CommandParser::new(&raw).parse().map_err(CommandParseError)
-        })
+    fn read_next_command(&self) -> (Result<UciCommand, ConsoleUciRxError>, Vec<ParserError>) {
+        match (self.read)() {
+            Ok(raw) => {
+                let (result, warnings) = CommandParser::new(&raw).parse_with_warnings();
+                (result.map_err(CommandParseError), warnings)
+            }
+            Err(error) => (Err(SystemError(error)), Vec::new()),
+        }
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::collections::VecDeque;
+
+    use inkayaku_core::constants::Square;
+    use inkayaku_core::fen::Fen;
+
+    use crate::uci::console::ConsoleUciRx;
+    use crate::uci::console::ConsoleUciRxError::{CommandParseError, CommandParseWarning};
+    use crate::uci::parser::ParserError::{InvalidUciMove, UnknownCommand};
+    use crate::uci::ParseUciMoveError::InvalidFormat;
+    use crate::uci::UciCommand::{PositionFrom, Quit};
+    use crate::uci::UciMove;
+
+    #[test]
+    fn test_warnings_precede_command() {
+        let lines = RefCell::new(VecDeque::from(["position startpos moves e2e4 garbage", "something", "quit"]));
+        let results = RefCell::new(Vec::new());
+
+        let read = || Ok(lines.borrow_mut().pop_front().unwrap().to_string());
+        let on_command = |result| results.borrow_mut().push(result);
+
+        ConsoleUciRx::new(read, on_command).start();
+
+        let results = results.take();
+        assert_eq!(results.len(), 4);
+        assert!(matches!(&results[0], Err(CommandParseWarning(InvalidUciMove(InvalidFormat(token)))) if token == "garbage"));
+        assert!(matches!(&results[1], Ok(command) if *command == PositionFrom { fen: Fen::default(), moves: vec![UciMove::new(Square::E2, Square::E4)] }));
+        assert!(matches!(&results[2], Err(CommandParseError(UnknownCommand(command))) if command == "something"));
+        assert!(matches!(&results[3], Ok(Quit)));
+    }
+}
 
 // #[cfg(test)]
 // mod tests {
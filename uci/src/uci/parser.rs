@@ -14,6 +14,7 @@ use crate::uci::UciCommand::{Go as GoCommand, IsReady, PonderHit, PositionFrom,
 
 pub struct CommandParser<'a> {
     queue: RefCell<VecDeque<&'a str>>,
+    warnings: RefCell<Vec<ParserError>>,
 }
 
 #[derive(Debug, Eq, PartialEq)]
@@ -62,11 +63,19 @@ impl<'a> CommandParser<'a> {
     pub fn new(command: &'a str) -> Self {
         let queue = command.trim().split(' ').filter(|&s| !s.is_empty()).collect();
 
-        Self { queue: RefCell::new(queue) }
+        Self { queue: RefCell::new(queue), warnings: RefCell::new(Vec::new()) }
     }
 
     pub fn parse(self) -> Result<UciCommand, ParserError> {
-        self.parse_root(self.next()?)
+        self.parse_with_warnings().0
+    }
+
+    /// Like [`CommandParser::parse`], but also returns the errors that were recovered from by skipping the offending token,
+    /// like unparseable moves in `position` and `go searchmoves`.
+    pub fn parse_with_warnings(self) -> (Result<UciCommand, ParserError>, Vec<ParserError>) {
+        let result = self.next().and_then(|root| self.parse_root(root));
+
+        (result, self.warnings.take())
     }
 
     fn parse_root(&self, root: &str) -> Result<UciCommand, ParserError> {
@@ -120,7 +129,7 @@ impl<'a> CommandParser<'a> {
         Ok(result)
     }
 
-    const GO_TOKENS: [&'static str; 12] = ["searchmoves", "ponder", "wtime", "btime", "winc", "binc", "movestogo", "depth", "nodes", "mate", "movetime", "infinite"];
+    const GO_TOKENS: [&'static str; 13] = ["searchmoves", "ponder", "wtime", "btime", "winc", "binc", "movestogo", "depth", "nodes", "mate", "movetime", "infinite", "perft"];
 
     fn parse_go(&self) -> Result<UciCommand, ParserError> {
         let mut go = Go::EMPTY;
@@ -132,7 +141,7 @@ impl<'a> CommandParser<'a> {
                 Ok(token) if visited_tokens.contains(token) => return Err(DuplicatedToken(token.to_string())),
                 Ok(token) => {
                     match token {
-                        "searchmoves" => go.search_moves = self.parse_moves_until_one_of_or_end(&Self::GO_TOKENS)?,
+                        "searchmoves" => go.search_moves = self.parse_search_moves()?,
                         "ponder" => go.ponder = true,
                         "wtime" => go.white_time = self.parse_duration().map(Some)?,
                         "btime" => go.black_time = self.parse_duration().map(Some)?,
@@ -144,6 +153,7 @@ impl<'a> CommandParser<'a> {
                         "mate" => go.mate = self.parse_u64().map(Some)?,
                         "movetime" => go.move_time = self.parse_duration().map(Some)?,
                         "infinite" => go.infinite = true,
+                        "perft" => go.perft = self.parse_u64().map(Some)?,
                         _ => return Err(UnexpectedToken { actual: token.to_string(), expected: format!("one of {:?}", Self::GO_TOKENS) }),
                     }
                     visited_tokens.insert(token);
@@ -156,6 +166,20 @@ impl<'a> CommandParser<'a> {
         Ok(GoCommand { go })
     }
 
+    /// Unlike the moves of `position`, a list without any valid move is rejected, searching all moves instead would
+    /// silently ignore the restriction
+    fn parse_search_moves(&self) -> Result<Vec<UciMove>, ParserError> {
+        let warnings_before = self.warnings.borrow().len();
+        let moves = self.parse_moves_until_one_of_or_end(&Self::GO_TOKENS)?;
+
+        let mut warnings = self.warnings.borrow_mut();
+        if moves.is_empty() && warnings.len() > warnings_before {
+            return Err(warnings.swap_remove(warnings_before));
+        }
+
+        Ok(moves)
+    }
+
     fn parse_duration(&self) -> Result<Duration, ParserError> { self.next()?.parse().map_err(InvalidInt).map(|d: i64| max(d, 0) as u64).map(Duration::from_millis) }
     fn parse_u64(&self) -> Result<u64, ParserError> { self.next()?.parse().map_err(InvalidInt) }
 
@@ -185,7 +209,10 @@ impl<'a> CommandParser<'a> {
         loop {
             match self.peek() {
                 Ok(token) if stop_tokens.contains(&token) => break,
-                Ok(_) => result.push(UciMove::from_str(self.next()?).map_err(InvalidUciMove)?),
+                Ok(_) => match UciMove::from_str(self.next()?) {
+                    Ok(mv) => result.push(mv),
+                    Err(error) => self.warnings.borrow_mut().push(InvalidUciMove(error)),
+                },
                 Err(UnexpectedEndOfCommand) => break,
                 Err(error) => return Err(error),
             }
@@ -241,7 +268,7 @@ mod tests {
     use crate::uci::{ParseUciMoveError, UciCommand, UciMove};
     use crate::uci::Go;
     use crate::uci::parser::CommandParser;
-    use crate::uci::parser::ParserError::{InvalidFen, InvalidUciMove, UnexpectedEndOfCommand, UnexpectedToken, UnknownCommand};
+    use crate::uci::parser::ParserError::{DuplicatedToken, InvalidFen, InvalidUciMove, UnexpectedEndOfCommand, UnexpectedToken, UnknownCommand};
     use crate::uci::ParseUciMoveError::InvalidFormat;
     use crate::uci::UciCommand::{Go as GoCommand, IsReady, PonderHit, PositionFrom, Quit, Register, RegisterLater, SetDebug, SetOption, SetOptionValue, Stop, Uci, UciNewGame};
 
@@ -293,7 +320,7 @@ mod tests {
         assert_eq!(CommandParser::new("position fen rnbqkbnr/pp1ppppp/8/2p5/4P3/5N2/PPPP1PPP/RNBQKB1R b - - 1 2").parse(), Ok(PositionFrom { fen: Fen::from_str("rnbqkbnr/pp1ppppp/8/2p5/4P3/5N2/PPPP1PPP/RNBQKB1R b - - 1 2").unwrap(), moves: Vec::new() }));
         assert_eq!(CommandParser::new("position fen rnbqkbnr/pp1ppppp/8/2p5/4P3/5N2/PPPP1PPP/RNBQKB1R b - - 1 2 moves").parse(), Ok(PositionFrom { fen: Fen::from_str("rnbqkbnr/pp1ppppp/8/2p5/4P3/5N2/PPPP1PPP/RNBQKB1R b - - 1 2").unwrap(), moves: Vec::new() }));
         assert_eq!(CommandParser::new("position fen rnbqkbnr/pp1ppppp/8/2p5/4P3/5N2/PPPP1PPP/RNBQKB1R b - - 1 2 moves h4h6q a1a2").parse(), Ok(PositionFrom { fen: Fen::from_str("rnbqkbnr/pp1ppppp/8/2p5/4P3/5N2/PPPP1PPP/RNBQKB1R b - - 1 2").unwrap(), moves: vec![UciMove::new_with_promotion(Square::H4, Square::H6, Piece::QUEEN), UciMove::new(Square::A1, Square::A2)] }));
-        assert_eq!(CommandParser::new("position fen rnbqkbnr/pp1ppppp/8/2p5/4P3/5N2/PPPP1PPP/RNBQKB1R b - - 1 2 moves h4h6q a1a9").parse(), Err(InvalidUciMove(InvalidFormat("a1a9".to_string()))));
+        assert_eq!(CommandParser::new("position fen rnbqkbnr/pp1ppppp/8/2p5/4P3/5N2/PPPP1PPP/RNBQKB1R b - - 1 2 moves h4h6q a1a9").parse(), Ok(PositionFrom { fen: Fen::from_str("rnbqkbnr/pp1ppppp/8/2p5/4P3/5N2/PPPP1PPP/RNBQKB1R b - - 1 2").unwrap(), moves: vec![UciMove::new_with_promotion(Square::H4, Square::H6, Piece::QUEEN)] }));
         assert_eq!(CommandParser::new("position fen rnbqkbnr/pp1ppppp/8/44/4P3/5N2/PPPP1PPP/RNBQKB1R b - - 1 2 moves h4h6q a1a9").parse(), Err(InvalidFen(ConcurrentNumbers { rank: "44".to_string() })));
         assert_eq!(CommandParser::new("position startpos").parse(), Ok(PositionFrom { fen: Fen::default(), moves: Vec::new() }));
         assert_eq!(CommandParser::new("position startpos moves").parse(), Ok(PositionFrom { fen: Fen::default(), moves: Vec::new() }));
@@ -301,6 +328,18 @@ mod tests {
         assert_eq!(CommandParser::new("position startpos something").parse(), Err(UnexpectedToken { expected: "moves".to_string(), actual: "something".to_string() }));
     }
 
+    #[test]
+    fn position_recovers_from_invalid_moves() {
+        assert_eq!(
+            CommandParser::new("position startpos moves e2e4 garbage e7e5").parse_with_warnings(),
+            (
+                Ok(PositionFrom { fen: Fen::default(), moves: vec![UciMove::new(Square::E2, Square::E4), UciMove::new(Square::E7, Square::E5)] }),
+                vec![InvalidUciMove(InvalidFormat("garbage".to_string()))],
+            )
+        );
+        assert_eq!(CommandParser::new("position startpos moves e2e4 e7e5").parse_with_warnings().1, vec![]);
+    }
+
     #[test]
     fn go() {
         assert_eq!(CommandParser::new("go").parse(), Ok(GoCommand { go: Go::EMPTY }));
@@ -319,6 +358,7 @@ mod tests {
                            Some(10),
                            Some(Duration::from_millis(999)),
                            true,
+                           None,
                        )
                    })
         );
@@ -337,6 +377,7 @@ mod tests {
                            Some(10),
                            Some(Duration::from_millis(999)),
                            false,
+                           None,
                        )
                    })
         );
@@ -355,14 +396,25 @@ mod tests {
                            Some(10),
                            Some(Duration::from_millis(999)),
                            false,
+                           None,
                        )
                    })
         );
         assert_eq!(CommandParser::new(" go    searchmoves h4h6q a1a2 wtime 60001 winc 1001  btime 60000 binc 1000 movestogo 10 depth 11 nodes 20000 mate 10 movetime 999  something").parse(), Err(UnexpectedToken { actual: "something".to_string(), expected: format!("one of {:?}", CommandParser::GO_TOKENS) }));
         assert_eq!(CommandParser::new("go searchmoves h4h6x").parse(), Err(InvalidUciMove(ParseUciMoveError::InvalidFormat("h4h6x".to_string()))));
+        assert_eq!(CommandParser::new("go searchmoves h4h6x a1a9 depth 5").parse(), Err(InvalidUciMove(ParseUciMoveError::InvalidFormat("h4h6x".to_string()))));
+        assert_eq!(CommandParser::new("go searchmoves h4h6x a1a2 depth 5").parse_with_warnings(), (Ok(GoCommand { go: Go { search_moves: vec![UciMove::new(Square::A1, Square::A2)], depth: Some(5), ..Go::EMPTY } }), vec![InvalidUciMove(ParseUciMoveError::InvalidFormat("h4h6x".to_string()))]));
+        assert_eq!(CommandParser::new("go searchmoves depth 5").parse(), Ok(GoCommand { go: Go { depth: Some(5), ..Go::EMPTY } }));
         assert_eq!(CommandParser::new("go btime -60000").parse(), Ok(GoCommand { go: Go { black_time: Some(Duration::from_millis(0)), ..Go::EMPTY } }));
     }
 
+    #[test]
+    fn go_perft() {
+        assert_eq!(CommandParser::new("go perft 3").parse(), Ok(GoCommand { go: Go { perft: Some(3), ..Go::EMPTY } }));
+        assert_eq!(CommandParser::new("go perft").parse(), Err(UnexpectedEndOfCommand));
+        assert_eq!(CommandParser::new("go perft 3 perft 4").parse(), Err(DuplicatedToken("perft".to_string())));
+    }
+
     #[test]
     fn uci() {
         run_test_for_simple_command("uci", Uci);